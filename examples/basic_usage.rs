@@ -72,8 +72,12 @@ async fn main(_spawner: Spawner) -> ! {
     let cs = Output::new(p.PF6, Level::High, Speed::High); // PF6 connected to /CS / PF6 连接 /CS
 
     // Create driver instance and initialize / 创建设备实例并初始化
-    let mut flash = W25q128jv::new(spi, cs);
-    flash.init().await; // Crucial: Activate CS pin / 关键：激活CS引脚
+    let mut flash = W25q128jv::new_stm32(spi, cs);
+    match flash.init().await { // Crucial: Activate CS pin / 关键：激活CS引脚
+        Ok(steps) if steps.any() => info!("Flash recovered with {:?} / Flash 经恢复步骤 {:?} 后可用", steps, steps),
+        Ok(_) => {}
+        Err(e) => error!("Failed to initialize flash: {:?} / 初始化Flash失败: {:?}", e, e),
+    }
 
     loop {
         info!("\n--- Starting Test Cycle / 开始测试周期 ---");
@@ -95,12 +99,12 @@ async fn main(_spawner: Spawner) -> ! {
         }
 
         // 2. Read Status Register (Verify device status) / 读取状态寄存器（验证设备状态）
-        match flash.read_status_register().await {
+        match flash.read_status_register_1().await {
             Ok(status) => {
-                info!("Status Register 1: 0x{:02X} / 状态寄存器1: 0x{:02X}", status, status);
-                info!("  BUSY: {} / BUSY: {}", if (status & 0x01) != 0 { "Busy" } else { "Idle" }, if (status & 0x01) != 0 { "忙" } else { "空闲" });
-                info!("  WEL: {} / WEL: {}", if (status & 0x02) != 0 { "Enabled" } else { "Disabled" }, if (status & 0x02) != 0 { "已使能" } else { "未使能" });
-                info!("  BP[2:0]: 0x{:01X} / BP[2:0]: 0x{:01X}", (status >> 2) & 0x07, (status >> 2) & 0x07); // Block Protect Bits / 块保护位
+                info!("Status Register 1: {:?} / 状态寄存器1: {:?}", status, status);
+                info!("  BUSY: {} / BUSY: {}", if status.busy { "Busy" } else { "Idle" }, if status.busy { "忙" } else { "空闲" });
+                info!("  WEL: {} / WEL: {}", if status.wel { "Enabled" } else { "Disabled" }, if status.wel { "已使能" } else { "未使能" });
+                info!("  BP[2:0]: 0x{:01X} / BP[2:0]: 0x{:01X}", status.bp, status.bp); // Block Protect Bits / 块保护位
             }
             Err(e) => {
                 error!("Failed to read Status Register: {:?} / 读取状态寄存器失败: {:?}", e, e);
@@ -167,7 +171,7 @@ async fn main(_spawner: Spawner) -> ! {
         }
 
         // 7. Sector Erase (Note address alignment) / 扇区擦除 (注意地址对齐)
-        match flash.erase_sector(0x000000).await { // 0x000000 is 4KB sector-aligned / 0x000000 是 4KB 扇区对齐的
+        match flash.erase_sector_4k(0x000000).await { // 0x000000 is 4KB sector-aligned / 0x000000 是 4KB 扇区对齐的
             Ok(()) => {
                 info!("Sector erase successful (Address 0x000000, Size {} bytes) / 扇区擦除成功 (地址 0x000000, 大小 {} bytes)", SECTOR_SIZE, SECTOR_SIZE);
             }
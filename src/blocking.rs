@@ -0,0 +1,42 @@
+// src/blocking.rs
+
+//! Blocking SPI Adapter / 阻塞式 SPI 适配器
+//!
+//! Lets a blocking `embedded_hal::spi::SpiDevice` drive the async `W25q128jv` driver.
+//! 让阻塞式 `embedded_hal::spi::SpiDevice` 可以驱动异步的 `W25q128jv` 驱动。
+//!
+//! Each transaction runs to completion inside `transaction()`, so the executor is
+//! blocked for the duration of the transfer.
+//! 每个事务在 `transaction()` 内部同步完成，因此传输期间会阻塞执行器。
+
+use embedded_hal::spi::{ErrorType, Operation};
+
+/// Wraps a blocking SPI device so it implements `embedded_hal_async::spi::SpiDevice`.
+/// 包装阻塞式 SPI 设备，使其实现 `embedded_hal_async::spi::SpiDevice`。
+pub struct BlockingSpi<SPI> {
+    spi: SPI,
+}
+
+impl<SPI> BlockingSpi<SPI> {
+    /// Wraps a blocking SPI device.
+    /// 包装一个阻塞式 SPI 设备。
+    pub fn new(spi: SPI) -> Self {
+        Self { spi }
+    }
+
+    /// Releases the wrapped SPI device.
+    /// 释放被包装的 SPI 设备。
+    pub fn release(self) -> SPI {
+        self.spi
+    }
+}
+
+impl<SPI: ErrorType> ErrorType for BlockingSpi<SPI> {
+    type Error = SPI::Error;
+}
+
+impl<SPI: embedded_hal::spi::SpiDevice> embedded_hal_async::spi::SpiDevice for BlockingSpi<SPI> {
+    async fn transaction(&mut self, operations: &mut [Operation<'_, u8>]) -> Result<(), Self::Error> {
+        self.spi.transaction(operations)
+    }
+}
@@ -0,0 +1,137 @@
+// src/geometry.rs
+
+//! Flash Geometry / Flash 几何参数
+//!
+//! `FlashGeometry` describes the detected W25Q part: total size, page/sector/block sizes and
+//! address width. `W25q128jv::init` fills it in from the JEDEC ID; until then the W25Q128
+//! geometry is assumed.
+//! `FlashGeometry` 描述检测到的 W25Q 器件：总容量、页/扇区/块大小以及地址宽度。
+//! `W25q128jv::init` 根据 JEDEC ID 填充它；在此之前默认使用 W25Q128 的几何参数。
+
+use core::ops::Range;
+
+use crate::w25q128jv::{BLOCK_32K_SIZE, BLOCK_64K_SIZE, JEDEC_MAN_ID, PAGE_SIZE, SECTOR_SIZE};
+
+/// Largest supported part (W25Q512, 64MB) / 支持的最大器件（W25Q512，64MB）
+pub(crate) const MAX_FLASH_SIZE: usize = 64 * 1024 * 1024;
+
+/// Size and addressing of a W25Q part / W25Q 器件的容量和寻址方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, defmt::Format)]
+pub struct FlashGeometry {
+    /// JEDEC ID (manufacturer, memory type, capacity) / JEDEC ID（制造商、内存类型、容量）
+    pub jedec_id: (u8, u8, u8),
+    /// Total size in bytes / 总容量（字节）
+    pub capacity: usize,
+    /// Page Program size / 页面编程大小
+    pub page_size: usize,
+    /// Sector Erase size / 扇区擦除大小
+    pub sector_size: usize,
+    /// 32KB Block Erase size / 32KB 块擦除大小
+    pub block_32k_size: usize,
+    /// 64KB Block Erase size / 64KB 块擦除大小
+    pub block_64k_size: usize,
+    /// Address bytes per command: 3, or 4 for parts above 16MB / 每条命令的地址字节数：3，容量超过 16MB 的器件为 4
+    pub address_bytes: u8,
+}
+
+impl Default for FlashGeometry {
+    fn default() -> Self {
+        Self::W25Q128
+    }
+}
+
+impl FlashGeometry {
+    /// W25Q128JV (the default before `init`) / W25Q128JV（`init` 之前的默认值）
+    pub const W25Q128: Self = Self::new((JEDEC_MAN_ID, 0x40, 0x18), 16 * 1024 * 1024);
+
+    const fn new(jedec_id: (u8, u8, u8), capacity: usize) -> Self {
+        Self {
+            jedec_id,
+            capacity,
+            page_size: PAGE_SIZE,
+            sector_size: SECTOR_SIZE,
+            block_32k_size: BLOCK_32K_SIZE,
+            block_64k_size: BLOCK_64K_SIZE,
+            address_bytes: if capacity > 16 * 1024 * 1024 { 4 } else { 3 },
+        }
+    }
+
+    /// Looks up a Winbond W25Q part by JEDEC ID; returns `None` for unknown parts.
+    /// 根据 JEDEC ID 查找 Winbond W25Q 器件；未知器件返回 `None`。
+    ///
+    /// | Memory type / 内存类型 | Parts / 器件 |
+    /// |---|---|
+    /// | `40h` | JV-IQ/JQ, FV (SPI mode / SPI 模式) |
+    /// | `60h` | JW-IQ, FV (QPI mode / QPI 模式) |
+    /// | `70h` | JV-IM/JM |
+    /// | `80h` | JW-IM |
+    ///
+    /// Capacity IDs `15h`-`19h` and `20h` map to W25Q16 (2MB) through W25Q512 (64MB).
+    /// 容量 ID `15h`-`19h` 和 `20h` 对应 W25Q16 (2MB) 到 W25Q512 (64MB)。
+    pub fn from_jedec_id(manufacturer: u8, memory_type: u8, capacity: u8) -> Option<Self> {
+        if manufacturer != JEDEC_MAN_ID || !matches!(memory_type, 0x40 | 0x60 | 0x70 | 0x80) {
+            return None;
+        }
+        let size = match capacity {
+            0x15 => 2 * 1024 * 1024,  // W25Q16
+            0x16 => 4 * 1024 * 1024,  // W25Q32
+            0x17 => 8 * 1024 * 1024,  // W25Q64
+            0x18 => 16 * 1024 * 1024, // W25Q128
+            0x19 => 32 * 1024 * 1024, // W25Q256
+            0x20 => 64 * 1024 * 1024, // W25Q512
+            _ => return None,
+        };
+        Some(Self::new((manufacturer, memory_type, capacity), size))
+    }
+
+    /// Returns `true` if `len` bytes starting at `address` lie inside the array.
+    /// 如果从 `address` 开始的 `len` 字节位于阵列内，返回 `true`。
+    pub fn contains(&self, address: u32, len: usize) -> bool {
+        matches!((address as usize).checked_add(len), Some(end) if end <= self.capacity)
+    }
+
+    /// Number of individual block lock units: 16 sectors in each of the top and bottom
+    /// 64KB blocks, plus every other 64KB block.
+    /// 独立块锁定单元的数量：顶部和底部 64KB 块中各 16 个扇区，加上其余所有 64KB 块。
+    pub fn block_lock_units(&self) -> usize {
+        2 * self.sectors_per_block() + self.blocks() - 2
+    }
+
+    /// Address range of individual block lock unit `index` / 独立块锁定单元 `index` 的地址范围
+    pub fn block_lock_unit(&self, index: usize) -> Range<u32> {
+        let sectors = self.sectors_per_block();
+        let last_block = self.blocks() - 1;
+        let (start, size) = if index < sectors {
+            (index * self.sector_size, self.sector_size)
+        } else if index < sectors + last_block - 1 {
+            ((index + 1 - sectors) * self.block_64k_size, self.block_64k_size)
+        } else {
+            (last_block * self.block_64k_size + (index + 1 - sectors - last_block) * self.sector_size, self.sector_size)
+        };
+        start as u32..(start + size) as u32
+    }
+
+    /// Index of the individual block lock unit holding `address` (per Datasheet Section 7.1.10).
+    /// 包含 `address` 的独立块锁定单元的索引（依据数据手册第 7.1.10 节）。
+    pub(crate) fn block_lock_index(&self, address: u32) -> usize {
+        let address = address as usize;
+        let sectors = self.sectors_per_block();
+        let block = address / self.block_64k_size;
+        let last_block = self.blocks() - 1;
+        if block == 0 {
+            address / self.sector_size
+        } else if block == last_block {
+            sectors + last_block - 1 + (address % self.block_64k_size) / self.sector_size
+        } else {
+            sectors + block - 1
+        }
+    }
+
+    fn sectors_per_block(&self) -> usize {
+        self.block_64k_size / self.sector_size
+    }
+
+    fn blocks(&self) -> usize {
+        self.capacity / self.block_64k_size
+    }
+}
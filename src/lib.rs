@@ -1,24 +1,53 @@
 // src/lib.rs
 
-//! W25Q128JV SPI Flash Driver Library for `embassy-stm32`
-//! 适用于 `embassy-stm32` 的 W25Q128JV SPI Flash 驱动库
+//! W25Q128JV SPI Flash Driver Library for `embedded-hal-async`
+//! 适用于 `embedded-hal-async` 的 W25Q128JV SPI Flash 驱动库
 //!
 //! This library provides basic operations for the Winbond W25Q128JV serial Flash memory.
 //! 该库提供了对 Winbond W25Q128JV 串行 Flash 存储器的基本操作。
 //!
+//! The driver is generic over `embedded_hal_async::spi::SpiDevice`; blocking
+//! `embedded_hal::spi::SpiDevice`s are supported through `W25q128jv::new_blocking`,
+//! and the `stm32` feature (enabled by default) adds `W25q128jv::new_stm32` (DMA) and
+//! `W25q128jv::new_stm32_blocking` for an `embassy_stm32::spi::Spi` plus an `Output` CS pin.
+//! 驱动基于 `embedded_hal_async::spi::SpiDevice` 泛型实现；阻塞式 `embedded_hal::spi::SpiDevice`
+//! 可通过 `W25q128jv::new_blocking` 使用，`stm32` 特性（默认启用）提供了基于
+//! `embassy_stm32::spi::Spi` 和 `Output` CS 引脚的 `W25q128jv::new_stm32`（DMA）和
+//! `W25q128jv::new_stm32_blocking`。
+//!
+//! On a bus shared with other devices, the `shared-bus` feature adds `W25q128jv::new_shared` and
+//! `W25q128jv::new_shared_with_bus_config`, which take a `Mutex`-protected bus from `embassy_embedded_hal::shared_bus`;
+//! the bus is locked for one CS frame at a time.
+//! 在与其他设备共享的总线上，`shared-bus` 特性提供 `W25q128jv::new_shared` 和 `W25q128jv::new_shared_with_bus_config`，
+//! 它们接受来自 `embassy_embedded_hal::shared_bus` 的、由 `Mutex` 保护的总线；每次只在一个 CS 周期内锁定总线。
+//!
+//! `SharedFlash` lets several tasks use one driver through a `&'static` reference, locking it
+//! for each whole operation.
+//! `SharedFlash` 允许多个任务通过 `&'static` 引用使用同一个驱动，并在每个完整操作期间锁定它。
+//!
+//! `W25q128jv::new_ospi` runs the same API on an `embassy_stm32::ospi::Ospi` and adds dual/quad
+//! reads through `W25q128jv::read_multi_io`, QPI mode through `W25q128jv::enter_qpi` on
+//! QPI-capable parts, and execute-in-place through `W25q128jv::enter_memory_mapped`.
+//! `W25q128jv::new_ospi` 在 `embassy_stm32::ospi::Ospi` 上提供相同的 API，并通过
+//! `W25q128jv::read_multi_io` 增加双线/四线读取，在支持 QPI 的器件上通过 `W25q128jv::enter_qpi` 增加 QPI 模式，
+//! 并通过 `W25q128jv::enter_memory_mapped` 支持就地执行。
+//!
 //! # Usage / 使用方法
 //!
-//! 1. Configure the SPI peripheral and CS pin.
+//! 1. Configure the SPI peripheral and CS pin (or an `SpiDevice`).
 //! 2. Create a `W25q128jv` instance.
 //! 3. Call `init()` to initialize the device.
 //! 4. Use the provided API for read, write, erase, etc.
 //!
-//! 1. 配置好 SPI 外设和 CS 引脚。
+//! 1. 配置好 SPI 外设和 CS 引脚（或一个 `SpiDevice`）。
 //! 2. 创建 `W25q128jv` 实例。
 //! 3. 调用 `init()` 初始化设备。
 //! 4. 使用提供的 API 进行读取、写入、擦除等操作。
 //!
-//! ```no_run
+// The example needs `embassy-stm32`; it is only compiled when the `stm32` feature is enabled.
+// 示例依赖 `embassy-stm32`，仅在启用 `stm32` 特性时编译。
+#![cfg_attr(feature = "stm32", doc = "```no_run")]
+#![cfg_attr(not(feature = "stm32"), doc = "```ignore")]
 //! // Example code snippet (see examples/basic_usage.rs for full example)
 //! // 示例代码片段 (见 examples/basic_usage.rs 获取完整示例)
 //! #![no_std]
@@ -29,7 +58,7 @@
 //! # use panic_probe as _;
 //! # use defmt_rtt as _;
 //! #
-//! use embassy_stm32_w25q128jv::{W25q128jv, JEDEC_MAN_ID, JEDEC_MEM_TYPE, JEDEC_CAPACITY};
+//! use w25q128::{W25q128jv, JEDEC_MAN_ID, JEDEC_MEM_TYPE, JEDEC_CAPACITY};
 //!
 //! #[embassy_executor::main]
 //! async fn main(_spawner: Spawner) {
@@ -44,16 +73,16 @@
 //!     };
 //!     let spi = Spi::new(
 //!         p.SPI5, p.PF7, p.PF9, p.PF8,
-//!         // Provide correct DMA channels if using DMA, otherwise pass None or omit
-//!         // 如果使用 DMA，请提供正确的通道，否则可以传递 None 或省略
-//!         // p.DMA2_CH7, p.DMA2_CH2,
+//!         // DMA channels; use `Spi::new_blocking` + `W25q128jv::new_stm32_blocking` without DMA
+//!         // DMA 通道；不使用 DMA 时请用 `Spi::new_blocking` + `W25q128jv::new_stm32_blocking`
+//!         p.DMA2_CH7, p.DMA2_CH2,
 //!         spi_config,
 //!     );
 //!     let cs = Output::new(p.PF6, Level::High, Speed::High);
 //!
 //!     // --- Create driver instance and initialize / 创建驱动实例并初始化 ---
-//!     let mut flash = W25q128jv::new(spi, cs);
-//!     flash.init().await;
+//!     let mut flash = W25q128jv::new_stm32(spi, cs);
+//!     flash.init().await.unwrap();
 //!
 //!     // --- Use the driver / 使用驱动 ---
 //!     match flash.read_jedec_id().await {
@@ -80,26 +109,70 @@
 //!   Ensure `/WP (IO2)` and `/HOLD or /RESET (IO3)` pins are pulled high for standard SPI mode.
 //!   确保 `/WP (IO2)` 和 `/HOLD or /RESET (IO3)` 引脚在标准 SPI 模式下被拉高。
 //! * **Error Handling / 错误处理**:
-//!   The driver returns `embassy_stm32::spi::Error`. The caller must handle these errors.
-//!   驱动返回 `embassy_stm32::spi::Error`。调用者需要处理这些错误。
+//!   The driver returns `w25q128::Error<E>`, which wraps the `SpiDevice` error `E` and adds
+//!   driver-level failures (out-of-range address, misalignment, busy timeout, write-protected
+//!   address, wrong JEDEC ID).
+//!   The caller must handle these errors.
+//!   驱动返回 `w25q128::Error<E>`，它包装了 `SpiDevice` 的错误 `E`，并增加了驱动层面的错误
+//!   （地址越界、未对齐、忙超时、地址受写保护、JEDEC ID 错误）。调用者需要处理这些错误。
 //! * **Asynchronous / 异步**:
-//!   All operations are asynchronous (`async`).
-//!   所有操作都是异步的 (`async`)。
+//!   All operations are asynchronous (`async`). With an async `SpiDevice` (e.g. `new_stm32` with DMA),
+//!   data transfers are awaited and do not stall the executor.
+//!   所有操作都是异步的 (`async`)。使用异步 `SpiDevice`（例如带 DMA 的 `new_stm32`）时，
+//!   数据传输以 await 方式进行，不会阻塞执行器。
 //!
 
-#![no_std] // Declare as a no_std library / 声明为 no_std 库
+#![no_std] // Declare as a no_std library (`std` feature only adds the simulator) / 声明为 no_std 库（`std` 特性仅增加模拟器）
 
 // Declare modules / 声明模块
+mod blocking;
+mod geometry;
+mod memory_mapped;
+mod multi_io;
+mod nor_flash;
+#[cfg(feature = "stm32")]
+mod ospi;
+mod protection;
+mod qpi;
+mod security;
+mod sfdp;
+#[cfg(feature = "shared-bus")]
+mod shared_bus;
+mod shared_flash;
+#[cfg(feature = "std")]
+mod simulated;
+mod status;
+#[cfg(feature = "stm32")]
+mod stm32;
 mod w25q128jv;
 
 // Re-export public items for easy access / 重新导出公共项，方便库使用者直接访问
 pub use w25q128jv::{
     W25q128jv, // Driver struct / 驱动结构体
-    JEDEC_MAN_ID, JEDEC_MEM_TYPE, JEDEC_CAPACITY, // Constants / 常量
-    SECTOR_SIZE, // Constants / 常量
+    Error, // Error type / 错误类型
+    Config, // Driver configuration / 驱动配置
+    AddressMode, // 3- or 4-byte address mode / 3 或 4 字节地址模式
+    PowerState, // Active or deep power-down / 工作或深度掉电
+    RecoverySteps, // Recovery steps taken by `init` / `init` 执行的恢复步骤
+    InterfaceMode, // SPI or QPI command interface / SPI 或 QPI 命令接口
+    JEDEC_MAN_ID, JEDEC_MEM_TYPE, JEDEC_CAPACITY, DEVICE_ID, // Constants / 常量
+    PAGE_SIZE, SECTOR_SIZE, BLOCK_32K_SIZE, BLOCK_64K_SIZE, FLASH_SIZE, // Constants / 常量
     // If there are other public functions or types, export them here too
     // 如果有其他公共函数或类型，也需要在这里导出
 };
-// If there's an error type in the future, it should also be exported
-// 如果将来有错误类型，也应该导出
-// pub use w25q128jv::Error;
\ No newline at end of file
+pub use blocking::BlockingSpi; // Blocking SPI adapter / 阻塞式 SPI 适配器
+pub use geometry::FlashGeometry; // Detected part geometry / 检测到的器件几何参数
+pub use memory_mapped::MemoryMappedSpiDevice; // Memory-mapped reads (XIP) / 内存映射读取（XIP）
+pub use multi_io::{MultiIoRead, MultiIoSpiDevice, ReadMode}; // Dual/quad reads / 双线/四线读取
+pub use protection::{BlockLockMap, ProtectionRegion}; // Block protection / 块保护
+pub use qpi::{DummyClocks, QpiSpiDevice, ReadParameters, WrapLength}; // QPI mode / QPI 模式
+pub use security::{OtpLockConfirmation, SECURITY_REGISTERS, SECURITY_REGISTER_SIZE}; // Security registers / 安全寄存器
+pub use shared_flash::{SharedFlash, SharedFlashGuard}; // Flash shared between tasks / 多任务共享的 Flash
+pub use sfdp::{AddressBytes, EraseType, FastReadMode, FastReadModes, ProgramTimings, SfdpParameters}; // SFDP / SFDP 参数
+pub use status::{DriveStrength, StatusRegister1, StatusRegister2, StatusRegister3, StatusWriteMode}; // Status registers / 状态寄存器
+#[cfg(feature = "std")]
+pub use simulated::{SimulatedBusError, SimulatedTiming, SimulatedW25q128}; // Host simulator / 主机模拟器
+#[cfg(feature = "stm32")]
+pub use stm32::Stm32SpiDevice; // STM32 SPI device / STM32 SPI 设备
+#[cfg(feature = "stm32")]
+pub use ospi::{OspiBusError, OspiDevice}; // STM32 OCTOSPI backend / STM32 OCTOSPI 后端
@@ -0,0 +1,49 @@
+// src/memory_mapped.rs
+
+//! Memory-Mapped Reads (XIP) / 内存映射读取（XIP）
+//!
+//! `MemoryMappedSpiDevice` is implemented by backends whose controller can serve CPU reads from a
+//! fixed address window, such as the STM32 OCTOSPI in memory-mapped mode. `W25q128jv::enter_memory_mapped`
+//! configures a continuous Fast Read Quad I/O (`EBh` with M7-0 = 20h, per Datasheet Section 8.2.12):
+//! the chip then expects only an address for every further read, and the controller sends the
+//! instruction only once. Code and constant data can be used in place instead of being copied to RAM.
+//! `MemoryMappedSpiDevice` 由控制器能够从固定地址窗口响应 CPU 读取的后端实现，例如内存映射模式下的 STM32 OCTOSPI。
+//! `W25q128jv::enter_memory_mapped` 配置连续四线 I/O 快速读取（`EBh`，M7-0 = 20h，依据数据手册第 8.2.12 节）：
+//! 此后芯片在每次读取时只需要地址，控制器也只发送一次指令。代码和常量数据可以就地使用，无需复制到 RAM。
+
+use crate::multi_io::{MultiIoRead, MultiIoSpiDevice};
+
+/// `MultiIoSpiDevice` that can map the flash into the address space / 能将 Flash 映射到地址空间的 `MultiIoSpiDevice`
+///
+/// The driver issues no other transfer while mapped, since the chip only accepts addresses in
+/// continuous read mode.
+/// 映射期间驱动不会发起其他任何传输，因为处于连续读取模式的芯片只接受地址。
+pub trait MemoryMappedSpiDevice: MultiIoSpiDevice {
+    /// Serves reads of the window with `read` (its address is ignored), sending the instruction
+    /// only for the first one, and returns the first `len` bytes of the window.
+    /// 使用 `read`（忽略其地址）响应对窗口的读取，只在第一次读取时发送指令，并返回窗口的前 `len` 个字节。
+    ///
+    /// The window must stay readable until `unmap`, even if the device is dropped in between
+    /// (e.g. by leaking the peripheral).
+    /// 窗口必须在 `unmap` 之前保持可读，即使设备在此期间被丢弃（例如泄漏外设）。
+    fn map(&mut self, read: MultiIoRead, len: usize) -> Result<&'static [u8], Self::Error>;
+
+    /// Returns to indirect transfers / 恢复间接传输
+    ///
+    /// # Safety
+    /// No slice returned by `map` may be used afterwards: reading the window is then a bus fault,
+    /// and program/erase would change memory behind a shared reference.
+    /// 之后不得再使用 `map` 返回的任何切片：此时读取窗口会导致总线错误，编程/擦除也会在共享引用背后修改内存。
+    unsafe fn unmap(&mut self) -> Result<(), Self::Error>;
+}
+
+impl<T: MemoryMappedSpiDevice + ?Sized> MemoryMappedSpiDevice for &mut T {
+    fn map(&mut self, read: MultiIoRead, len: usize) -> Result<&'static [u8], Self::Error> {
+        T::map(self, read, len)
+    }
+
+    unsafe fn unmap(&mut self) -> Result<(), Self::Error> {
+        // SAFETY: forwarded from the caller / 由调用者保证
+        unsafe { T::unmap(self) }
+    }
+}
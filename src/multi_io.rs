@@ -0,0 +1,99 @@
+// src/multi_io.rs
+
+//! Dual/Quad Fast Reads / 双线/四线快速读取
+//!
+//! `MultiIoSpiDevice` extends `SpiDevice` with a single read transfer whose address and data
+//! phases may use two or four lines (per Datasheet Section 8.2.8-8.2.13). Backends that drive
+//! IO0-IO3, such as the STM32 OCTOSPI one, implement it; `W25q128jv::read_multi_io` then picks
+//! the opcode, address width and dummy clocks for a `ReadMode` and manages the QE bit.
+//! `MultiIoSpiDevice` 在 `SpiDevice` 的基础上扩展了一种读取传输，其地址和数据阶段可以使用两线或四线
+//! （依据数据手册第 8.2.8-8.2.13 节）。驱动 IO0-IO3 的后端（例如 STM32 OCTOSPI 后端）实现该特征；
+//! `W25q128jv::read_multi_io` 根据 `ReadMode` 选择操作码、地址宽度和虚拟时钟，并管理 QE 位。
+
+use embedded_hal_async::spi::SpiDevice;
+
+/// Fast Read instruction used by `W25q128jv::read_multi_io`, named after the lines used for
+/// the `instruction-address-data` phases.
+/// `W25q128jv::read_multi_io` 使用的快速读取指令，以 `指令-地址-数据` 各阶段使用的线数命名。
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, defmt::Format)]
+pub enum ReadMode {
+    /// 1-1-1 Fast Read (`0Bh`) / 快速读取 (`0Bh`)
+    #[default]
+    Single,
+    /// 1-1-2 Fast Read Dual Output (`3Bh`) / 双线输出快速读取 (`3Bh`)
+    DualOutput,
+    /// 1-1-4 Fast Read Quad Output (`6Bh`), needs QE = 1 / 四线输出快速读取 (`6Bh`)，需要 QE = 1
+    QuadOutput,
+    /// 1-2-2 Fast Read Dual I/O (`BBh`) / 双线 I/O 快速读取 (`BBh`)
+    DualIo,
+    /// 1-4-4 Fast Read Quad I/O (`EBh`), needs QE = 1 / 四线 I/O 快速读取 (`EBh`)，需要 QE = 1
+    QuadIo,
+}
+
+impl ReadMode {
+    /// Opcode with a 3-byte address / 使用 3 字节地址时的操作码
+    pub fn opcode(&self) -> u8 {
+        match self {
+            ReadMode::Single => 0x0B,
+            ReadMode::DualOutput => 0x3B,
+            ReadMode::QuadOutput => 0x6B,
+            ReadMode::DualIo => 0xBB,
+            ReadMode::QuadIo => 0xEB,
+        }
+    }
+
+    /// Returns `true` if IO2/IO3 carry data, which needs the QE bit set.
+    /// IO2/IO3 用于传输数据时返回 `true`，此时需要置位 QE 位。
+    pub fn needs_quad_enable(&self) -> bool {
+        matches!(self, ReadMode::QuadOutput | ReadMode::QuadIo)
+    }
+
+    /// Describes the transfer for `opcode` (the 3- or 4-byte variant of `self.opcode()`).
+    /// 描述 `opcode`（`self.opcode()` 的 3 或 4 字节版本）对应的传输。
+    pub(crate) fn transfer(&self, opcode: u8, address: u32, address_bytes: u8) -> MultiIoRead {
+        // (address lines, data lines, mode bits, dummy clocks) / （地址线数、数据线数、模式位、虚拟时钟数）
+        let (address_lines, data_lines, mode_bits, dummy_clocks) = match self {
+            ReadMode::Single => (1, 1, None, 8),
+            ReadMode::DualOutput => (1, 2, None, 8),
+            ReadMode::QuadOutput => (1, 4, None, 8),
+            // M5-4 != 10 keeps the chip out of continuous read mode / M5-4 != 10 使芯片不进入连续读取模式
+            ReadMode::DualIo => (2, 2, Some(0x00), 0),
+            ReadMode::QuadIo => (4, 4, Some(0x00), 4),
+        };
+        MultiIoRead { opcode, address, address_bytes, address_lines, mode_bits, dummy_clocks, data_lines }
+    }
+}
+
+/// One multi-line read transfer. The opcode is always sent on IO0; the mode bits (M7-0) use
+/// the address lines and are followed by `dummy_clocks` clocks before the data.
+/// 一次多线读取传输。操作码总是通过 IO0 发送；模式位 (M7-0) 使用地址线，之后是 `dummy_clocks` 个时钟，然后是数据。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, defmt::Format)]
+pub struct MultiIoRead {
+    /// Instruction opcode / 指令操作码
+    pub opcode: u8,
+    /// Start address / 起始地址
+    pub address: u32,
+    /// Address bytes: 3 or 4 / 地址字节数：3 或 4
+    pub address_bytes: u8,
+    /// Lines used for the address and mode bits: 1, 2 or 4 / 地址和模式位使用的线数：1、2 或 4
+    pub address_lines: u8,
+    /// Mode bits M7-0, if the instruction has them / 模式位 M7-0（如果指令包含）
+    pub mode_bits: Option<u8>,
+    /// Dummy clocks after the address and mode bits / 地址和模式位之后的虚拟时钟数
+    pub dummy_clocks: u8,
+    /// Lines used for the data: 1, 2 or 4 / 数据使用的线数：1、2 或 4
+    pub data_lines: u8,
+}
+
+/// SPI device that can also run dual/quad read transfers / 还能执行双线/四线读取传输的 SPI 设备
+#[allow(async_fn_in_trait)]
+pub trait MultiIoSpiDevice: SpiDevice {
+    /// Runs `read` in one CS frame, filling `buf` / 在一个 CS 周期内执行 `read`，填充 `buf`
+    async fn read_multi_io(&mut self, read: MultiIoRead, buf: &mut [u8]) -> Result<(), Self::Error>;
+}
+
+impl<T: MultiIoSpiDevice + ?Sized> MultiIoSpiDevice for &mut T {
+    async fn read_multi_io(&mut self, read: MultiIoRead, buf: &mut [u8]) -> Result<(), Self::Error> {
+        T::read_multi_io(self, read, buf).await
+    }
+}
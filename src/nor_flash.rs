@@ -0,0 +1,80 @@
+// src/nor_flash.rs
+
+//! `embedded-storage` NOR Flash Traits / `embedded-storage` NOR Flash 特征实现
+//!
+//! Implements `embedded_storage_async::nor_flash::{ReadNorFlash, NorFlash, MultiwriteNorFlash}`
+//! and the blocking `embedded_storage` equivalents on `W25q128jv`, so the chip can back
+//! `sequential-storage`, `embassy-boot`, `littlefs2`, etc.
+//! 为 `W25q128jv` 实现 `embedded_storage_async::nor_flash::{ReadNorFlash, NorFlash, MultiwriteNorFlash}`
+//! 及对应的阻塞式 `embedded_storage` 特征，使芯片可用于 `sequential-storage`、`embassy-boot`、`littlefs2` 等。
+//!
+//! The blocking traits run the async driver to completion with `embassy_futures::block_on`.
+//! 阻塞式特征通过 `embassy_futures::block_on` 同步执行异步驱动。
+
+use embassy_futures::block_on;
+use embedded_hal_async::spi::SpiDevice;
+use embedded_storage::nor_flash as blocking;
+use embedded_storage_async::nor_flash::{ErrorType, MultiwriteNorFlash, NorFlash, ReadNorFlash};
+
+use crate::w25q128jv::{Error, W25q128jv, SECTOR_SIZE};
+
+impl<SPI: SpiDevice> ErrorType for W25q128jv<SPI> {
+    type Error = Error<SPI::Error>;
+}
+
+impl<SPI: SpiDevice> ReadNorFlash for W25q128jv<SPI> {
+    const READ_SIZE: usize = 1;
+
+    async fn read(&mut self, offset: u32, bytes: &mut [u8]) -> Result<(), Self::Error> {
+        self.fast_read(offset, bytes).await
+    }
+
+    fn capacity(&self) -> usize {
+        self.geometry().capacity
+    }
+}
+
+impl<SPI: SpiDevice> NorFlash for W25q128jv<SPI> {
+    const WRITE_SIZE: usize = 1;
+    const ERASE_SIZE: usize = SECTOR_SIZE;
+
+    async fn erase(&mut self, from: u32, to: u32) -> Result<(), Self::Error> {
+        // `to` is exclusive and must not precede `from` / `to` 为开区间终点，且不能小于 `from`
+        let len = to.checked_sub(from).ok_or(Error::OutOfBounds)?;
+        self.erase_range(from, len as usize).await
+    }
+
+    async fn write(&mut self, offset: u32, bytes: &[u8]) -> Result<(), Self::Error> {
+        self.program(offset, bytes).await
+    }
+}
+
+// NOR programming only clears bits, so rewriting a location is allowed / NOR 编程只会清零位，因此允许重复写入同一位置
+impl<SPI: SpiDevice> MultiwriteNorFlash for W25q128jv<SPI> {}
+
+impl<SPI: SpiDevice> blocking::ReadNorFlash for W25q128jv<SPI> {
+    const READ_SIZE: usize = <Self as ReadNorFlash>::READ_SIZE;
+
+    fn read(&mut self, offset: u32, bytes: &mut [u8]) -> Result<(), Self::Error> {
+        block_on(ReadNorFlash::read(self, offset, bytes))
+    }
+
+    fn capacity(&self) -> usize {
+        ReadNorFlash::capacity(self)
+    }
+}
+
+impl<SPI: SpiDevice> blocking::NorFlash for W25q128jv<SPI> {
+    const WRITE_SIZE: usize = <Self as NorFlash>::WRITE_SIZE;
+    const ERASE_SIZE: usize = <Self as NorFlash>::ERASE_SIZE;
+
+    fn erase(&mut self, from: u32, to: u32) -> Result<(), Self::Error> {
+        block_on(NorFlash::erase(self, from, to))
+    }
+
+    fn write(&mut self, offset: u32, bytes: &[u8]) -> Result<(), Self::Error> {
+        block_on(NorFlash::write(self, offset, bytes))
+    }
+}
+
+impl<SPI: SpiDevice> blocking::MultiwriteNorFlash for W25q128jv<SPI> {}
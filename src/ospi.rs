@@ -0,0 +1,367 @@
+// src/ospi.rs
+
+//! STM32 OCTOSPI Backend / STM32 OCTOSPI 后端
+//!
+//! `OspiDevice` drives the chip through an `embassy_stm32::ospi::Ospi` in indirect mode. Plain
+//! `SpiDevice` transactions are framed as a single-line instruction, address, alternate bytes and
+//! data, which puts exactly the same bits on IO0 as a regular SPI bus, so every driver operation
+//! works unchanged. `MultiIoSpiDevice` adds the dual/quad reads used by `W25q128jv::read_multi_io`,
+//! `QpiSpiDevice` frames every phase on four lines while the chip is in QPI mode, and
+//! `MemoryMappedSpiDevice` maps the chip at the OCTOSPI window (0x9000_0000 for OCTOSPI1 on the
+//! STM32H7) for `W25q128jv::enter_memory_mapped`.
+//! `OspiDevice` 通过间接模式下的 `embassy_stm32::ospi::Ospi` 驱动芯片。普通的 `SpiDevice` 事务被组织为
+//! 单线的指令、地址、交替字节和数据阶段，在 IO0 上产生与普通 SPI 总线完全相同的比特流，因此所有驱动操作均可
+//! 照常使用。`MultiIoSpiDevice` 增加了 `W25q128jv::read_multi_io` 所用的双线/四线读取，
+//! `QpiSpiDevice` 在芯片处于 QPI 模式时以四线组织所有阶段，`MemoryMappedSpiDevice` 为
+//! `W25q128jv::enter_memory_mapped` 将芯片映射到 OCTOSPI 窗口（STM32H7 上 OCTOSPI1 为 0x9000_0000）。
+//!
+//! Create the peripheral with `Ospi::new_quadspi` (or `new_blocking_quadspi`) for the quad modes;
+//! the OCTOSPI NCS pin replaces the GPIO CS pin. For memory-mapped mode, `device_size` in the
+//! `Ospi` configuration must cover the chip, and the peripheral and pins must be owned (`'static`)
+//! rather than borrowed.
+//! 使用四线模式时，请用 `Ospi::new_quadspi`（或 `new_blocking_quadspi`）创建外设；由 OCTOSPI 的 NCS 引脚代替 GPIO CS 引脚。
+//! 使用内存映射模式时，`Ospi` 配置中的 `device_size` 必须覆盖整个芯片，且外设和引脚必须是被拥有的（`'static`）而非借用的。
+
+use core::any::TypeId;
+use core::mem::ManuallyDrop;
+
+use embassy_stm32::mode::{Async, Blocking, Mode};
+use embassy_stm32::peripherals::OCTOSPI2;
+use embassy_stm32::ospi::{AddressSize, DummyCycles, Instance, Ospi, OspiError, OspiWidth, TransferConfig};
+use embassy_time::Timer;
+use embedded_hal::spi::{ErrorKind, ErrorType, Operation};
+
+use crate::memory_mapped::MemoryMappedSpiDevice;
+use crate::multi_io::{MultiIoRead, MultiIoSpiDevice};
+use crate::qpi::QpiSpiDevice;
+use crate::W25q128jv;
+
+/// Command bytes that fit before the data phase: instruction, 4 address and 4 alternate bytes.
+/// 数据阶段之前可容纳的命令字节数：指令、4 个地址字节和 4 个交替字节。
+const MAX_HEADER: usize = 9;
+
+/// OCTOSPI bus error / OCTOSPI 总线错误
+#[derive(Debug, defmt::Format)]
+pub enum OspiBusError {
+    /// Error reported by the peripheral driver / 外设驱动报告的错误
+    Ospi(OspiError),
+    /// Transaction the peripheral cannot frame (e.g. a read before the last operation)
+    /// 外设无法组织的事务（例如读操作不在最后）
+    Unsupported,
+    /// Indirect transfer while the peripheral is in memory-mapped mode / 外设处于内存映射模式时的间接传输
+    MemoryMapped,
+}
+
+impl From<OspiError> for OspiBusError {
+    fn from(e: OspiError) -> Self {
+        OspiBusError::Ospi(e)
+    }
+}
+
+impl embedded_hal::spi::Error for OspiBusError {
+    fn kind(&self) -> ErrorKind {
+        ErrorKind::Other
+    }
+}
+
+/// Data transfers of a blocking or async (DMA) `Ospi` / 阻塞式或异步（DMA）`Ospi` 的数据传输
+trait OspiTransfer {
+    async fn read_data(&mut self, buf: &mut [u8], config: TransferConfig) -> Result<(), OspiError>;
+    async fn write_data(&mut self, buf: &[u8], config: TransferConfig) -> Result<(), OspiError>;
+}
+
+impl<'d, T: Instance> OspiTransfer for Ospi<'d, T, Async> {
+    async fn read_data(&mut self, buf: &mut [u8], config: TransferConfig) -> Result<(), OspiError> {
+        self.read(buf, config).await
+    }
+
+    async fn write_data(&mut self, buf: &[u8], config: TransferConfig) -> Result<(), OspiError> {
+        self.write(buf, config).await
+    }
+}
+
+impl<'d, T: Instance> OspiTransfer for Ospi<'d, T, Blocking> {
+    async fn read_data(&mut self, buf: &mut [u8], config: TransferConfig) -> Result<(), OspiError> {
+        self.blocking_read(buf, config)
+    }
+
+    async fn write_data(&mut self, buf: &[u8], config: TransferConfig) -> Result<(), OspiError> {
+        self.blocking_write(buf, config)
+    }
+}
+
+/// SPI device on an STM32 OCTOSPI peripheral / 基于 STM32 OCTOSPI 外设的 SPI 设备
+///
+/// Dropped while memory-mapped, the device leaks the `Ospi` instead of disconnecting its pins, so
+/// the `&'static` window returned by `map` stays readable.
+/// 在内存映射期间被丢弃时，设备会泄漏 `Ospi` 而不是断开其引脚，从而使 `map` 返回的 `&'static` 窗口保持可读。
+pub struct OspiDevice<'d, T: Instance, M: Mode> {
+    ospi: ManuallyDrop<Ospi<'d, T, M>>,
+    /// Chip is in QPI mode; every phase uses four lines / 芯片处于 QPI 模式；所有阶段使用四线
+    qpi: bool,
+    /// The last frame was Enable Reset (`66h`) / 上一帧为复位使能（`66h`）
+    reset_enabled: bool,
+    /// The peripheral is in memory-mapped mode / 外设处于内存映射模式
+    memory_mapped: bool,
+}
+
+impl<'d, T: Instance, M: Mode> OspiDevice<'d, T, M> {
+    /// Wraps a configured OCTOSPI peripheral / 包装一个已配置的 OCTOSPI 外设
+    pub fn new(ospi: Ospi<'d, T, M>) -> Self {
+        Self { ospi: ManuallyDrop::new(ospi), qpi: false, reset_enabled: false, memory_mapped: false }
+    }
+
+    /// Releases the OCTOSPI peripheral; returns the device unchanged while memory-mapped.
+    /// 释放 OCTOSPI 外设；内存映射期间原样返回设备。
+    pub fn release(self) -> Result<Ospi<'d, T, M>, Self> {
+        if self.memory_mapped {
+            return Err(self);
+        }
+        let mut this = ManuallyDrop::new(self);
+        // SAFETY: `this` is never used or dropped again, and the other fields need no drop
+        // `this` 不会再被使用或释放，其余字段也无需释放
+        Ok(unsafe { ManuallyDrop::take(&mut this.ospi) })
+    }
+
+    /// Rejects indirect transfers, which would reconfigure the peripheral under the mapped window
+    /// 拒绝间接传输，因为它们会在映射窗口之下重新配置外设
+    fn check_unmapped(&self) -> Result<(), OspiBusError> {
+        if self.memory_mapped {
+            return Err(OspiBusError::MemoryMapped);
+        }
+        Ok(())
+    }
+
+    /// Follows the mode-switch commands described on `QpiSpiDevice` / 跟随 `QpiSpiDevice` 中描述的模式切换命令
+    fn follow_mode(&mut self, header: &[u8]) {
+        let reset_enabled = core::mem::take(&mut self.reset_enabled);
+        match header {
+            [0x38] => self.qpi = true,
+            [0xFF] => self.qpi = false,
+            [0x66] => self.reset_enabled = true,
+            [0x99] if reset_enabled => self.qpi = false,
+            _ => {}
+        }
+    }
+}
+
+impl<'d, T: Instance, M: Mode> Drop for OspiDevice<'d, T, M> {
+    fn drop(&mut self) {
+        if !self.memory_mapped {
+            // SAFETY: `ospi` is not used after `drop` / `drop` 之后不再使用 `ospi`
+            unsafe { ManuallyDrop::drop(&mut self.ospi) }
+        }
+    }
+}
+
+fn width(lines: u8) -> OspiWidth {
+    match lines {
+        1 => OspiWidth::SING,
+        2 => OspiWidth::DUAL,
+        4 => OspiWidth::QUAD,
+        _ => OspiWidth::OCTO,
+    }
+}
+
+fn size(bytes: usize) -> AddressSize {
+    match bytes {
+        1 => AddressSize::_8Bit,
+        2 => AddressSize::_16Bit,
+        3 => AddressSize::_24bit,
+        _ => AddressSize::_32bit,
+    }
+}
+
+fn dummy_cycles(clocks: u8) -> Result<DummyCycles, OspiBusError> {
+    Ok(match clocks {
+        0 => DummyCycles::_0,
+        2 => DummyCycles::_2,
+        4 => DummyCycles::_4,
+        6 => DummyCycles::_6,
+        8 => DummyCycles::_8,
+        10 => DummyCycles::_10,
+        _ => return Err(OspiBusError::Unsupported),
+    })
+}
+
+/// Big-endian value of up to 4 bytes / 至多 4 个字节的大端数值
+fn be_value(bytes: &[u8]) -> u32 {
+    bytes.iter().fold(0, |value, &byte| (value << 8) | byte as u32)
+}
+
+/// Dual/quad read transfer for `read` / `read` 对应的双线/四线读取传输
+fn multi_io_config(read: &MultiIoRead) -> Result<TransferConfig, OspiBusError> {
+    Ok(TransferConfig {
+        iwidth: OspiWidth::SING,
+        instruction: Some(read.opcode as u32),
+        adwidth: width(read.address_lines),
+        address: Some(read.address),
+        adsize: size(read.address_bytes as usize),
+        // Mode bits M7-0 travel on the address lines / 模式位 M7-0 通过地址线传输
+        abwidth: if read.mode_bits.is_some() { width(read.address_lines) } else { OspiWidth::NONE },
+        alternate_bytes: Some(read.mode_bits.unwrap_or(0) as u32),
+        absize: AddressSize::_8Bit,
+        dwidth: width(read.data_lines),
+        dummy: dummy_cycles(read.dummy_clocks)?,
+        ..Default::default()
+    })
+}
+
+/// Transfer sending `header` as instruction, address (bytes 1-4) and alternate bytes (bytes 5-8),
+/// all on `lines` lines.
+/// 将 `header` 作为指令、地址（第 1-4 字节）和交替字节（第 5-8 字节）发送的传输，全部使用 `lines` 根线。
+fn command_config(header: &[u8], lines: u8) -> TransferConfig {
+    let (address, alternate) = header[1..].split_at((header.len() - 1).min(4));
+    TransferConfig {
+        iwidth: width(lines),
+        instruction: Some(header[0] as u32),
+        adwidth: if address.is_empty() { OspiWidth::NONE } else { width(lines) },
+        address: (!address.is_empty()).then(|| be_value(address)),
+        adsize: size(address.len()),
+        abwidth: if alternate.is_empty() { OspiWidth::NONE } else { width(lines) },
+        // Always written so the alternate phase of an earlier transfer is switched off
+        // 总是写入，以关闭先前传输遗留的交替字节阶段
+        alternate_bytes: Some(be_value(alternate)),
+        absize: size(alternate.len()),
+        ..Default::default()
+    }
+}
+
+impl<'d, T: Instance, M: Mode> ErrorType for OspiDevice<'d, T, M> {
+    type Error = OspiBusError;
+}
+
+impl<'d, T: Instance, M: Mode> embedded_hal_async::spi::SpiDevice for OspiDevice<'d, T, M>
+where
+    Ospi<'d, T, M>: OspiTransfer,
+{
+    async fn transaction(&mut self, operations: &mut [Operation<'_, u8>]) -> Result<(), Self::Error> {
+        self.check_unmapped()?;
+        // The last operation is the data phase if it is a read, or a write after the command bytes
+        // 最后一个操作若为读操作，或为命令字节之后的写操作，则作为数据阶段
+        let data_start = match operations.last() {
+            Some(Operation::Read(_)) => operations.len() - 1,
+            Some(Operation::Write(_)) if operations.len() > 1 => operations.len() - 1,
+            _ => operations.len(),
+        };
+        let (commands, data) = operations.split_at_mut(data_start);
+        let mut header = [0u8; MAX_HEADER];
+        let mut len = 0;
+        for operation in commands {
+            match operation {
+                Operation::Write(bytes) => {
+                    header.get_mut(len..len + bytes.len()).ok_or(OspiBusError::Unsupported)?.copy_from_slice(bytes);
+                    len += bytes.len();
+                }
+                // NCS is driven by the peripheral, so a delay cannot hold it low / NCS 由外设控制，延时无法使其保持低电平
+                Operation::DelayNs(ns) => Timer::after_nanos(*ns as u64).await,
+                _ => return Err(OspiBusError::Unsupported),
+            }
+        }
+        if len == 0 {
+            return Ok(());
+        }
+        let header = &header[..len];
+        // Exit QPI goes out on four lines whatever the tracked mode / 无论跟踪的模式如何，退出 QPI 都通过四线发送
+        let lines = if self.qpi || header == [0xFF] { 4 } else { 1 };
+        let config = command_config(header, lines);
+        match data.first_mut() {
+            Some(Operation::Read(buf)) if !buf.is_empty() => {
+                self.ospi.read_data(buf, TransferConfig { dwidth: width(lines), ..config }).await?
+            }
+            Some(Operation::Write(buf)) if !buf.is_empty() => {
+                self.ospi.write_data(buf, TransferConfig { dwidth: width(lines), ..config }).await?
+            }
+            _ => self.ospi.blocking_command(&config)?,
+        }
+        self.follow_mode(header);
+        Ok(())
+    }
+}
+
+impl<'d, T: Instance, M: Mode> MultiIoSpiDevice for OspiDevice<'d, T, M>
+where
+    Ospi<'d, T, M>: OspiTransfer,
+{
+    async fn read_multi_io(&mut self, read: MultiIoRead, buf: &mut [u8]) -> Result<(), Self::Error> {
+        self.check_unmapped()?;
+        if self.qpi {
+            return Err(OspiBusError::Unsupported);
+        }
+        if buf.is_empty() {
+            return Ok(());
+        }
+        self.ospi.read_data(buf, multi_io_config(&read)?).await?;
+        Ok(())
+    }
+}
+
+/// Start of the memory-mapped window of `T` on the STM32H7 / STM32H7 上 `T` 的内存映射窗口起始地址
+fn window_base<T: Instance + 'static>() -> usize {
+    if TypeId::of::<T>() == TypeId::of::<OCTOSPI2>() {
+        0x7000_0000
+    } else {
+        0x9000_0000
+    }
+}
+
+// `'static` peripherals only: a leaked `Ospi<'d>` would hand its pins back when `'d` ends
+// 仅限 `'static` 外设：泄漏的 `Ospi<'d>` 会在 `'d` 结束时交还其引脚
+impl<T: Instance + 'static, M: Mode> MemoryMappedSpiDevice for OspiDevice<'static, T, M>
+where
+    Ospi<'static, T, M>: OspiTransfer,
+{
+    fn map(&mut self, read: MultiIoRead, len: usize) -> Result<&'static [u8], Self::Error> {
+        self.check_unmapped()?;
+        if self.qpi {
+            return Err(OspiBusError::Unsupported);
+        }
+        // The bus access supplies the address; no write phases, the window is read-only
+        // 地址由总线访问提供；没有写阶段，窗口为只读
+        let read_config = TransferConfig { address: None, ..multi_io_config(&read)? };
+        self.ospi.enable_memory_mapped_mode(read_config, TransferConfig::default())?;
+        self.memory_mapped = true;
+        // SAFETY: the window stays readable until `unmap`, whose caller guarantees the slice is
+        // no longer used: `release` refuses and `drop` leaks the peripheral while mapped
+        // 窗口在 `unmap` 之前保持可读，`unmap` 的调用者保证此后不再使用该切片：映射期间 `release` 拒绝释放，`drop` 泄漏外设
+        Ok(unsafe { core::slice::from_raw_parts(window_base::<T>() as *const u8, len) })
+    }
+
+    unsafe fn unmap(&mut self) -> Result<(), Self::Error> {
+        if self.memory_mapped {
+            self.ospi.disable_memory_mapped_mode();
+            self.memory_mapped = false;
+        }
+        Ok(())
+    }
+}
+
+impl<'d, T: Instance, M: Mode> QpiSpiDevice for OspiDevice<'d, T, M> where Ospi<'d, T, M>: OspiTransfer {}
+
+impl<'d, T: Instance> W25q128jv<OspiDevice<'d, T, Async>> {
+    /// Creates a new driver instance from an async (DMA) OCTOSPI peripheral.
+    /// 使用异步（DMA）OCTOSPI 外设创建新的驱动实例。
+    ///
+    /// # Arguments / 参数
+    /// * `ospi`: An OCTOSPI instance created with `Ospi::new_quadspi` (or `new_singlespi` / `new_dualspi`
+    ///   for fewer lines). / 使用 `Ospi::new_quadspi`（线数较少时使用 `new_singlespi` / `new_dualspi`）创建的 OCTOSPI 实例。
+    ///
+    pub fn new_ospi(ospi: Ospi<'d, T, Async>) -> Self {
+        Self::new(OspiDevice::new(ospi))
+    }
+}
+
+impl<'d, T: Instance> W25q128jv<OspiDevice<'d, T, Blocking>> {
+    /// Creates a new driver instance from a blocking OCTOSPI peripheral.
+    /// 使用阻塞式 OCTOSPI 外设创建新的驱动实例。
+    ///
+    /// Transfers block the executor; prefer `new_ospi` when a DMA channel is available.
+    /// 传输会阻塞执行器；如有 DMA 通道，建议使用 `new_ospi`。
+    ///
+    /// # Arguments / 参数
+    /// * `ospi`: An OCTOSPI instance created with `Ospi::new_blocking_quadspi`. / 使用 `Ospi::new_blocking_quadspi` 创建的 OCTOSPI 实例。
+    ///
+    pub fn new_ospi_blocking(ospi: Ospi<'d, T, Blocking>) -> Self {
+        Self::new(OspiDevice::new(ospi))
+    }
+}
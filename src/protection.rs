@@ -0,0 +1,219 @@
+// src/protection.rs
+
+//! Block Protection Regions / 块保护区域
+//!
+//! Maps the BP, TB, SEC and CMP bits to the protected address range from the W25Q
+//! protection tables (Datasheet Section 7.1.8, WPS = 0) and back, and holds the lock bitmap
+//! of the individual block lock mode (WPS = 1). Both depend on the `FlashGeometry`.
+//! 在 W25Q 保护表（数据手册第 7.1.8 节，WPS = 0）中，将 BP、TB、SEC 和 CMP 位映射为受保护的地址范围，
+//! 以及反向映射；并提供独立块锁定（WPS = 1）的锁定状态位图。两者都取决于 `FlashGeometry`。
+
+use core::ops::Range;
+
+use crate::geometry::{FlashGeometry, MAX_FLASH_SIZE};
+use crate::status::{StatusRegister1, StatusRegister2};
+use crate::w25q128jv::{BLOCK_64K_SIZE, SECTOR_SIZE};
+
+/// Parts above 16MB have BP3 in place of TB, TB in place of SEC, and no SEC bit.
+/// 容量超过 16MB 的器件用 BP3 代替 TB、用 TB 代替 SEC，且没有 SEC 位。
+fn has_bp3(geometry: &FlashGeometry) -> bool {
+    geometry.capacity > 16 * 1024 * 1024
+}
+
+/// Block protection setting (BP, TB, SEC, CMP) / 块保护设置 (BP, TB, SEC, CMP)
+///
+/// Only a fixed set of ranges can be protected: nothing, everything, the top or bottom
+/// 1/64 to 1/2 of the array (in 64KB steps on parts above 16MB), the top or bottom 4KB to
+/// 32KB (parts up to 16MB), and (with CMP = 1) the complement of any of those.
+/// 只有固定的几种范围可以被保护：无、全部、阵列顶部或底部的 1/64 到 1/2（容量超过 16MB 的器件以 64KB 为步进）、
+/// 顶部或底部的 4KB 到 32KB（容量不超过 16MB 的器件），以及（CMP = 1 时）上述任一范围的补集。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, defmt::Format)]
+pub struct ProtectionRegion {
+    /// BP2-BP0, plus BP3 on parts above 16MB / BP2-BP0，容量超过 16MB 的器件还包括 BP3
+    pub bp: u8,
+    /// TB: count from the bottom (1) instead of the top (0) / 从底部 (1) 而非顶部 (0) 开始计算
+    pub tb: bool,
+    /// SEC: 4KB sector (1) instead of 64KB block (0) granularity / 以 4KB 扇区 (1) 而非 64KB 块 (0) 为粒度
+    pub sec: bool,
+    /// CMP: protect the complement of the selected range / 保护所选范围的补集
+    pub cmp: bool,
+}
+
+impl ProtectionRegion {
+    /// No protection / 无保护
+    pub const NONE: Self = Self { bp: 0, tb: false, sec: false, cmp: false };
+    /// Whole array protected (all BP bits set) / 保护整个阵列（所有 BP 位置位）
+    pub const ALL: Self = Self { bp: 0b1111, tb: false, sec: false, cmp: false };
+
+    /// Builds the region from the current Status Register 1 and 2 values.
+    /// 根据当前状态寄存器1和2的值构造保护区域。
+    pub fn from_status(sr1: StatusRegister1, sr2: StatusRegister2, geometry: &FlashGeometry) -> Self {
+        if has_bp3(geometry) {
+            // S5 is BP3 and S6 is TB / S5 为 BP3，S6 为 TB
+            Self { bp: (sr1.bp & 0x07) | ((sr1.tb as u8) << 3), tb: sr1.sec, sec: false, cmp: sr2.cmp }
+        } else {
+            Self { bp: sr1.bp & 0x07, tb: sr1.tb, sec: sr1.sec, cmp: sr2.cmp }
+        }
+    }
+
+    /// Stores the region into Status Register 1 and 2 values, leaving the other bits unchanged.
+    /// 将保护区域写入状态寄存器1和2的值中，其他位保持不变。
+    pub fn apply(&self, sr1: &mut StatusRegister1, sr2: &mut StatusRegister2, geometry: &FlashGeometry) {
+        sr1.bp = self.bp & 0x07;
+        if has_bp3(geometry) {
+            sr1.tb = self.bp & 0x08 != 0;
+            sr1.sec = self.tb;
+        } else {
+            sr1.tb = self.tb;
+            sr1.sec = self.sec;
+        }
+        sr2.cmp = self.cmp;
+    }
+
+    /// Finds the bit combination protecting exactly `range`, or `None` if the table
+    /// has no such entry. Empty ranges map to `NONE`.
+    /// 查找恰好保护 `range` 的位组合；保护表中没有对应条目时返回 `None`。空范围对应 `NONE`。
+    pub fn from_range(range: Range<u32>, geometry: &FlashGeometry) -> Option<Self> {
+        let wanted = if range.is_empty() { 0..0 } else { range };
+        let (bp_values, sec_values): (u8, &[bool]) = if has_bp3(geometry) { (16, &[false]) } else { (8, &[false, true]) };
+        // Prefer CMP = 0 and the smallest BP value / 优先使用 CMP = 0 和最小的 BP 值
+        [false, true]
+            .into_iter()
+            .flat_map(|cmp| sec_values.iter().map(move |&sec| (cmp, sec)))
+            .flat_map(|(cmp, sec)| [false, true].into_iter().map(move |tb| (cmp, sec, tb)))
+            .flat_map(|(cmp, sec, tb)| (0..bp_values).map(move |bp| Self { bp, tb, sec, cmp }))
+            .find(|region| region.range(geometry) == wanted)
+    }
+
+    /// Protected address range; empty ranges are returned as `0..0`.
+    /// 受保护的地址范围；空范围返回 `0..0`。
+    pub fn range(&self, geometry: &FlashGeometry) -> Range<u32> {
+        let size = geometry.capacity as u32;
+        let bp3 = has_bp3(geometry);
+        let bp = self.bp & if bp3 { 0x0F } else { 0x07 };
+        // Length of the range selected by BP/SEC before CMP / 应用 CMP 之前由 BP/SEC 选择的范围长度
+        let len = match (self.sec && !bp3, bp) {
+            (_, 0) => 0,
+            (_, 7) if !bp3 => size,
+            // 1/64 of the array (at least 64KB) doubling per BP step, up to everything
+            // 阵列的 1/64（至少 64KB），BP 每增加 1 翻倍，直到整个阵列
+            (false, bp) => {
+                let unit = if bp3 { BLOCK_64K_SIZE as u32 } else { (size >> 6).max(BLOCK_64K_SIZE as u32) };
+                unit.checked_shl(bp as u32 - 1).unwrap_or(size).min(size)
+            }
+            // 4KB, 8KB, 16KB, then 32KB for BP = 10x / 4KB、8KB、16KB，BP = 10x 时为 32KB
+            (true, bp) => (SECTOR_SIZE as u32) << (bp.min(4) - 1),
+        };
+        // With CMP the bottom range becomes the top remainder and vice versa / 启用 CMP 时，底部范围变为顶部剩余部分，反之亦然
+        let (bottom, len) = if self.cmp { (!self.tb, size - len) } else { (self.tb, len) };
+        match len {
+            0 => 0..0,
+            _ if bottom => 0..len,
+            _ => size - len..size,
+        }
+    }
+
+    /// Number of bytes protected by both `self` and `other`.
+    /// `self` 与 `other` 同时保护的字节数。
+    pub(crate) fn shared_len(&self, other: &Self, geometry: &FlashGeometry) -> u32 {
+        let (a, b) = (self.range(geometry), other.range(geometry));
+        a.end.min(b.end).saturating_sub(a.start.max(b.start))
+    }
+
+    /// Returns `true` if any of the `len` bytes starting at `address` is protected.
+    /// 如果从 `address` 开始的 `len` 字节中有任何字节受保护，返回 `true`。
+    pub fn overlaps(&self, address: u32, len: usize, geometry: &FlashGeometry) -> bool {
+        let range = self.range(geometry);
+        let end = address as u64 + len as u64;
+        len > 0 && (address as u64) < range.end as u64 && end > range.start as u64
+    }
+}
+
+/// Words needed for the lock bits of the largest part / 最大器件的锁定位所需的字数
+const LOCK_WORDS: usize = (2 * (BLOCK_64K_SIZE / SECTOR_SIZE) + MAX_FLASH_SIZE / BLOCK_64K_SIZE - 2).div_ceil(32);
+
+/// Lock state of every individual block/sector lock unit / 每个独立块/扇区锁定单元的锁定状态
+///
+/// Units follow `FlashGeometry::block_lock_unit`: the 16 sectors of the bottom 64KB block,
+/// the 64KB blocks in between, then the 16 sectors of the top 64KB block.
+/// 锁定单元顺序与 `FlashGeometry::block_lock_unit` 一致：底部 64KB 块的 16 个扇区、中间的 64KB 块，
+/// 然后是顶部 64KB 块的 16 个扇区。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, defmt::Format)]
+pub struct BlockLockMap {
+    geometry: FlashGeometry,
+    bits: [u32; LOCK_WORDS],
+}
+
+impl BlockLockMap {
+    /// Every unit unlocked / 所有单元均未锁定
+    pub fn unlocked(geometry: FlashGeometry) -> Self {
+        Self { geometry, bits: [0; LOCK_WORDS] }
+    }
+
+    /// Every unit locked (the power-up state with WPS = 1) / 所有单元均锁定（WPS = 1 时的上电状态）
+    pub fn locked(geometry: FlashGeometry) -> Self {
+        let mut map = Self::unlocked(geometry);
+        (0..geometry.block_lock_units()).for_each(|index| map.set(index, true));
+        map
+    }
+
+    /// Returns `true` if lock unit `index` is locked / 锁定单元 `index` 被锁定时返回 `true`
+    pub fn is_unit_locked(&self, index: usize) -> bool {
+        self.bits[index / 32] & (1 << (index % 32)) != 0
+    }
+
+    /// Returns `true` if the unit holding `address` is locked / 包含 `address` 的单元被锁定时返回 `true`
+    pub fn is_locked(&self, address: u32) -> bool {
+        self.is_unit_locked(self.geometry.block_lock_index(address))
+    }
+
+    /// Returns `true` if any unit touched by `len` bytes at `address` is locked.
+    /// 如果 `address` 处 `len` 字节涉及的任何单元被锁定，返回 `true`。
+    pub fn overlaps(&self, address: u32, len: usize) -> bool {
+        if len == 0 {
+            return false;
+        }
+        let last = (address as usize + len - 1).min(self.geometry.capacity - 1) as u32;
+        let units = self.geometry.block_lock_index(address)..=self.geometry.block_lock_index(last);
+        units.into_iter().any(|index| self.is_unit_locked(index))
+    }
+
+    /// Number of locked units / 被锁定的单元数量
+    pub fn locked_count(&self) -> usize {
+        self.bits.iter().map(|word| word.count_ones() as usize).sum()
+    }
+
+    /// Sets the lock bit of unit `index` / 设置单元 `index` 的锁定位
+    pub(crate) fn set(&mut self, index: usize, locked: bool) {
+        if locked {
+            self.bits[index / 32] |= 1 << (index % 32);
+        } else {
+            self.bits[index / 32] &= !(1 << (index % 32));
+        }
+    }
+
+    /// Sets the lock bit of the unit holding `address` / 设置包含 `address` 的单元的锁定位
+    pub(crate) fn set_address(&mut self, address: u32, locked: bool) {
+        self.set(self.geometry.block_lock_index(address), locked);
+    }
+}
+
+/// Active write protection scheme, selected by WPS / 由 WPS 选择的当前写保护方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum WriteProtection {
+    /// WPS = 0: BP/TB/SEC/CMP region / WPS = 0：BP/TB/SEC/CMP 保护区域
+    Region(ProtectionRegion),
+    /// WPS = 1: individual block/sector locks / WPS = 1：独立块/扇区锁定
+    BlockLocks(BlockLockMap),
+}
+
+impl WriteProtection {
+    /// Returns `true` if any of `len` bytes at `address` cannot be programmed or erased.
+    /// 如果 `address` 处 `len` 字节中有任何字节无法被编程或擦除，返回 `true`。
+    pub(crate) fn overlaps(&self, address: u32, len: usize, geometry: &FlashGeometry) -> bool {
+        match self {
+            WriteProtection::Region(region) => region.overlaps(address, len, geometry),
+            WriteProtection::BlockLocks(locks) => locks.overlaps(address, len),
+        }
+    }
+}
@@ -0,0 +1,93 @@
+// src/qpi.rs
+
+//! QPI Mode / QPI 模式
+//!
+//! In QPI mode (Enable QPI `38h`, per the W25Q128FV/JW datasheets) every instruction, address
+//! and data byte travels on IO0-IO3, two clocks per byte. The bytes of each command are unchanged, so the
+//! driver keeps building the same frames and only the backend switches its line width: a
+//! `QpiSpiDevice` follows the mode-switch commands it sends. Set Read Parameters (`C0h`) selects the
+//! dummy clocks of Fast Read in QPI mode and the wrap length.
+//! 在 QPI 模式下（启用 QPI `38h`，依据 W25Q128FV/JW 数据手册），所有指令、地址和数据字节都通过 IO0-IO3 传输，
+//! 每字节两个时钟。各命令的字节不变，因此驱动仍构造相同的帧，只由后端切换线宽：`QpiSpiDevice` 跟随其发送的
+//! 模式切换命令。设置读取参数（`C0h`）选择 QPI 模式下快速读取的虚拟时钟数和回绕长度。
+//!
+//! Only QPI-capable parts (W25Q128FV/JW) enter QPI; the W25Q128JV ignores `38h`, which
+//! `W25q128jv::enter_qpi` detects and reports as `Error::Unsupported`.
+//! 只有支持 QPI 的器件（W25Q128FV/JW）会进入 QPI；W25Q128JV 会忽略 `38h`，`W25q128jv::enter_qpi`
+//! 会检测到这种情况并返回 `Error::Unsupported`。
+
+use crate::multi_io::MultiIoSpiDevice;
+
+/// Dummy clocks of Fast Read (`0Bh`) in QPI mode (P5-P4) / QPI 模式下快速读取（`0Bh`）的虚拟时钟数（P5-P4）
+///
+/// More clocks allow a higher SCLK; see the Set Read Parameters table in the datasheet.
+/// 时钟数越多，允许的 SCLK 越高；参见数据手册中的设置读取参数表。
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, defmt::Format)]
+pub enum DummyClocks {
+    /// 2 clocks (power-up default) / 2 个时钟（上电默认值）
+    #[default]
+    Two,
+    /// 4 clocks / 4 个时钟
+    Four,
+    /// 6 clocks / 6 个时钟
+    Six,
+    /// 8 clocks / 8 个时钟
+    Eight,
+}
+
+/// Wrap length of Burst Read with Wrap (P1-P0) / 带回绕的突发读取的回绕长度（P1-P0）
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, defmt::Format)]
+pub enum WrapLength {
+    /// 8 bytes (power-up default) / 8 字节（上电默认值）
+    #[default]
+    Bytes8,
+    /// 16 bytes / 16 字节
+    Bytes16,
+    /// 32 bytes / 32 字节
+    Bytes32,
+    /// 64 bytes / 64 字节
+    Bytes64,
+}
+
+/// Parameter byte of Set Read Parameters (`C0h`) / 设置读取参数（`C0h`）的参数字节
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, defmt::Format)]
+pub struct ReadParameters {
+    /// Dummy clocks in QPI mode / QPI 模式下的虚拟时钟数
+    pub dummy_clocks: DummyClocks,
+    /// Burst wrap length / 突发回绕长度
+    pub wrap_length: WrapLength,
+}
+
+impl ReadParameters {
+    /// Register value: P5-P4 dummy clocks, P1-P0 wrap length / 寄存器值：P5-P4 为虚拟时钟，P1-P0 为回绕长度
+    pub fn bits(&self) -> u8 {
+        ((self.dummy_clocks as u8) << 4) | self.wrap_length as u8
+    }
+
+    /// Dummy bytes after the address of a QPI Fast Read (two clocks per byte).
+    /// QPI 快速读取中地址之后的虚拟字节数（每字节两个时钟）。
+    pub(crate) fn dummy_bytes(&self) -> usize {
+        self.dummy_clocks as usize + 1
+    }
+}
+
+/// `MultiIoSpiDevice` that can run whole transactions in QPI (4-4-4) mode.
+/// 能以 QPI（4-4-4）模式执行完整事务的 `MultiIoSpiDevice`。
+///
+/// The backend tracks the chip's mode from the frames it sends and uses four lines for every
+/// phase while in QPI:
+/// 后端根据其发送的帧跟踪芯片的模式，处于 QPI 时所有阶段都使用四线：
+/// * after a frame holding only Enable QPI (`38h`) it switches to four lines;
+///   / 在仅包含启用 QPI（`38h`）的帧之后切换到四线；
+/// * a frame holding only Exit QPI (`FFh`) is always sent on four lines (a chip in SPI mode sees
+///   an incomplete instruction and ignores it), and switches back to one line;
+///   / 仅包含退出 QPI（`FFh`）的帧总是通过四线发送（处于 SPI 模式的芯片只看到不完整的指令并将其忽略），
+///   之后切换回单线；
+/// * Reset Device (`99h`) right after Enable Reset (`66h`) switches back to one line.
+///   / 紧随复位使能（`66h`）之后的复位器件（`99h`）切换回单线。
+///
+/// Dual/quad reads through `MultiIoSpiDevice` are only used in SPI mode.
+/// 通过 `MultiIoSpiDevice` 的双线/四线读取仅在 SPI 模式下使用。
+pub trait QpiSpiDevice: MultiIoSpiDevice {}
+
+impl<T: QpiSpiDevice + ?Sized> QpiSpiDevice for &mut T {}
@@ -0,0 +1,37 @@
+// src/security.rs
+
+//! Security Registers / 安全寄存器
+//!
+//! Three 256-byte one-time-programmable areas outside the main array (per Datasheet Section 8.2.29-8.2.31),
+//! addressed as `index` 1-3 and a byte offset. Setting a register's LB bit in Status Register 2
+//! makes it permanently read-only, so `lock_security_register` takes an `OtpLockConfirmation`.
+//! 主阵列之外的三个 256 字节一次性可编程区域（依据数据手册第 8.2.29-8.2.31 节），通过 `index` 1-3 和字节偏移寻址。
+//! 置位状态寄存器2中对应的 LB 位会使其永久只读，因此 `lock_security_register` 需要一个 `OtpLockConfirmation`。
+
+/// Size of each security register / 每个安全寄存器的大小
+pub const SECURITY_REGISTER_SIZE: usize = 256;
+/// Number of security registers (indices 1 to 3) / 安全寄存器的数量（索引 1 到 3）
+pub const SECURITY_REGISTERS: u8 = 3;
+
+/// Explicit acknowledgement that locking a security register cannot be undone.
+/// 明确确认锁定安全寄存器无法撤销。
+#[derive(Debug)]
+pub struct OtpLockConfirmation(());
+
+impl OtpLockConfirmation {
+    /// Confirms that the register will stay read-only forever / 确认寄存器将永久保持只读
+    pub fn i_understand_this_is_permanent() -> Self {
+        Self(())
+    }
+}
+
+/// Address of byte `offset` in security register `index`: A15-A12 select the register.
+/// Returns `None` if `index` is not 1-3 or `len` bytes at `offset` do not fit in the register.
+/// 安全寄存器 `index` 中字节 `offset` 的地址：A15-A12 选择寄存器。
+/// 如果 `index` 不在 1-3 之间，或 `offset` 处的 `len` 字节超出寄存器，返回 `None`。
+pub(crate) fn security_register_address(index: u8, offset: usize, len: usize) -> Option<u32> {
+    if !(1..=SECURITY_REGISTERS).contains(&index) || offset.checked_add(len)? > SECURITY_REGISTER_SIZE {
+        return None;
+    }
+    Some(((index as u32) << 12) | offset as u32)
+}
@@ -0,0 +1,249 @@
+// src/sfdp.rs
+
+//! Serial Flash Discoverable Parameters / 串行 Flash 可发现参数 (SFDP)
+//!
+//! Parses the SFDP header and the JEDEC Basic Flash Parameter Table (BFPT, JESD216) read
+//! with `W25q128jv::read_sfdp_parameters`.
+//! 解析通过 `W25q128jv::read_sfdp_parameters` 读取的 SFDP 头和 JEDEC 基本 Flash 参数表（BFPT，JESD216）。
+
+use embassy_time::Duration;
+
+/// "SFDP" signature at SFDP address 0 / SFDP 地址 0 处的 "SFDP" 签名
+pub(crate) const SFDP_SIGNATURE: u32 = 0x5044_4653;
+/// Parameter ID of the JEDEC Basic Flash Parameter Table / JEDEC 基本 Flash 参数表的参数 ID
+pub(crate) const BFPT_ID: u16 = 0xFF00;
+/// DWORDs of the BFPT that are parsed (JESD216B) / 解析的 BFPT 双字数量 (JESD216B)
+pub(crate) const BFPT_MAX_DWORDS: usize = 16;
+/// The first 9 DWORDs are mandatory since JESD216 / 自 JESD216 起前 9 个双字为必需
+pub(crate) const BFPT_MIN_DWORDS: usize = 9;
+
+/// SFDP parameter header / SFDP 参数头
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct ParameterHeader {
+    pub id: u16,
+    pub major: u8,
+    pub minor: u8,
+    /// Table length in DWORDs / 参数表长度（双字）
+    pub dwords: usize,
+    /// Table address in the SFDP space / 参数表在 SFDP 空间中的地址
+    pub pointer: u32,
+}
+
+impl ParameterHeader {
+    pub(crate) fn parse(raw: [u8; 8]) -> Self {
+        Self {
+            id: u16::from_le_bytes([raw[0], raw[7]]),
+            minor: raw[1],
+            major: raw[2],
+            dwords: raw[3] as usize,
+            pointer: u32::from_le_bytes([raw[4], raw[5], raw[6], 0]),
+        }
+    }
+}
+
+/// Supported address lengths (BFPT DWORD 1 bits 18:17) / 支持的地址长度（BFPT 第 1 个双字第 18:17 位）
+#[derive(Debug, Clone, Copy, PartialEq, Eq, defmt::Format)]
+pub enum AddressBytes {
+    /// 3-byte addressing only / 仅 3 字节地址
+    Three,
+    /// 3-byte by default, 4-byte can be entered / 默认 3 字节，可进入 4 字节模式
+    ThreeOrFour,
+    /// 4-byte addressing only / 仅 4 字节地址
+    Four,
+}
+
+/// A fast-read instruction and its cycle counts / 快速读取指令及其周期数
+#[derive(Debug, Clone, Copy, PartialEq, Eq, defmt::Format)]
+pub struct FastReadMode {
+    /// Instruction opcode / 指令操作码
+    pub opcode: u8,
+    /// Wait states (dummy clocks) / 等待状态（虚拟时钟）
+    pub dummy_clocks: u8,
+    /// Mode bit clocks / 模式位时钟数
+    pub mode_clocks: u8,
+}
+
+impl FastReadMode {
+    /// Decodes one 16-bit fast-read field of the BFPT / 解码 BFPT 中一个 16 位快速读取字段
+    fn parse(field: u16) -> Self {
+        Self {
+            opcode: (field >> 8) as u8,
+            dummy_clocks: (field & 0x1F) as u8,
+            mode_clocks: ((field >> 5) & 0x07) as u8,
+        }
+    }
+}
+
+/// Fast-read modes advertised by the BFPT, named `instruction-address-data` lines.
+/// BFPT 声明的快速读取模式，以 `指令-地址-数据` 线数命名。
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, defmt::Format)]
+pub struct FastReadModes {
+    /// 1-1-2 Fast Read Dual Output / 双线输出快速读取
+    pub read_1_1_2: Option<FastReadMode>,
+    /// 1-2-2 Fast Read Dual I/O / 双线 I/O 快速读取
+    pub read_1_2_2: Option<FastReadMode>,
+    /// 1-1-4 Fast Read Quad Output / 四线输出快速读取
+    pub read_1_1_4: Option<FastReadMode>,
+    /// 1-4-4 Fast Read Quad I/O / 四线 I/O 快速读取
+    pub read_1_4_4: Option<FastReadMode>,
+    /// 2-2-2 Fast Read (DPI) / 2-2-2 快速读取 (DPI)
+    pub read_2_2_2: Option<FastReadMode>,
+    /// 4-4-4 Fast Read (QPI) / 4-4-4 快速读取 (QPI)
+    pub read_4_4_4: Option<FastReadMode>,
+}
+
+/// One of the up to four erase types / 最多四种擦除类型之一
+#[derive(Debug, Clone, Copy, PartialEq, Eq, defmt::Format)]
+pub struct EraseType {
+    /// Erase size in bytes / 擦除大小（字节）
+    pub size: u32,
+    /// Instruction opcode / 指令操作码
+    pub opcode: u8,
+    /// Typical erase time (BFPT DWORD 10, JESD216A+) / 典型擦除时间（BFPT 第 10 个双字，JESD216A+）
+    pub typical_time: Option<Duration>,
+    /// Maximum erase time (BFPT DWORD 10, JESD216A+) / 最大擦除时间（BFPT 第 10 个双字，JESD216A+）
+    pub max_time: Option<Duration>,
+}
+
+/// Program timings (BFPT DWORD 11, JESD216A+) / 编程时序（BFPT 第 11 个双字，JESD216A+）
+#[derive(Debug, Clone, Copy, PartialEq, Eq, defmt::Format)]
+pub struct ProgramTimings {
+    /// Page size in bytes / 页面大小（字节）
+    pub page_size: usize,
+    /// Typical Page Program time / 典型页面编程时间
+    pub page_program_typical: Duration,
+    /// Maximum Page Program time / 最大页面编程时间
+    pub page_program_max: Duration,
+    /// Typical Chip Erase time / 典型整片擦除时间
+    pub chip_erase_typical: Duration,
+    /// Maximum Chip Erase time / 最大整片擦除时间
+    pub chip_erase_max: Duration,
+}
+
+/// Parsed JEDEC Basic Flash Parameter Table / 解析后的 JEDEC 基本 Flash 参数表
+#[derive(Debug, Clone, Copy, PartialEq, Eq, defmt::Format)]
+pub struct SfdpParameters {
+    /// BFPT revision (major, minor) / BFPT 版本（主版本，次版本）
+    pub revision: (u8, u8),
+    /// Memory density in bytes / 存储容量（字节）
+    pub capacity: u64,
+    /// Supported address lengths / 支持的地址长度
+    pub address_bytes: AddressBytes,
+    /// 4KB erase opcode, if uniform 4KB erase is supported / 支持统一 4KB 擦除时的 4KB 擦除操作码
+    pub sector_erase_opcode: Option<u8>,
+    /// Erase types 1-4; `None` for unused entries / 擦除类型 1-4；未使用的条目为 `None`
+    pub erase_types: [Option<EraseType>; 4],
+    /// Supported fast-read modes / 支持的快速读取模式
+    pub fast_read: FastReadModes,
+    /// Page size and program/chip erase timings, if the table is long enough.
+    /// 页面大小及编程/整片擦除时序（参数表足够长时提供）。
+    pub program: Option<ProgramTimings>,
+}
+
+impl SfdpParameters {
+    /// Parses a BFPT given as little-endian DWORDs; returns `None` if it is shorter than
+    /// the 9 mandatory DWORDs.
+    /// 解析以小端双字形式给出的 BFPT；不足 9 个必需双字时返回 `None`。
+    pub fn parse(revision: (u8, u8), table: &[u8]) -> Option<Self> {
+        if table.len() < BFPT_MIN_DWORDS * 4 {
+            return None;
+        }
+        // 1-based DWORD access like the standard / 与标准一致，使用从 1 开始的双字编号
+        let dword = |n: usize| {
+            table.get((n - 1) * 4..n * 4).map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+        };
+        let dw1 = dword(1)?;
+        let dw2 = dword(2)?;
+
+        let address_bytes = match (dw1 >> 17) & 0x03 {
+            0 => AddressBytes::Three,
+            1 => AddressBytes::ThreeOrFour,
+            _ => AddressBytes::Four,
+        };
+        // Bit 31 selects 2^N bits instead of N+1 bits / 第 31 位选择 2^N 位而非 N+1 位
+        let capacity_bits = if dw2 & 0x8000_0000 == 0 {
+            dw2 as u64 + 1
+        } else {
+            1u64.checked_shl(dw2 & 0x7FFF_FFFF).unwrap_or(0)
+        };
+        let sector_erase_opcode = (dw1 & 0x03 == 0x01).then_some((dw1 >> 8) as u8);
+
+        let dw3 = dword(3)?;
+        let dw4 = dword(4)?;
+        let dw5 = dword(5)?;
+        let supported = |bit: u32, field: u32| (dw1 & (1 << bit) != 0).then(|| FastReadMode::parse(field as u16));
+        let fast_read = FastReadModes {
+            read_1_1_2: supported(16, dw4),
+            read_1_2_2: supported(20, dw4 >> 16),
+            read_1_1_4: supported(22, dw3 >> 16),
+            read_1_4_4: supported(21, dw3),
+            read_2_2_2: if dw5 & 0x01 != 0 { Some(FastReadMode::parse((dword(6)? >> 16) as u16)) } else { None },
+            read_4_4_4: if dw5 & 0x10 != 0 { Some(FastReadMode::parse((dword(7)? >> 16) as u16)) } else { None },
+        };
+
+        // Erase types: size exponent + opcode pairs in DWORDs 8 and 9 / 擦除类型：第 8、9 个双字中的大小指数和操作码
+        let erase_fields = [dword(8)? as u16, (dword(8)? >> 16) as u16, dword(9)? as u16, (dword(9)? >> 16) as u16];
+        let erase_times = dword(10).map(erase_times);
+        let mut erase_types = [None; 4];
+        for (i, field) in erase_fields.into_iter().enumerate() {
+            let exponent = field & 0xFF;
+            if exponent == 0 {
+                continue;
+            }
+            let times = erase_times.map(|times| times[i]);
+            erase_types[i] = Some(EraseType {
+                size: 1u32.checked_shl(exponent as u32).unwrap_or(0),
+                opcode: (field >> 8) as u8,
+                typical_time: times.map(|(typical, _)| typical),
+                max_time: times.map(|(_, max)| max),
+            });
+        }
+
+        Some(Self {
+            revision,
+            capacity: capacity_bits / 8,
+            address_bytes,
+            sector_erase_opcode,
+            erase_types,
+            fast_read,
+            program: dword(11).map(program_timings),
+        })
+    }
+
+    /// Looks up the erase type with the given size / 查找指定大小的擦除类型
+    pub fn erase_type(&self, size: u32) -> Option<EraseType> {
+        self.erase_types.iter().flatten().find(|erase| erase.size == size).copied()
+    }
+}
+
+/// Typical/maximum times of erase types 1-4 from DWORD 10 / 从第 10 个双字解析擦除类型 1-4 的典型/最大时间
+fn erase_times(dw10: u32) -> [(Duration, Duration); 4] {
+    let multiplier = 2 * ((dw10 & 0x0F) as u64 + 1);
+    let mut times = [(Duration::from_ticks(0), Duration::from_ticks(0)); 4];
+    for (i, time) in times.iter_mut().enumerate() {
+        let field = (dw10 >> (4 + 7 * i)) & 0x7F;
+        let count = (field & 0x1F) as u64 + 1;
+        let unit_ms = [1, 16, 128, 1000][(field >> 5) as usize];
+        let typical = Duration::from_millis(count * unit_ms);
+        *time = (typical, typical * multiplier as u32);
+    }
+    times
+}
+
+/// Page size and program/chip erase times from DWORD 11 / 从第 11 个双字解析页面大小及编程/整片擦除时间
+fn program_timings(dw11: u32) -> ProgramTimings {
+    let multiplier = 2 * ((dw11 & 0x0F) + 1);
+    let page_count = ((dw11 >> 8) & 0x1F) as u64 + 1;
+    let page_unit_us = if dw11 & (1 << 13) != 0 { 64 } else { 8 };
+    let chip_count = ((dw11 >> 24) & 0x1F) as u64 + 1;
+    let chip_unit_ms = [16, 256, 4_000, 64_000][((dw11 >> 29) & 0x03) as usize];
+    let page_program_typical = Duration::from_micros(page_count * page_unit_us);
+    let chip_erase_typical = Duration::from_millis(chip_count * chip_unit_ms);
+    ProgramTimings {
+        page_size: 1 << ((dw11 >> 4) & 0x0F),
+        page_program_typical,
+        page_program_max: page_program_typical * multiplier,
+        chip_erase_typical,
+        chip_erase_max: chip_erase_typical * multiplier,
+    }
+}
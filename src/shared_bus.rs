@@ -0,0 +1,61 @@
+// src/shared_bus.rs
+
+//! Shared SPI Bus / 共享 SPI 总线
+//!
+//! Constructors for a flash that shares its SPI bus with other devices (SD card, display, ...)
+//! through `embassy_embedded_hal::shared_bus::asynch::spi`. The bus sits in an
+//! `embassy_sync::mutex::Mutex` and every device owns its CS pin. Each driver command is one
+//! `SpiDevice` transaction, so the bus is locked for exactly one CS frame; busy polling and
+//! the waits between frames leave it free for the other devices.
+//! 用于通过 `embassy_embedded_hal::shared_bus::asynch::spi` 与其他设备（SD 卡、显示屏等）共享 SPI 总线的
+//! Flash 的构造函数。总线放在 `embassy_sync::mutex::Mutex` 中，每个设备持有自己的 CS 引脚。
+//! 每条驱动命令是一次 `SpiDevice` 事务，因此总线只在一个 CS 周期内被锁定；忙轮询和帧之间的等待期间，
+//! 其他设备可以使用总线。
+//!
+//! With `new_shared_with_bus_config` the bus is reconfigured with the flash's own frequency
+//! and mode at the start of every frame, so the other devices may use different settings.
+//! 使用 `new_shared_with_bus_config` 时，每帧开始时都会用 Flash 自己的频率和模式重新配置总线，
+//! 因此其他设备可以使用不同的设置。
+//!
+//! `embassy-embedded-hal` 0.3 locks the bus with an embassy-sync 0.6 `Mutex`, while `SharedFlash`
+//! uses embassy-sync 0.7; the bus mutex must come from embassy-sync 0.6.
+//! `embassy-embedded-hal` 0.3 使用 embassy-sync 0.6 的 `Mutex` 锁定总线，而 `SharedFlash` 使用
+//! embassy-sync 0.7；总线互斥锁必须来自 embassy-sync 0.6。
+
+use embassy_embedded_hal::{
+    shared_bus::asynch::spi::{SpiDevice, SpiDeviceWithConfig},
+    SetConfig,
+};
+use embassy_sync_06::{blocking_mutex::raw::RawMutex, mutex::Mutex};
+use embedded_hal::digital::OutputPin;
+use embedded_hal_async::spi::SpiBus;
+
+use crate::W25q128jv;
+
+impl<'a, M: RawMutex, BUS: SpiBus, CS: OutputPin> W25q128jv<SpiDevice<'a, M, BUS, CS>> {
+    /// Creates a new driver instance on a shared SPI bus, using the bus as configured.
+    /// 在共享 SPI 总线上创建新的驱动实例，使用总线当前的配置。
+    ///
+    /// # Arguments / 参数
+    /// * `bus`: The mutex-protected SPI bus shared with the other devices. / 与其他设备共享、由互斥锁保护的 SPI 总线。
+    /// * `cs`: A GPIO output pin for /CS. / 用于 /CS 的 GPIO 输出引脚。
+    ///
+    pub fn new_shared(bus: &'a Mutex<M, BUS>, cs: CS) -> Self {
+        Self::new(SpiDevice::new(bus, cs))
+    }
+}
+
+impl<'a, M: RawMutex, BUS: SpiBus + SetConfig, CS: OutputPin> W25q128jv<SpiDeviceWithConfig<'a, M, BUS, CS>> {
+    /// Creates a new driver instance on a shared SPI bus that is set to `bus_config` for
+    /// every flash transaction.
+    /// 在共享 SPI 总线上创建新的驱动实例，每次 Flash 事务都会将总线设置为 `bus_config`。
+    ///
+    /// # Arguments / 参数
+    /// * `bus`: The mutex-protected SPI bus shared with the other devices. / 与其他设备共享、由互斥锁保护的 SPI 总线。
+    /// * `cs`: A GPIO output pin for /CS. / 用于 /CS 的 GPIO 输出引脚。
+    /// * `bus_config`: SPI frequency and mode for the flash (e.g. `embassy_stm32::spi::Config`). / Flash 使用的 SPI 频率和模式（例如 `embassy_stm32::spi::Config`）。
+    ///
+    pub fn new_shared_with_bus_config(bus: &'a Mutex<M, BUS>, cs: CS, bus_config: BUS::Config) -> Self {
+        Self::new(SpiDeviceWithConfig::new(bus, cs, bus_config))
+    }
+}
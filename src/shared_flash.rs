@@ -0,0 +1,200 @@
+// src/shared_flash.rs
+
+//! Flash Shared Between Tasks / 多任务共享的 Flash
+//!
+//! `SharedFlash` puts a `W25q128jv` behind an `embassy_sync::mutex::Mutex`, so several tasks can
+//! use one chip through a `&'static SharedFlash` (e.g. from a `StaticCell`). Every method holds
+//! the lock for a whole driver operation: a program runs its wait for idle, Write Enable, Page
+//! Program and completion polling without another task's command in between, even while it
+//! sleeps on the busy flag. Sequences of several operations use `lock`.
+//! `SharedFlash` 将 `W25q128jv` 放在 `embassy_sync::mutex::Mutex` 之后，多个任务可以通过
+//! `&'static SharedFlash`（例如来自 `StaticCell`）使用同一颗芯片。每个方法在整个驱动操作期间持有锁：
+//! 编程操作的等待空闲、写使能、页面编程和完成轮询之间不会插入其他任务的命令，即使在等待忙标志期间休眠也是如此。
+//! 由多个操作组成的序列使用 `lock`。
+//!
+//! `&SharedFlash` also implements the `embedded-storage-async` NOR flash traits, so each task can
+//! hand its reference to `sequential-storage` and similar crates.
+//! `&SharedFlash` 还实现了 `embedded-storage-async` 的 NOR Flash 特征，每个任务都可以把自己的引用交给
+//! `sequential-storage` 等库。
+
+use core::ops::{Deref, DerefMut};
+use core::sync::atomic::{AtomicUsize, Ordering};
+
+use embassy_sync::{
+    blocking_mutex::raw::RawMutex,
+    mutex::{Mutex, MutexGuard},
+};
+use embassy_time::Timer;
+use embedded_hal_async::spi::SpiDevice;
+use embedded_storage_async::nor_flash::{ErrorType, MultiwriteNorFlash, NorFlash, ReadNorFlash};
+
+use crate::w25q128jv::{Error, RecoverySteps, W25q128jv, SECTOR_SIZE};
+
+/// `W25q128jv` shared between tasks through a mutex / 通过互斥锁在多个任务间共享的 `W25q128jv`
+///
+/// Use `CriticalSectionRawMutex` for tasks on different executors or interrupt priorities,
+/// `NoopRawMutex` when all of them run on one executor.
+/// 任务位于不同执行器或中断优先级时使用 `CriticalSectionRawMutex`，全部运行在同一执行器上时使用 `NoopRawMutex`。
+pub struct SharedFlash<M: RawMutex, SPI> {
+    flash: Mutex<M, W25q128jv<SPI>>,
+    /// Driver capacity, refreshed whenever a `SharedFlashGuard` is dropped / 驱动容量，每次释放 `SharedFlashGuard` 时刷新
+    capacity: AtomicUsize,
+}
+
+/// Lock on a `SharedFlash`, returned by `SharedFlash::lock` / `SharedFlash::lock` 返回的 `SharedFlash` 锁
+///
+/// Dropping it stores the driver's capacity in the `SharedFlash`, so an `init` run under the lock
+/// is reported by `SharedFlash::capacity` from then on.
+/// 释放时将驱动容量存入 `SharedFlash`，因此在锁内运行的 `init` 此后会反映在 `SharedFlash::capacity` 中。
+pub struct SharedFlashGuard<'a, M: RawMutex, SPI: SpiDevice> {
+    flash: MutexGuard<'a, M, W25q128jv<SPI>>,
+    capacity: &'a AtomicUsize,
+}
+
+impl<M: RawMutex, SPI: SpiDevice> Deref for SharedFlashGuard<'_, M, SPI> {
+    type Target = W25q128jv<SPI>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.flash
+    }
+}
+
+impl<M: RawMutex, SPI: SpiDevice> DerefMut for SharedFlashGuard<'_, M, SPI> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.flash
+    }
+}
+
+impl<M: RawMutex, SPI: SpiDevice> Drop for SharedFlashGuard<'_, M, SPI> {
+    fn drop(&mut self) {
+        self.capacity.store(self.flash.geometry().capacity, Ordering::Relaxed);
+    }
+}
+
+impl<M: RawMutex, SPI: SpiDevice> SharedFlash<M, SPI> {
+    /// Wraps a driver, initialized or not; `capacity` follows the geometry `init` detects later.
+    /// 包装一个驱动（无论是否已初始化）；`capacity` 会跟随之后 `init` 检测到的几何参数。
+    pub fn new(flash: W25q128jv<SPI>) -> Self {
+        let capacity = AtomicUsize::new(flash.geometry().capacity);
+        Self { flash: Mutex::new(flash), capacity }
+    }
+
+    /// `W25q128jv::init` under the lock / 在锁内执行 `W25q128jv::init`
+    pub async fn init(&self) -> Result<RecoverySteps, Error<SPI::Error>> {
+        self.lock().await.init().await
+    }
+
+    /// Locks the driver for a sequence of operations; other tasks wait until the guard is dropped.
+    /// 为一系列操作锁定驱动；其他任务会等待，直到守卫被释放。
+    pub async fn lock(&self) -> SharedFlashGuard<'_, M, SPI> {
+        SharedFlashGuard { flash: self.flash.lock().await, capacity: &self.capacity }
+    }
+
+    /// Releases the driver / 释放驱动
+    pub fn into_inner(self) -> W25q128jv<SPI> {
+        self.flash.into_inner()
+    }
+
+    /// Capacity of the detected part in bytes / 检测到的器件容量（字节）
+    ///
+    /// Follows every `init`, whether run through `SharedFlash::init` or `lock`, once its guard is
+    /// dropped.
+    /// 跟随每一次 `init`（无论通过 `SharedFlash::init` 还是 `lock` 运行），在其守卫释放后生效。
+    pub fn capacity(&self) -> usize {
+        self.capacity.load(Ordering::Relaxed)
+    }
+
+    /// Puts the chip into deep power-down after `Config::auto_power_down` of inactivity; spawn it
+    /// next to the tasks using the flash. Sleeps until `power_down_deadline`, then calls
+    /// `power_down_if_idle` under the lock, so commands issued meanwhile push the deadline back.
+    /// While no deadline is pending (chip asleep, erase running or memory-mapped) it checks again
+    /// after `auto_power_down`.
+    /// 在空闲 `Config::auto_power_down` 后使芯片进入深度掉电；与使用 Flash 的任务一起运行。休眠到
+    /// `power_down_deadline`，然后在锁内调用 `power_down_if_idle`，期间发出的命令会推迟截止时间。
+    /// 没有待定的截止时间时（芯片已睡眠、擦除正在运行或处于内存映射模式），在 `auto_power_down` 之后再次检查。
+    ///
+    /// Returns `Ok(())` as soon as `auto_power_down` is `None`, so a disabled policy costs no
+    /// wakeups; run it again after enabling it with `set_config`. Returns the error of a failed
+    /// power-down.
+    /// 一旦 `auto_power_down` 为 `None` 即返回 `Ok(())`，因此关闭该策略不会带来任何唤醒；通过 `set_config`
+    /// 启用后需重新运行。掉电失败时返回相应错误。
+    pub async fn run_auto_power_down(&self) -> Result<(), Error<SPI::Error>> {
+        loop {
+            let (deadline, timeout) = {
+                let flash = self.lock().await;
+                (flash.power_down_deadline(), flash.config().auto_power_down)
+            };
+            let Some(timeout) = timeout else {
+                return Ok(());
+            };
+            match deadline {
+                Some(deadline) => Timer::at(deadline).await,
+                None => Timer::after(timeout).await,
+            }
+            self.lock().await.power_down_if_idle().await?;
+        }
+    }
+
+    /// `W25q128jv::fast_read` under the lock / 在锁内执行 `W25q128jv::fast_read`
+    pub async fn fast_read(&self, address: u32, buf: &mut [u8]) -> Result<(), Error<SPI::Error>> {
+        self.lock().await.fast_read(address, buf).await
+    }
+
+    /// `W25q128jv::program` under the lock / 在锁内执行 `W25q128jv::program`
+    pub async fn program(&self, address: u32, data: &[u8]) -> Result<(), Error<SPI::Error>> {
+        self.lock().await.program(address, data).await
+    }
+
+    /// `W25q128jv::erase_range` under the lock / 在锁内执行 `W25q128jv::erase_range`
+    pub async fn erase_range(&self, start: u32, len: usize) -> Result<(), Error<SPI::Error>> {
+        self.lock().await.erase_range(start, len).await
+    }
+
+    /// `W25q128jv::write` under the lock: no other task's command lands between reading a sector
+    /// and reprogramming it.
+    /// 在锁内执行 `W25q128jv::write`：读取扇区与重新编程之间不会插入其他任务的命令。
+    pub async fn write(&self, address: u32, data: &[u8]) -> Result<(), Error<SPI::Error>> {
+        self.lock().await.write(address, data).await
+    }
+
+    /// `W25q128jv::write_with_buffer` under the lock / 在锁内执行 `W25q128jv::write_with_buffer`
+    pub async fn write_with_buffer(
+        &self,
+        address: u32,
+        data: &[u8],
+        buffer: &mut [u8; SECTOR_SIZE],
+    ) -> Result<(), Error<SPI::Error>> {
+        self.lock().await.write_with_buffer(address, data, buffer).await
+    }
+}
+
+impl<M: RawMutex, SPI: SpiDevice> ErrorType for &SharedFlash<M, SPI> {
+    type Error = Error<SPI::Error>;
+}
+
+impl<M: RawMutex, SPI: SpiDevice> ReadNorFlash for &SharedFlash<M, SPI> {
+    const READ_SIZE: usize = <W25q128jv<SPI> as ReadNorFlash>::READ_SIZE;
+
+    async fn read(&mut self, offset: u32, bytes: &mut [u8]) -> Result<(), Self::Error> {
+        ReadNorFlash::read(&mut *self.lock().await, offset, bytes).await
+    }
+
+    fn capacity(&self) -> usize {
+        SharedFlash::capacity(self)
+    }
+}
+
+impl<M: RawMutex, SPI: SpiDevice> NorFlash for &SharedFlash<M, SPI> {
+    const WRITE_SIZE: usize = <W25q128jv<SPI> as NorFlash>::WRITE_SIZE;
+    const ERASE_SIZE: usize = <W25q128jv<SPI> as NorFlash>::ERASE_SIZE;
+
+    async fn erase(&mut self, from: u32, to: u32) -> Result<(), Self::Error> {
+        NorFlash::erase(&mut *self.lock().await, from, to).await
+    }
+
+    async fn write(&mut self, offset: u32, bytes: &[u8]) -> Result<(), Self::Error> {
+        NorFlash::write(&mut *self.lock().await, offset, bytes).await
+    }
+}
+
+impl<M: RawMutex, SPI: SpiDevice> MultiwriteNorFlash for &SharedFlash<M, SPI> {}
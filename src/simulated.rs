@@ -0,0 +1,841 @@
+// src/simulated.rs
+
+//! RAM-backed W25Q128JV Simulator / 基于 RAM 的 W25Q128JV 模拟器
+//!
+//! `SimulatedW25q128` models the chip behind the SPI bus: it decodes the same command
+//! frames the real device sees and implements `embedded_hal_async::spi::SpiDevice`.
+//! Wrapping it in `W25q128jv` therefore gives exactly the same API and traits as the
+//! real driver, so application logic can be tested with `cargo test` on the host.
+//! `SimulatedW25q128` 在 SPI 总线之后模拟芯片：它解析与真实器件相同的命令帧，并实现
+//! `embedded_hal_async::spi::SpiDevice`。将其包装进 `W25q128jv` 即可获得与真实驱动完全相同的
+//! API 和特征，从而可以在主机上用 `cargo test` 测试应用逻辑。
+//!
+//! Modelled NOR behaviour / 模拟的 NOR 行为:
+//! * Erase sets bytes to 0xFF. / 擦除将字节置为 0xFF。
+//! * Page Program can only clear bits (`old & new`). / 页面编程只能清零位（`old & new`）。
+//! * Page Program wraps around inside the 256-byte page. / 页面编程在 256 字节页面内回绕。
+//! * WEL must be set before program/erase and is cleared afterwards. / 编程/擦除前必须置位 WEL，完成后清除。
+//! * BUSY stays set for the configured operation time; commands other than
+//!   Read Status Register 1-3 and Suspend are ignored while busy. / BUSY 在设定的操作时间内保持置位；
+//!   忙时除读状态寄存器 1-3 和暂停外的命令都会被忽略。
+//! * Program/erase commands touching the BP/TB/SEC/CMP protected region (WPS = 0) or a
+//!   locked block/sector (WPS = 1, all locked at power-up) are ignored.
+//!   / 涉及 BP/TB/SEC/CMP 保护区域（WPS = 0）或已锁定块/扇区（WPS = 1，上电时全部锁定）的编程/擦除命令会被忽略。
+//! * JEDEC ID (`9Fh`), Manufacturer/Device ID (`90h`), Unique ID (`4Bh`) and SFDP (`5Ah`) return
+//!   W25Q128JV values, or those of the part given to `with_geometry`.
+//!   / JEDEC ID (`9Fh`)、制造商/设备 ID (`90h`)、唯一 ID (`4Bh`) 和 SFDP (`5Ah`) 返回 W25Q128JV
+//!   的值，或传给 `with_geometry` 的器件的值。
+//! * Parts above 16MB (`with_geometry`) decode the 4-byte opcodes (`13h`/`0Ch`/`12h`/`21h`/`DCh`),
+//!   Enter/Exit 4-Byte Address Mode (`B7h`/`E9h`, SR3 ADS) and the Extended Address Register
+//!   (`C5h`/`C8h`), which supplies A31-A24 in 3-byte mode. / 容量超过 16MB 的器件（`with_geometry`）
+//!   解析 4 字节操作码（`13h`/`0Ch`/`12h`/`21h`/`DCh`）、进入/退出 4 字节地址模式（`B7h`/`E9h`，SR3 ADS）
+//!   以及扩展地址寄存器（`C5h`/`C8h`），后者在 3 字节模式下提供 A31-A24。
+//! * Security Registers 1-3 (`48h`/`42h`/`44h`) behave like small NOR pages and become read-only once
+//!   their LB bit is set. / 安全寄存器 1-3（`48h`/`42h`/`44h`）的行为类似小型 NOR 页面，其 LB 位置位后变为只读。
+//! * Erase/Program Suspend (`75h`) pauses a running erase or program (not Chip Erase or a Status
+//!   Register write) and sets SUS; Resume (`7Ah`) continues it. Program/erase/status writes are
+//!   ignored while suspended. / 擦除/编程暂停（`75h`）暂停正在运行的擦除或编程（整片擦除和写状态寄存器除外）并置位 SUS；
+//!   恢复（`7Ah`）使其继续。暂停期间忽略编程/擦除/写状态寄存器命令。
+//! * Power-down (`B9h`) makes the chip ignore everything but Release Power-down (`ABh`), which also
+//!   returns the device ID after 3 dummy bytes. / 掉电（`B9h`）后芯片忽略除释放掉电（`ABh`）以外的所有命令；
+//!   `ABh` 在 3 个虚拟字节之后还会返回设备 ID。
+//! * Dual/quad Fast Reads (`3Bh`/`6Bh`/`BBh`/`EBh` and their 4-byte opcodes) through `MultiIoSpiDevice`;
+//!   the quad modes return 0xFF unless QE is set, as do transfers with the wrong line, mode bit or
+//!   dummy clock counts. / 通过 `MultiIoSpiDevice` 支持双线/四线快速读取（`3Bh`/`6Bh`/`BBh`/`EBh` 及其 4 字节操作码）；
+//!   QE 未置位时四线模式返回 0xFF，线数、模式位或虚拟时钟数不正确的传输同样返回 0xFF。
+//! * Enable Reset + Reset Device (`66h` + `99h`) abort a running operation, even while busy, and
+//!   restore the power-up state of WEL, SUS, the block locks, ADS and the Extended Address Register.
+//!   / 复位使能 + 复位器件（`66h` + `99h`）即使在忙时也会中止正在运行的操作，并将 WEL、SUS、块锁定、ADS
+//!   和扩展地址寄存器恢复为上电状态。
+//! * QPI mode (`38h`, needs QE = 1) when created with `set_qpi_capable(true)`, as on the W25Q128FV/JW;
+//!   the default W25Q128JV ignores `38h`. The simulated bus follows the `QpiSpiDevice` rules, so
+//!   frames sent on the wrong number of lines are ignored and read 0xFF. In QPI mode Fast Read uses
+//!   the dummy clocks from Set Read Parameters (`C0h`), JEDEC ID reports memory type 60h, and Read
+//!   Data, Unique ID, SFDP and the security registers are not decoded; `FFh` or a reset leaves QPI.
+//!   / 通过 `set_qpi_capable(true)` 支持 QPI 模式（`38h`，需要 QE = 1），与 W25Q128FV/JW 相同；默认的
+//!   W25Q128JV 会忽略 `38h`。模拟总线遵循 `QpiSpiDevice` 的规则，因此以错误线数发送的帧会被忽略并读取为 0xFF。
+//!   QPI 模式下快速读取使用设置读取参数（`C0h`）中的虚拟时钟数，JEDEC ID 报告内存类型 60h，且不解析读取数据、
+//!   唯一 ID、SFDP 和安全寄存器；`FFh` 或复位会退出 QPI。
+//! * `MemoryMappedSpiDevice` returns a snapshot of the array, refreshed on every `map` in one buffer
+//!   leaked per simulator. Until `unmap`, transfers and a second `map` fail with
+//!   `SimulatedBusError::MemoryMapped`. Mode bits M5-4 = 10 leave the chip in continuous read mode,
+//!   where it ignores frames until one starts with at least 8 clocks of `FFh` on IO0. / `MemoryMappedSpiDevice` 返回阵列的快照，每次 `map` 时在每个模拟器只泄漏一次的缓冲区中刷新。
+//!   `unmap` 之前，传输和再次 `map` 都会返回 `SimulatedBusError::MemoryMapped`。
+//!   模式位 M5-4 = 10 使芯片停留在连续读取模式，此时芯片忽略所有帧，直到某帧以 IO0 上至少 8 个时钟的 `FFh` 开始。
+//! * Status Registers 1-3 are readable and writable, either after WEL (non-volatile, busy for tW)
+//!   or after `50h` (volatile, immediate); LB1-LB3 can only be set. / 状态寄存器 1-3 可读可写，
+//!   写入前需置位 WEL（非易失，忙 tW）或发送 `50h`（易失，立即生效）；LB1-LB3 只能置位。
+//!
+//! ```
+//! # embassy_futures::block_on(async {
+//! use w25q128::{SimulatedW25q128, W25q128jv};
+//!
+//! let mut flash = W25q128jv::new(SimulatedW25q128::new());
+//! flash.program(0x1000, b"hello").await.unwrap();
+//! let mut buf = [0u8; 5];
+//! flash.read_data(0x1000, &mut buf).await.unwrap();
+//! assert_eq!(&buf, b"hello");
+//! # });
+//! ```
+
+extern crate std;
+
+use core::ptr::NonNull;
+use std::{vec, vec::Vec};
+
+use embassy_time::{Duration, Instant};
+use embedded_hal::spi::{ErrorKind, ErrorType, Operation};
+
+use crate::geometry::FlashGeometry;
+use crate::memory_mapped::MemoryMappedSpiDevice;
+use crate::multi_io::{MultiIoRead, MultiIoSpiDevice};
+use crate::protection::{BlockLockMap, ProtectionRegion};
+use crate::qpi::QpiSpiDevice;
+use crate::security::SECURITY_REGISTER_SIZE;
+use crate::w25q128jv::{BLOCK_32K_SIZE, BLOCK_64K_SIZE, PAGE_SIZE, SECTOR_SIZE};
+
+/// Address of the Basic Flash Parameter Table in the SFDP space / 基本 Flash 参数表在 SFDP 空间中的地址
+const BFPT_ADDRESS: usize = 0x80;
+
+/// SFDP header, one parameter header and the 16-DWORD BFPT (little-endian DWORDs).
+/// SFDP 头、一个参数头以及 16 个双字的 BFPT（小端双字）。
+const SFDP_HEADER: [u32; 4] = [
+    0x5044_4653, // "SFDP"
+    0xFF00_0105, // Rev 1.5, NPH = 0, access protocol FFh / 版本 1.5，NPH = 0，访问协议 FFh
+    0x1001_0500, // BFPT ID LSB 00h, rev 1.5, 16 DWORDs / BFPT ID 低字节 00h，版本 1.5，16 个双字
+    0xFF00_0080, // Table pointer 000080h, ID MSB FFh / 参数表指针 000080h，ID 高字节 FFh
+];
+const SFDP_BFPT: [u32; 16] = [
+    0xFFF9_20E5, // 4KB erase 20h, 1-1-2/1-2-2/1-4-4/1-1-4, 3-byte address / 4KB 擦除 20h，3 字节地址
+    0x07FF_FFFF, // 128Mbit
+    0x6B08_EB44, // 1-4-4 EBh (2 mode + 4 dummy), 1-1-4 6Bh (8 dummy)
+    0xBB42_3B08, // 1-1-2 3Bh (8 dummy), 1-2-2 BBh (2 mode + 2 dummy)
+    0xFFFF_FFEE, // No 2-2-2 / 4-4-4 / 不支持 2-2-2 / 4-4-4
+    0x0000_FFFF,
+    0x0000_FFFF,
+    0x520F_200C, // 4KB 20h, 32KB 52h
+    0x0000_D810, // 64KB D8h
+    0x00A5_3A23, // Erase 48ms/128ms/160ms typical, max x8 / 擦除典型 48ms/128ms/160ms，最大 x8
+    0x4914_E683, // 256B page, tPP 448us, tCE 40s typical, max x8 / 256B 页，tPP 448us，tCE 典型 40s，最大 x8
+    0xFFFF_FFFF,
+    0xFFFF_FFFF,
+    0xFFFF_FFFF,
+    0xFFFF_FFFF,
+    0xFFFF_FFFF,
+];
+
+/// Byte at `address` in the SFDP space of a part with `geometry`; undefined locations read 0xFF.
+/// 具有 `geometry` 的器件的 SFDP 空间中 `address` 处的字节；未定义的位置读取为 0xFF。
+fn sfdp_byte(address: usize, geometry: &FlashGeometry) -> u8 {
+    let mut bfpt = SFDP_BFPT;
+    // Density in bits minus one / 以位为单位的容量减一
+    bfpt[1] = (geometry.capacity * 8 - 1) as u32;
+    let dword = |table: &[u32], offset: usize| table.get(offset / 4).map(|dw| dw.to_le_bytes()[offset % 4]);
+    match address {
+        0..0x10 => dword(&SFDP_HEADER, address),
+        BFPT_ADDRESS.. => dword(&bfpt, address - BFPT_ADDRESS),
+        _ => None,
+    }
+    .unwrap_or(0xFF)
+}
+
+/// Simulated operation times / 模拟的操作时间
+///
+/// `Default` uses the typical values from the datasheet (Section 9.6 AC Electrical Characteristics).
+/// `Default` 使用数据手册中的典型值（第 9.6 节 AC 电气特性）。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SimulatedTiming {
+    /// Page Program time (tPP) / 页面编程时间 (tPP)
+    pub page_program: Duration,
+    /// 4KB Sector Erase time (tSE) / 4KB 扇区擦除时间 (tSE)
+    pub sector_erase: Duration,
+    /// 32KB Block Erase time (tBE1) / 32KB 块擦除时间 (tBE1)
+    pub block_erase_32k: Duration,
+    /// 64KB Block Erase time (tBE2) / 64KB 块擦除时间 (tBE2)
+    pub block_erase_64k: Duration,
+    /// Chip Erase time (tCE) / 整片擦除时间 (tCE)
+    pub chip_erase: Duration,
+    /// Non-volatile Write Status Register time (tW) / 非易失写状态寄存器时间 (tW)
+    pub status_write: Duration,
+}
+
+impl SimulatedTiming {
+    /// Every operation completes immediately / 所有操作立即完成
+    pub const ZERO: Self = Self {
+        page_program: Duration::from_ticks(0),
+        sector_erase: Duration::from_ticks(0),
+        block_erase_32k: Duration::from_ticks(0),
+        block_erase_64k: Duration::from_ticks(0),
+        chip_erase: Duration::from_ticks(0),
+        status_write: Duration::from_ticks(0),
+    };
+}
+
+impl Default for SimulatedTiming {
+    fn default() -> Self {
+        Self {
+            page_program: Duration::from_micros(400),
+            sector_erase: Duration::from_millis(45),
+            block_erase_32k: Duration::from_millis(120),
+            block_erase_64k: Duration::from_millis(150),
+            chip_erase: Duration::from_secs(40),
+            status_write: Duration::from_millis(10),
+        }
+    }
+}
+
+/// RAM-backed W25Q128JV simulator / 基于 RAM 的 W25Q128JV 模拟器
+///
+/// Implements `SpiDevice`; use it with `W25q128jv::new`. Each `transaction()` is one CS frame.
+/// 实现了 `SpiDevice`；配合 `W25q128jv::new` 使用。每次 `transaction()` 即一个 CS 周期。
+pub struct SimulatedW25q128 {
+    geometry: FlashGeometry,
+    memory: Vec<u8>,
+    timing: SimulatedTiming,
+    write_enabled: bool,
+    /// `50h` was sent; the next Write Status Register is volatile / 已发送 `50h`；下一次写状态寄存器为易失写入
+    volatile_sr_enabled: bool,
+    /// Writable bits of Status Registers 1-3 / 状态寄存器 1-3 的可写位
+    status_registers: [u8; 3],
+    /// Individual block/sector lock bits / 独立块/扇区锁定位
+    block_locks: BlockLockMap,
+    /// Security Registers 1-3 / 安全寄存器 1-3
+    security_registers: [[u8; SECURITY_REGISTER_SIZE]; 3],
+    /// Extended Address Register (A31-A24 in 3-byte address mode) / 扩展地址寄存器（3 字节地址模式下的 A31-A24）
+    extended_address: u8,
+    unique_id: u64,
+    busy_until: Instant,
+    /// The running operation can be suspended / 正在运行的操作可以被暂停
+    suspendable: bool,
+    /// Remaining time of a suspended operation (SUS = 1) / 被暂停操作的剩余时间（SUS = 1）
+    suspended: Option<Duration>,
+    /// In deep power-down (`B9h`) / 处于深度掉电（`B9h`）
+    powered_down: bool,
+    /// `66h` was sent; the next frame may be Reset Device / 已发送 `66h`；下一帧可以是复位器件
+    reset_enabled: bool,
+    /// Enable QPI (`38h`) is decoded / 解析启用 QPI（`38h`）
+    qpi_capable: bool,
+    /// Chip is in QPI mode / 芯片处于 QPI 模式
+    qpi: bool,
+    /// The bus sends the next frame on four lines (`QpiSpiDevice` rules) / 总线以四线发送下一帧（`QpiSpiDevice` 规则）
+    bus_qpi: bool,
+    /// The bus saw `66h` in the last frame / 总线在上一帧中看到了 `66h`
+    bus_reset_enabled: bool,
+    /// Set Read Parameters value (P5-P4 dummy clocks, P1-P0 wrap length) / 设置读取参数的值（P5-P4 虚拟时钟，P1-P0 回绕长度）
+    read_parameters: u8,
+    /// Continuous read mode (M5-4 = 10) / 连续读取模式（M5-4 = 10）
+    continuous_read: bool,
+    /// MOSI bytes received in the current CS frame / 当前 CS 周期内收到的 MOSI 字节
+    frame: Vec<u8>,
+    /// Leaked buffer behind the memory-mapped window, refreshed by every `map`
+    /// 内存映射窗口背后泄漏的缓冲区，每次 `map` 时刷新
+    mapped_window: Option<NonNull<[u8]>>,
+    /// Between `map` and `unmap` / 处于 `map` 与 `unmap` 之间
+    mapped: bool,
+}
+
+// SAFETY: `mapped_window` is only written by `map` through `&mut self`, and `map` refuses while a
+// slice it returned may still be in use (`mapped`)
+// SAFETY：`mapped_window` 只由 `map` 通过 `&mut self` 写入，且在其返回的切片可能仍在使用时（`mapped`）`map` 会拒绝
+unsafe impl Send for SimulatedW25q128 {}
+
+/// Simulator bus error / 模拟器总线错误
+#[derive(Debug, Clone, Copy, PartialEq, Eq, defmt::Format)]
+pub enum SimulatedBusError {
+    /// Transfer or `map` while the window is mapped / 窗口映射期间的传输或 `map`
+    MemoryMapped,
+    /// `map` of a window longer than the array / `map` 的窗口长度超出阵列
+    OutOfBounds,
+}
+
+impl embedded_hal::spi::Error for SimulatedBusError {
+    fn kind(&self) -> ErrorKind {
+        ErrorKind::Other
+    }
+}
+
+impl Default for SimulatedW25q128 {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SimulatedW25q128 {
+    /// Creates an erased (all 0xFF) device with typical datasheet timing.
+    /// 创建一个已擦除（全 0xFF）且使用数据手册典型时序的器件。
+    pub fn new() -> Self {
+        Self::with_timing(SimulatedTiming::default())
+    }
+
+    /// Creates an erased (all 0xFF) device with the given operation times.
+    /// 创建一个已擦除（全 0xFF）且使用给定操作时间的器件。
+    pub fn with_timing(timing: SimulatedTiming) -> Self {
+        Self::with_geometry(FlashGeometry::W25Q128, timing)
+    }
+
+    /// Creates an erased (all 0xFF) device that identifies as the part described by `geometry`.
+    /// 创建一个已擦除（全 0xFF）、并以 `geometry` 所描述的器件身份响应的器件。
+    pub fn with_geometry(geometry: FlashGeometry, timing: SimulatedTiming) -> Self {
+        Self {
+            geometry,
+            memory: vec![0xFF; geometry.capacity],
+            timing,
+            write_enabled: false,
+            volatile_sr_enabled: false,
+            // DRV1-DRV0 = 11 (25%) out of the factory / 出厂时 DRV1-DRV0 = 11 (25%)
+            status_registers: [0x00, 0x00, 0x60],
+            block_locks: BlockLockMap::locked(geometry),
+            security_registers: [[0xFF; SECURITY_REGISTER_SIZE]; 3],
+            extended_address: 0,
+            unique_id: 0xD2_6C_41_3B_17_0A_29_05,
+            busy_until: Instant::MIN,
+            suspendable: false,
+            suspended: None,
+            powered_down: false,
+            reset_enabled: false,
+            qpi_capable: false,
+            qpi: false,
+            bus_qpi: false,
+            bus_reset_enabled: false,
+            read_parameters: 0,
+            continuous_read: false,
+            frame: Vec::new(),
+            mapped_window: None,
+            mapped: false,
+        }
+    }
+
+    /// Raw view of the simulated array / 模拟存储阵列的原始视图
+    pub fn memory(&self) -> &[u8] {
+        &self.memory
+    }
+
+    /// Mutable raw view of the simulated array, e.g. to preload contents.
+    /// 模拟存储阵列的可变原始视图，例如用于预置内容。
+    pub fn memory_mut(&mut self) -> &mut [u8] {
+        &mut self.memory
+    }
+
+    /// Sets the 64-bit Unique ID returned by `4Bh` / 设置 `4Bh` 返回的 64 位唯一 ID
+    pub fn set_unique_id(&mut self, unique_id: u64) {
+        self.unique_id = unique_id;
+    }
+
+    /// Returns `true` while a program/erase operation is in progress.
+    /// 编程/擦除操作进行中时返回 `true`。
+    pub fn is_busy(&self) -> bool {
+        Instant::now() < self.busy_until
+    }
+
+    /// Returns `true` while in deep power-down / 处于深度掉电时返回 `true`
+    pub fn is_powered_down(&self) -> bool {
+        self.powered_down
+    }
+
+    /// Makes the chip decode Enable QPI (`38h`), like a W25Q128FV/JW / 使芯片解析启用 QPI（`38h`），与 W25Q128FV/JW 相同
+    pub fn set_qpi_capable(&mut self, capable: bool) {
+        self.qpi_capable = capable;
+    }
+
+    /// Returns `true` while the chip is in QPI mode / 芯片处于 QPI 模式时返回 `true`
+    pub fn is_qpi(&self) -> bool {
+        self.qpi
+    }
+
+    /// Returns the bus to single-line SPI, as an MCU reset would, while the chip keeps its state.
+    /// 像 MCU 复位那样将总线恢复为单线 SPI，芯片保持其状态不变。
+    pub fn host_reset(&mut self) {
+        self.bus_qpi = false;
+        self.bus_reset_enabled = false;
+    }
+
+    /// Status Register 1 as the chip would report it / 芯片会报告的状态寄存器1
+    fn status(&self) -> u8 {
+        self.status_registers[0] | (self.is_busy() as u8) | ((self.write_enabled as u8) << 1)
+    }
+
+    /// Clocks one byte: `mosi` is what the host sends, the return value is MISO.
+    /// 传输一个字节：`mosi` 为主机发送的数据，返回值为 MISO。
+    fn clock(&mut self, mosi: u8) -> u8 {
+        let pos = self.frame.len();
+        self.frame.push(mosi);
+        // Sent on the wrong number of lines, the frame is noise to the chip / 以错误线数发送的帧对芯片而言只是噪声
+        if pos == 0 || self.bus_qpi != self.qpi || self.continuous_read {
+            return 0xFF;
+        }
+        // Release Power-down / Device ID: the ID follows 3 dummy bytes / 释放掉电 / 设备 ID：3 个虚拟字节之后输出 ID
+        if self.frame[0] == 0xAB && !self.is_busy() {
+            return if pos >= 4 { self.geometry.jedec_id.2 - 1 } else { 0xFF };
+        }
+        // MISO stays high-impedance in power-down / 掉电时 MISO 保持高阻
+        if self.powered_down {
+            return 0xFF;
+        }
+        // Only Read Status is decoded while busy / 忙时只解析读状态命令
+        if (self.is_busy() && !matches!(self.frame[0], 0x05 | 0x35 | 0x15)) || !self.decodes(self.frame[0]) {
+            return 0xFF;
+        }
+        let n = self.address_len(self.frame[0]);
+        match self.frame[0] {
+            // Read JEDEC ID / 读取 JEDEC ID
+            0x9F => {
+                let (manufacturer, memory_type, capacity) = self.geometry.jedec_id;
+                // The W25Q128FV reports memory type 60h in QPI mode / W25Q128FV 在 QPI 模式下报告内存类型 60h
+                let memory_type = if self.qpi { 0x60 } else { memory_type };
+                [manufacturer, memory_type, capacity].get(pos - 1).copied().unwrap_or(0xFF)
+            }
+            // Read Status Register 1 (repeats continuously) / 读取状态寄存器1（连续重复输出）
+            0x05 => self.status(),
+            // Read Status Registers 2 and 3 / 读取状态寄存器2和3
+            0x35 => self.status_registers[1] | ((self.suspended.is_some() as u8) << 7),
+            0x15 => self.status_registers[2],
+            // Manufacturer/Device ID: address bit 0 selects which comes first / 制造商/设备 ID：地址第 0 位决定先后顺序
+            0x90 if pos >= 4 => {
+                // The device ID is one below the JEDEC capacity ID (17h for W25Q128) / 设备 ID 比 JEDEC 容量 ID 小一（W25Q128 为 17h）
+                let ids = [self.geometry.jedec_id.0, self.geometry.jedec_id.2 - 1];
+                ids[(pos - 4 + (self.frame[3] & 0x01) as usize) % 2]
+            }
+            // Unique ID: 8 bytes after 4 dummy bytes / 唯一 ID：4 个虚拟字节之后的 8 个字节
+            0x4B if pos >= 5 => self.unique_id.to_be_bytes().get(pos - 5).copied().unwrap_or(0xFF),
+            // SFDP: data after 3 address bytes + 1 dummy byte / SFDP：3 个地址字节 + 1 个虚拟字节之后输出数据
+            0x5A if pos >= 5 => sfdp_byte(frame_address(&self.frame) + pos - 5, &self.geometry),
+            // Read Block Lock: bit 0 of the addressed unit / 读取块锁定：所寻址单元的第 0 位
+            0x3D if pos > n => self.block_locks.is_locked(self.array_address(&self.frame) as u32) as u8,
+            // Read Data: data starts after the address / 读取数据：地址之后开始输出数据
+            0x03 | 0x13 if pos > n => self.read_at(self.array_address(&self.frame) + pos - 1 - n),
+            // Fast Read: data starts after the address + dummy bytes / 快速读取：地址 + 虚拟字节之后开始输出数据
+            0x0B | 0x0C if pos > n + self.dummy_bytes() => {
+                self.read_at(self.array_address(&self.frame) + pos - 1 - n - self.dummy_bytes())
+            }
+            // Read Security Register: data after the address + 1 dummy byte, wrapping inside the register
+            // 读取安全寄存器：地址 + 1 个虚拟字节之后输出数据，在寄存器内回绕
+            0x48 if pos > n + 1 => match self.security_register(&self.frame) {
+                Some((index, offset)) => self.security_registers[index][(offset + pos - 2 - n) % SECURITY_REGISTER_SIZE],
+                None => 0xFF,
+            },
+            // Read Extended Address Register / 读取扩展地址寄存器
+            0xC8 => self.extended_address,
+            _ => 0xFF,
+        }
+    }
+
+    /// Returns `true` if the protection bits (WPS = 0) or block locks (WPS = 1) cover any of
+    /// `len` bytes at `address`.
+    /// 如果保护位（WPS = 0）或块锁定（WPS = 1）覆盖了 `address` 处 `len` 字节中的任何字节，返回 `true`。
+    fn is_protected(&self, address: usize, len: usize) -> bool {
+        if self.status_registers[2] & 0x04 != 0 {
+            return self.block_locks.overlaps(address as u32, len);
+        }
+        let (sr1, sr2) = (self.status_registers[0].into(), self.status_registers[1].into());
+        ProtectionRegion::from_status(sr1, sr2, &self.geometry).overlaps(address as u32, len, &self.geometry)
+    }
+
+    /// Returns `false` for the commands only parts above 16MB decode, and for those the current
+    /// interface mode (SPI or QPI) does not decode.
+    /// 对于仅容量超过 16MB 的器件才能解析的命令，以及当前接口模式（SPI 或 QPI）不解析的命令，返回 `false`。
+    fn decodes(&self, opcode: u8) -> bool {
+        const FOUR_BYTE_ONLY: [u8; 13] = [0x13, 0x0C, 0x3C, 0x6C, 0xBC, 0xEC, 0x12, 0x21, 0xDC, 0xB7, 0xE9, 0xC5, 0xC8];
+        const SPI_ONLY: [u8; 14] = [0x03, 0x13, 0x4B, 0x5A, 0x48, 0x42, 0x44, 0x38, 0x3B, 0x3C, 0x6B, 0x6C, 0xBB, 0xBC];
+        const QPI_ONLY: [u8; 1] = [0xC0];
+        let interface = if self.qpi { !SPI_ONLY.contains(&opcode) } else { !QPI_ONLY.contains(&opcode) };
+        interface && (self.geometry.address_bytes == 4 || !FOUR_BYTE_ONLY.contains(&opcode))
+    }
+
+    /// Dummy bytes of Fast Read: one in SPI mode, P5-P4 + 1 (two clocks each) in QPI mode.
+    /// 快速读取的虚拟字节数：SPI 模式下为一个，QPI 模式下为 P5-P4 + 1（每字节两个时钟）。
+    fn dummy_bytes(&self) -> usize {
+        if self.qpi {
+            ((self.read_parameters >> 4) & 0x03) as usize + 1
+        } else {
+            1
+        }
+    }
+
+    /// Address bytes after `opcode`: 4 for the dedicated 4-byte opcodes, and for the other array
+    /// commands in 4-byte address mode (ADS = 1); 3 otherwise.
+    /// `opcode` 之后的地址字节数：专用 4 字节操作码为 4，4 字节地址模式（ADS = 1）下的其他阵列命令也为 4；否则为 3。
+    fn address_len(&self, opcode: u8) -> usize {
+        let four_byte_mode = self.status_registers[2] & 0x01 != 0;
+        match opcode {
+            0x13 | 0x0C | 0x3C | 0x6C | 0xBC | 0xEC | 0x12 | 0x21 | 0xDC => 4,
+            0x03 | 0x0B | 0x3B | 0x6B | 0xBB | 0xEB | 0x02 | 0x20 | 0x52 | 0xD8 | 0x36 | 0x39 | 0x3D | 0x48 | 0x42 | 0x44 if four_byte_mode => 4,
+            _ => 3,
+        }
+    }
+
+    /// Data for a dual/quad read, or `None` if the chip would not drive the lines as expected.
+    /// 双线/四线读取返回的数据；如果芯片不会按预期驱动数据线，返回 `None`。
+    fn multi_io_read(&self, read: &MultiIoRead) -> Option<usize> {
+        // (address lines, data lines, mode bits, dummy clocks) per Datasheet Section 8.2.8-8.2.12
+        // 依据数据手册第 8.2.8-8.2.12 节：（地址线数、数据线数、模式位、虚拟时钟数）
+        let expected = match read.opcode {
+            0x0B | 0x0C => (1, 1, false, 8),
+            0x3B | 0x3C => (1, 2, false, 8),
+            0x6B | 0x6C => (1, 4, false, 8),
+            0xBB | 0xBC => (2, 2, true, 0),
+            0xEB | 0xEC => (4, 4, true, 4),
+            _ => return None,
+        };
+        let quad_enabled = self.status_registers[1] & 0x02 != 0;
+        let usable = !self.is_busy()
+            && !self.powered_down
+            && !self.qpi
+            && !self.bus_qpi
+            && self.decodes(read.opcode)
+            && read.address_bytes as usize == self.address_len(read.opcode)
+            && (read.address_lines, read.data_lines, read.mode_bits.is_some(), read.dummy_clocks) == expected
+            && (quad_enabled || read.data_lines < 4);
+        if !usable {
+            return None;
+        }
+        let mut frame = vec![read.opcode];
+        frame.extend_from_slice(&read.address.to_be_bytes()[4 - read.address_bytes as usize..]);
+        Some(self.array_address(&frame))
+    }
+
+    /// Array address after the opcode; the Extended Address Register supplies A31-A24 of 3-byte addresses.
+    /// 操作码之后的阵列地址；3 字节地址的 A31-A24 由扩展地址寄存器提供。
+    fn array_address(&self, frame: &[u8]) -> usize {
+        let n = self.address_len(frame[0]);
+        let address = frame[1..=n].iter().fold(0, |address, &byte| (address << 8) | byte as usize);
+        let address = if n == 3 { address | (self.extended_address as usize) << 24 } else { address };
+        address % self.geometry.capacity
+    }
+
+    /// Security register (0-2) and byte offset addressed by the frame; A15-A12 hold the register number 1-3.
+    /// 帧所寻址的安全寄存器（0-2）和字节偏移；A15-A12 为寄存器编号 1-3。
+    fn security_register(&self, frame: &[u8]) -> Option<(usize, usize)> {
+        let n = self.address_len(frame[0]);
+        let index = (frame[n - 1] >> 4) as usize;
+        (1..=3).contains(&index).then_some((index - 1, frame[n] as usize))
+    }
+
+    /// Returns `true` if the LB bit of security register `index` (0-2) is set.
+    /// 安全寄存器 `index`（0-2）的 LB 位置位时返回 `true`。
+    fn is_security_register_locked(&self, index: usize) -> bool {
+        self.status_registers[1] & (0x08 << index) != 0
+    }
+
+    /// Reads with wrap-around at the end of the array / 读取，到达阵列末尾后回绕
+    fn read_at(&self, address: usize) -> u8 {
+        self.memory[address % self.geometry.capacity]
+    }
+
+    /// Executes commands that take effect when CS goes high.
+    /// 执行在 CS 拉高时生效的命令。
+    fn end_frame(&mut self) {
+        let frame = core::mem::take(&mut self.frame);
+        // Exit QPI always travels on four lines / 退出 QPI 总是通过四线传输
+        let heard = self.bus_qpi == self.qpi || frame == [0xFF];
+        // The bus follows the mode-switch commands it sends / 总线跟随其发送的模式切换命令
+        match (core::mem::take(&mut self.bus_reset_enabled), frame.as_slice()) {
+            (_, [0x38]) => self.bus_qpi = true,
+            (_, [0xFF]) => self.bus_qpi = false,
+            (_, [0x66]) => self.bus_reset_enabled = true,
+            (true, [0x99]) => self.bus_qpi = false,
+            _ => {}
+        }
+        if !heard {
+            return;
+        }
+        // The frame is an address; M4 = IO0 = 1 ends continuous read (a lone `FFh` only lasts two clocks)
+        // 该帧被视为地址；M4 = IO0 = 1 结束连续读取（单独的 `FFh` 只持续两个时钟）
+        if self.continuous_read {
+            self.continuous_read = !(frame.len() >= 2 && frame[0] == 0xFF);
+            return;
+        }
+        if self.powered_down {
+            self.powered_down = frame.first() != Some(&0xAB);
+            return;
+        }
+        // `66h` only enables the frame right after it / `66h` 只对紧随其后的帧有效
+        match (core::mem::take(&mut self.reset_enabled), frame.as_slice()) {
+            (_, [0x66]) => {
+                self.reset_enabled = true;
+                return;
+            }
+            (true, [0x99]) => {
+                self.reset();
+                return;
+            }
+            _ => {}
+        }
+        match frame.as_slice() {
+            // Erase/Program Suspend: only while a suspendable operation runs / 擦除/编程暂停：仅在可暂停的操作运行时有效
+            [0x75] if self.is_busy() && self.suspendable => {
+                self.suspended = Some(self.busy_until.saturating_duration_since(Instant::now()));
+                self.busy_until = Instant::now();
+                return;
+            }
+            // Erase/Program Resume / 擦除/编程恢复
+            [0x7A] if !self.is_busy() => {
+                if let Some(remaining) = self.suspended.take() {
+                    self.busy_until = Instant::now() + remaining;
+                }
+                return;
+            }
+            _ => {}
+        }
+        if frame.is_empty() || self.is_busy() || !self.decodes(frame[0]) {
+            return;
+        }
+        // Nothing that programs or erases is accepted while suspended / 暂停期间不接受任何编程或擦除命令
+        const WRITES: [u8; 18] = [0x01, 0x31, 0x11, 0x02, 0x12, 0x20, 0x21, 0x52, 0xD8, 0xDC, 0xC7, 0x60, 0x36, 0x39, 0x7E, 0x98, 0x42, 0x44];
+        if self.suspended.is_some() && WRITES.contains(&frame[0]) {
+            return;
+        }
+        let n = self.address_len(frame[0]);
+        // `50h` only applies to the command right after it / `50h` 只对紧随其后的命令有效
+        let volatile = core::mem::take(&mut self.volatile_sr_enabled);
+        match frame[0] {
+            0x06 if frame.len() == 1 => self.write_enabled = true, // Write Enable / 写使能
+            0x04 if frame.len() == 1 => self.write_enabled = false, // Write Disable / 写禁止
+            0x50 if frame.len() == 1 => self.volatile_sr_enabled = true, // Volatile SR Write Enable / 易失性状态寄存器写使能
+            0xB9 if frame.len() == 1 => self.powered_down = true, // Power-down / 掉电
+            // Enable QPI needs QE = 1; Exit QPI is an incomplete instruction in SPI mode
+            // 启用 QPI 需要 QE = 1；退出 QPI 在 SPI 模式下是不完整的指令
+            0x38 if frame.len() == 1 => self.qpi = self.qpi_capable && self.status_registers[1] & 0x02 != 0,
+            0xFF if frame.len() == 1 => self.qpi = false,
+            0xC0 if frame.len() == 2 => self.read_parameters = frame[1], // Set Read Parameters / 设置读取参数
+            // Write Status Register 1 (a second byte also writes SR2) / 写状态寄存器1（第二个字节同时写入状态寄存器2）
+            0x01 if frame.len() == 2 || frame.len() == 3 => self.write_status(&[0, 1], &frame[1..], volatile),
+            0x31 if frame.len() == 2 => self.write_status(&[1], &frame[1..], volatile),
+            0x11 if frame.len() == 2 => self.write_status(&[2], &frame[1..], volatile),
+            0x02 | 0x12 if frame.len() > 1 + n => {
+                let address = self.array_address(&frame);
+                self.page_program(address, &frame[1 + n..]);
+            }
+            0x20 | 0x21 if frame.len() == 1 + n => {
+                self.erase(self.array_address(&frame), SECTOR_SIZE, self.timing.sector_erase)
+            }
+            0x52 if frame.len() == 1 + n => {
+                self.erase(self.array_address(&frame), BLOCK_32K_SIZE, self.timing.block_erase_32k)
+            }
+            0xD8 | 0xDC if frame.len() == 1 + n => {
+                self.erase(self.array_address(&frame), BLOCK_64K_SIZE, self.timing.block_erase_64k)
+            }
+            0xC7 | 0x60 if frame.len() == 1 => self.erase(0, self.geometry.capacity, self.timing.chip_erase),
+            // Individual / global block locks / 独立/全局块锁定
+            0x36 | 0x39 if frame.len() == 1 + n && core::mem::take(&mut self.write_enabled) => {
+                self.block_locks.set_address(self.array_address(&frame) as u32, frame[0] == 0x36)
+            }
+            0x7E if frame.len() == 1 && core::mem::take(&mut self.write_enabled) => {
+                self.block_locks = BlockLockMap::locked(self.geometry)
+            }
+            0x98 if frame.len() == 1 && core::mem::take(&mut self.write_enabled) => {
+                self.block_locks = BlockLockMap::unlocked(self.geometry)
+            }
+            // Program/Erase Security Register / 编程/擦除安全寄存器
+            0x42 if frame.len() > 1 + n => {
+                if let Some((index, offset)) = self.security_register(&frame) {
+                    self.program_security_register(index, offset, &frame[1 + n..]);
+                }
+            }
+            0x44 if frame.len() == 1 + n => {
+                if let Some((index, _)) = self.security_register(&frame) {
+                    self.erase_security_register(index);
+                }
+            }
+            // 4-byte address mode (SR3 ADS) and Extended Address Register / 4 字节地址模式（SR3 ADS）和扩展地址寄存器
+            0xB7 if frame.len() == 1 => self.status_registers[2] |= 0x01,
+            0xE9 if frame.len() == 1 => self.status_registers[2] &= !0x01,
+            0xC5 if frame.len() == 2 && core::mem::take(&mut self.write_enabled) => self.extended_address = frame[1],
+            _ => {}
+        }
+    }
+
+    /// Marks the device busy for `time` / 将器件标记为忙 `time` 时长
+    fn start_busy(&mut self, time: Duration, suspendable: bool) {
+        self.busy_until = Instant::now() + time;
+        self.suspendable = suspendable;
+    }
+
+    /// Page Program: only clears bits and wraps around inside the page.
+    /// 页面编程：只清零位，并在页面内回绕。
+    fn page_program(&mut self, address: usize, data: &[u8]) {
+        if !core::mem::take(&mut self.write_enabled) {
+            return;
+        }
+        // Bytes past the end of the page wrap to its start and overwrite earlier ones in the page latch
+        // 超出页面末尾的字节回绕到页面开头，并覆盖页面锁存器中先前的数据
+        let mut latch = [0xFFu8; PAGE_SIZE];
+        for (i, &byte) in data.iter().enumerate() {
+            latch[(address + i) % PAGE_SIZE] = byte;
+        }
+        let page = address & !(PAGE_SIZE - 1);
+        if self.is_protected(page, PAGE_SIZE) {
+            return;
+        }
+        for (cell, byte) in self.memory[page..page + PAGE_SIZE].iter_mut().zip(latch) {
+            *cell &= byte;
+        }
+        self.start_busy(self.timing.page_program, true);
+    }
+
+    /// Program Security Register: only clears bits, wraps inside the register and is ignored once locked.
+    /// 编程安全寄存器：只清零位，在寄存器内回绕，锁定后被忽略。
+    fn program_security_register(&mut self, index: usize, offset: usize, data: &[u8]) {
+        if !core::mem::take(&mut self.write_enabled) || self.is_security_register_locked(index) {
+            return;
+        }
+        for (i, &byte) in data.iter().enumerate() {
+            self.security_registers[index][(offset + i) % SECURITY_REGISTER_SIZE] &= byte;
+        }
+        self.start_busy(self.timing.page_program, true);
+    }
+
+    /// Erase Security Register: sets the whole register to 0xFF unless locked.
+    /// 擦除安全寄存器：未锁定时将整个寄存器置为 0xFF。
+    fn erase_security_register(&mut self, index: usize) {
+        if !core::mem::take(&mut self.write_enabled) || self.is_security_register_locked(index) {
+            return;
+        }
+        self.security_registers[index] = [0xFF; SECURITY_REGISTER_SIZE];
+        self.start_busy(self.timing.sector_erase, true);
+    }
+
+    /// Write Status Register: updates the writable bits of the registers in `index`.
+    /// 写状态寄存器：更新 `index` 中各寄存器的可写位。
+    fn write_status(&mut self, index: &[usize], values: &[u8], volatile: bool) {
+        // Writable bits of SR1/SR2/SR3; ADP only exists above 16MB / SR1/SR2/SR3 的可写位；ADP 仅存在于容量超过 16MB 的器件
+        let adp = if self.geometry.address_bytes == 4 { 0x02 } else { 0x00 };
+        let writable = [0xFC, 0x43, 0x64 | adp];
+        const LOCK_BITS: u8 = 0x38; // LB1-LB3 (OTP) / LB1-LB3（一次性可编程）
+        if !volatile && !core::mem::take(&mut self.write_enabled) {
+            return;
+        }
+        for (&i, &value) in index.iter().zip(values) {
+            let register = &mut self.status_registers[i];
+            let mut new = (*register & !writable[i]) | (value & writable[i]);
+            if i == 1 {
+                new |= (*register | value) & LOCK_BITS;
+            }
+            *register = new;
+        }
+        if !volatile {
+            self.start_busy(self.timing.status_write, false);
+        }
+    }
+
+    /// Software reset: aborts the running operation and restores the volatile state.
+    /// 软件复位：中止正在运行的操作并恢复易失性状态。
+    fn reset(&mut self) {
+        self.busy_until = Instant::MIN;
+        self.suspended = None;
+        self.write_enabled = false;
+        self.volatile_sr_enabled = false;
+        self.block_locks = BlockLockMap::locked(self.geometry);
+        self.extended_address = 0;
+        self.qpi = false;
+        self.read_parameters = 0;
+        // ADS takes the ADP default / ADS 取 ADP 的默认值
+        let adp = (self.status_registers[2] >> 1) & 0x01;
+        self.status_registers[2] = (self.status_registers[2] & !0x01) | adp;
+    }
+
+    fn erase(&mut self, address: usize, size: usize, time: Duration) {
+        if !core::mem::take(&mut self.write_enabled) {
+            return;
+        }
+        // The chip ignores the low address bits / 芯片忽略低位地址
+        let start = address & !(size - 1);
+        if self.is_protected(start, size) {
+            return;
+        }
+        self.memory[start..start + size].fill(0xFF);
+        // Chip Erase cannot be suspended / 整片擦除不能被暂停
+        self.start_busy(time, size < self.geometry.capacity);
+    }
+}
+
+/// 24-bit address following the opcode; the frame must hold at least 4 bytes.
+/// 操作码之后的 24 位地址；帧至少需要包含 4 个字节。
+fn frame_address(frame: &[u8]) -> usize {
+    ((frame[1] as usize) << 16) | ((frame[2] as usize) << 8) | frame[3] as usize
+}
+
+impl ErrorType for SimulatedW25q128 {
+    type Error = SimulatedBusError;
+}
+
+impl SimulatedW25q128 {
+    /// Rejects transfers while mapped, like a controller serving the window / 映射期间拒绝传输，与响应窗口的控制器一致
+    fn check_unmapped(&self) -> Result<(), SimulatedBusError> {
+        if self.mapped {
+            Err(SimulatedBusError::MemoryMapped)
+        } else {
+            Ok(())
+        }
+    }
+}
+
+impl embedded_hal_async::spi::SpiDevice for SimulatedW25q128 {
+    async fn transaction(&mut self, operations: &mut [Operation<'_, u8>]) -> Result<(), Self::Error> {
+        self.check_unmapped()?;
+        for op in operations {
+            match op {
+                Operation::Read(buf) => buf.iter_mut().for_each(|b| *b = self.clock(0x00)),
+                Operation::Write(data) => data.iter().for_each(|&b| {
+                    self.clock(b);
+                }),
+                Operation::Transfer(read, write) => {
+                    for i in 0..read.len().max(write.len()) {
+                        let miso = self.clock(write.get(i).copied().unwrap_or(0x00));
+                        if let Some(r) = read.get_mut(i) {
+                            *r = miso;
+                        }
+                    }
+                }
+                Operation::TransferInPlace(buf) => buf.iter_mut().for_each(|b| *b = self.clock(*b)),
+                Operation::DelayNs(_) => {}
+            }
+        }
+        self.end_frame();
+        Ok(())
+    }
+}
+
+impl MultiIoSpiDevice for SimulatedW25q128 {
+    async fn read_multi_io(&mut self, read: MultiIoRead, buf: &mut [u8]) -> Result<(), Self::Error> {
+        self.check_unmapped()?;
+        match self.multi_io_read(&read) {
+            Some(address) => buf.iter_mut().enumerate().for_each(|(i, b)| *b = self.read_at(address + i)),
+            None => buf.fill(0xFF),
+        }
+        self.reset_enabled = false;
+        Ok(())
+    }
+}
+
+impl QpiSpiDevice for SimulatedW25q128 {}
+
+impl MemoryMappedSpiDevice for SimulatedW25q128 {
+    fn map(&mut self, read: MultiIoRead, len: usize) -> Result<&'static [u8], Self::Error> {
+        self.check_unmapped()?;
+        let capacity = self.geometry.capacity;
+        if len > capacity {
+            return Err(SimulatedBusError::OutOfBounds);
+        }
+        // Leaked once per simulator, so repeated mapping does not leak the array again
+        // 每个模拟器只泄漏一次，重复映射不会再次泄漏整个阵列
+        let mut buffer = *self.mapped_window.get_or_insert_with(|| NonNull::from(vec![0xFF; capacity].leak()));
+        // SAFETY: the buffer is never freed, and no slice from an earlier `map` is live: `map`
+        // refuses until `unmap`, whose caller guarantees those slices are no longer used
+        // SAFETY：缓冲区永不释放，且不存在先前 `map` 返回的存活切片：在 `unmap` 之前 `map` 会拒绝，
+        // 而 `unmap` 的调用者保证这些切片不再使用
+        let window = unsafe { &mut buffer.as_mut()[..len] };
+        match self.multi_io_read(&read) {
+            Some(address) => window.iter_mut().enumerate().for_each(|(i, b)| *b = self.read_at(address + i)),
+            None => window.fill(0xFF),
+        }
+        self.continuous_read |= read.mode_bits.is_some_and(|bits| bits & 0x30 == 0x20);
+        self.mapped = true;
+        // SAFETY: as above / 同上
+        Ok(unsafe { &buffer.as_ref()[..len] })
+    }
+
+    unsafe fn unmap(&mut self) -> Result<(), Self::Error> {
+        self.mapped = false;
+        Ok(())
+    }
+}
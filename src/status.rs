@@ -0,0 +1,152 @@
+// src/status.rs
+
+//! Typed Status Registers / 类型化的状态寄存器
+//!
+//! Decoded views of Status Registers 1, 2 and 3 (per Datasheet Section 7.1). Each register
+//! converts from and into its raw byte with `From`; reserved bits read as 0 and are written as 0.
+//! 状态寄存器 1、2、3 的解码视图（依据数据手册第 7.1 节）。每个寄存器都可通过 `From` 与原始字节互相转换；
+//! 保留位读取为 0，写入时也为 0。
+
+/// Status Register 1 (`05h` read, `01h` write) / 状态寄存器1（`05h` 读取，`01h` 写入）
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, defmt::Format)]
+pub struct StatusRegister1 {
+    /// S0 BUSY: program/erase/status write in progress (read-only) / 编程/擦除/写状态寄存器进行中（只读）
+    pub busy: bool,
+    /// S1 WEL: Write Enable Latch (read-only) / 写使能锁存（只读）
+    pub wel: bool,
+    /// S4-S2 BP2-BP0: Block Protect bits / 块保护位
+    pub bp: u8,
+    /// S5 TB: protect from the Top (0) or Bottom (1) / 从顶部 (0) 或底部 (1) 开始保护
+    pub tb: bool,
+    /// S6 SEC: protect 4KB sectors (1) or 64KB blocks (0) / 按 4KB 扇区 (1) 或 64KB 块 (0) 保护
+    pub sec: bool,
+    /// S7 SRP: Status Register Protect / 状态寄存器保护
+    pub srp: bool,
+}
+
+impl From<u8> for StatusRegister1 {
+    fn from(bits: u8) -> Self {
+        Self {
+            busy: bits & 0x01 != 0,
+            wel: bits & 0x02 != 0,
+            bp: (bits >> 2) & 0x07,
+            tb: bits & 0x20 != 0,
+            sec: bits & 0x40 != 0,
+            srp: bits & 0x80 != 0,
+        }
+    }
+}
+
+impl From<StatusRegister1> for u8 {
+    fn from(sr: StatusRegister1) -> Self {
+        (sr.busy as u8)
+            | ((sr.wel as u8) << 1)
+            | ((sr.bp & 0x07) << 2)
+            | ((sr.tb as u8) << 5)
+            | ((sr.sec as u8) << 6)
+            | ((sr.srp as u8) << 7)
+    }
+}
+
+/// Status Register 2 (`35h` read, `31h` write) / 状态寄存器2（`35h` 读取，`31h` 写入）
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, defmt::Format)]
+pub struct StatusRegister2 {
+    /// S8 SRL: Status Register Lock / 状态寄存器锁定
+    pub srl: bool,
+    /// S9 QE: Quad Enable / 四线使能
+    pub qe: bool,
+    /// S11-S13 LB1-LB3: Security Register Lock bits, one-time programmable (`lb[0]` is LB1)
+    /// 安全寄存器锁定位，一次性可编程（`lb[0]` 为 LB1）
+    pub lb: [bool; 3],
+    /// S14 CMP: Complement Protect / 保护区域取反
+    pub cmp: bool,
+    /// S15 SUS: Erase/Program Suspend Status (read-only) / 擦除/编程暂停状态（只读）
+    pub sus: bool,
+}
+
+impl From<u8> for StatusRegister2 {
+    fn from(bits: u8) -> Self {
+        Self {
+            srl: bits & 0x01 != 0,
+            qe: bits & 0x02 != 0,
+            lb: [bits & 0x08 != 0, bits & 0x10 != 0, bits & 0x20 != 0],
+            cmp: bits & 0x40 != 0,
+            sus: bits & 0x80 != 0,
+        }
+    }
+}
+
+impl From<StatusRegister2> for u8 {
+    fn from(sr: StatusRegister2) -> Self {
+        (sr.srl as u8)
+            | ((sr.qe as u8) << 1)
+            | ((sr.lb[0] as u8) << 3)
+            | ((sr.lb[1] as u8) << 4)
+            | ((sr.lb[2] as u8) << 5)
+            | ((sr.cmp as u8) << 6)
+            | ((sr.sus as u8) << 7)
+    }
+}
+
+/// Output driver strength (DRV1-DRV0) / 输出驱动强度 (DRV1-DRV0)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, defmt::Format)]
+pub enum DriveStrength {
+    /// DRV = 00
+    Percent100,
+    /// DRV = 01
+    Percent75,
+    /// DRV = 10
+    Percent50,
+    /// DRV = 11 (factory default / 出厂默认)
+    #[default]
+    Percent25,
+}
+
+/// Status Register 3 (`15h` read, `11h` write) / 状态寄存器3（`15h` 读取，`11h` 写入）
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, defmt::Format)]
+pub struct StatusRegister3 {
+    /// S16 ADS: current address mode is 4-byte (read-only, parts above 16MB)
+    /// 当前地址模式为 4 字节（只读，容量超过 16MB 的器件）
+    pub ads: bool,
+    /// S17 ADP: power up in 4-byte address mode (parts above 16MB)
+    /// 上电后进入 4 字节地址模式（容量超过 16MB 的器件）
+    pub adp: bool,
+    /// S18 WPS: Write Protect Selection (0 = BP/TB/SEC/CMP, 1 = individual block locks)
+    /// 写保护方式选择（0 = BP/TB/SEC/CMP，1 = 独立块锁定）
+    pub wps: bool,
+    /// S22-S21 DRV1-DRV0: Output Driver Strength / 输出驱动强度
+    pub drv: DriveStrength,
+}
+
+impl From<u8> for StatusRegister3 {
+    fn from(bits: u8) -> Self {
+        let drv = match (bits >> 5) & 0x03 {
+            0 => DriveStrength::Percent100,
+            1 => DriveStrength::Percent75,
+            2 => DriveStrength::Percent50,
+            _ => DriveStrength::Percent25,
+        };
+        Self { ads: bits & 0x01 != 0, adp: bits & 0x02 != 0, wps: bits & 0x04 != 0, drv }
+    }
+}
+
+impl From<StatusRegister3> for u8 {
+    fn from(sr: StatusRegister3) -> Self {
+        (sr.ads as u8) | ((sr.adp as u8) << 1) | ((sr.wps as u8) << 2) | ((sr.drv as u8) << 5)
+    }
+}
+
+/// Whether a Status Register write survives power-down / 状态寄存器写入是否在掉电后保留
+///
+/// `NonVolatile` uses Write Enable (`06h`) and takes up to tW (15ms); `Volatile` uses
+/// Write Enable for Volatile Status Register (`50h`), takes effect immediately and does
+/// not wear the non-volatile bits.
+/// `NonVolatile` 使用写使能 (`06h`)，最多耗时 tW (15ms)；`Volatile` 使用易失性状态寄存器写使能
+/// (`50h`)，立即生效且不会磨损非易失位。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, defmt::Format)]
+pub enum StatusWriteMode {
+    /// Persist the new value / 持久保存新值
+    NonVolatile,
+    /// Change the value until the next power cycle / 修改值直到下次上电
+    Volatile,
+}
@@ -0,0 +1,54 @@
+// src/stm32.rs
+
+//! STM32 Constructors / STM32 构造函数
+//!
+//! Thin wrappers that build an SPI device from an `embassy_stm32::spi::Spi` and an
+//! `Output` CS pin, for boards that do not manage CS through a shared-bus device.
+//! 由 `embassy_stm32::spi::Spi` 和 `Output` CS 引脚构建 SPI 设备的轻量封装，
+//! 适用于不通过共享总线设备管理 CS 的板子。
+
+use embassy_stm32::{gpio::Output, mode, spi::Spi};
+use embassy_time::Delay;
+use embedded_hal_bus::spi::ExclusiveDevice;
+
+use crate::{blocking::BlockingSpi, W25q128jv};
+
+/// SPI device built from an STM32 SPI peripheral and a GPIO CS pin.
+/// 由 STM32 SPI 外设和 GPIO CS 引脚构建的 SPI 设备。
+pub type Stm32SpiDevice<'d, M> = ExclusiveDevice<Spi<'d, M>, Output<'d>, Delay>;
+
+impl<'d> W25q128jv<Stm32SpiDevice<'d, mode::Async>> {
+    /// Creates a new driver instance from an async (DMA) STM32 SPI peripheral and CS pin.
+    /// 使用异步（DMA）STM32 SPI 外设和 CS 引脚创建新的驱动实例。
+    ///
+    /// Data phases are awaited DMA transfers, so other tasks keep running during large reads and writes.
+    /// 数据阶段使用可等待的 DMA 传输，大数据量读写期间其他任务可以继续运行。
+    ///
+    /// # Arguments / 参数
+    /// * `spi`: A configured SPI instance created with `Spi::new` (TX/RX DMA channels). / 使用 `Spi::new`（带 TX/RX DMA 通道）创建并配置好的 SPI 实例。
+    /// * `cs`: A GPIO output pin for /CS. / 用于 /CS 的 GPIO 输出引脚。
+    ///
+    pub fn new_stm32(spi: Spi<'d, mode::Async>, cs: Output<'d>) -> Self {
+        // Setting an STM32 GPIO can't fail / STM32 GPIO 设置不会失败
+        let Ok(device) = ExclusiveDevice::new(spi, cs, Delay);
+        Self::new(device)
+    }
+}
+
+impl<'d> W25q128jv<BlockingSpi<Stm32SpiDevice<'d, mode::Blocking>>> {
+    /// Creates a new driver instance from a blocking STM32 SPI peripheral and CS pin.
+    /// 使用阻塞式 STM32 SPI 外设和 CS 引脚创建新的驱动实例。
+    ///
+    /// Transfers block the executor; prefer `new_stm32` when DMA channels are available.
+    /// 传输会阻塞执行器；如有 DMA 通道，建议使用 `new_stm32`。
+    ///
+    /// # Arguments / 参数
+    /// * `spi`: A configured SPI instance created with `Spi::new_blocking`. / 使用 `Spi::new_blocking` 创建并配置好的 SPI 实例。
+    /// * `cs`: A GPIO output pin for /CS. / 用于 /CS 的 GPIO 输出引脚。
+    ///
+    pub fn new_stm32_blocking(spi: Spi<'d, mode::Blocking>, cs: Output<'d>) -> Self {
+        // Setting an STM32 GPIO can't fail / STM32 GPIO 设置不会失败
+        let Ok(device) = ExclusiveDevice::new(spi, cs, Delay);
+        Self::new_blocking(device)
+    }
+}
@@ -2,8 +2,9 @@
 
 //! W25Q128JV SPI Flash Driver / W25Q128JV SPI 闪存驱动
 //!
-//! Based on `embassy-stm32` and `embedded-hal`.
-//! 基于 `embassy-stm32` 和 `embedded-hal`。
+//! Generic over `embedded-hal-async` `SpiDevice`, so CS handling, bus sharing and
+//! transactions are provided by the HAL.
+//! 基于 `embedded-hal-async` 的 `SpiDevice` 泛型实现，CS 控制、总线共享和事务由 HAL 提供。
 //!
 //! Implements basic operations for the Winbond W25Q128JV Flash chip.
 //! 实现了对 Winbond W25Q128JV Flash 芯片的基本操作。
@@ -14,13 +15,21 @@
 //! 确保 `/WP (IO2)` 和 `/HOLD or /RESET (IO3)` 引脚在标准 SPI 模式下被拉高
 //! （例如，通过 10kΩ 电阻连接到 VCC）。
 
-use embassy_stm32::{
-    gpio::Output,
-    spi::{self, Spi},
-    mode,
-};
-use embassy_time::Timer;
-use embedded_hal::spi::SpiBus;
+use core::ops::Range;
+
+use embassy_time::{Duration, Instant, Timer};
+use embedded_hal_async::spi::{Operation, SpiDevice};
+use embedded_storage::nor_flash::{NorFlashError, NorFlashErrorKind};
+
+use crate::blocking::BlockingSpi;
+use crate::geometry::FlashGeometry;
+use crate::memory_mapped::MemoryMappedSpiDevice;
+use crate::multi_io::{MultiIoRead, MultiIoSpiDevice, ReadMode};
+use crate::qpi::{QpiSpiDevice, ReadParameters};
+use crate::protection::{BlockLockMap, ProtectionRegion, WriteProtection};
+use crate::security::{security_register_address, OtpLockConfirmation};
+use crate::sfdp::{ParameterHeader, SfdpParameters, BFPT_ID, BFPT_MAX_DWORDS, SFDP_SIGNATURE};
+use crate::status::{StatusRegister1, StatusRegister2, StatusRegister3, StatusWriteMode};
 
 // --- Public Constants / 公共常量 ---
 
@@ -30,8 +39,18 @@ pub const JEDEC_MAN_ID: u8 = 0xEF;
 pub const JEDEC_MEM_TYPE: u8 = 0x40;
 /// W25Q128JV Expected JEDEC Capacity ID / W25Q128JV 预期的 JEDEC 容量 ID
 pub const JEDEC_CAPACITY: u8 = 0x18;
+/// W25Q128JV Device ID returned by Manufacturer/Device ID (90h) / W25Q128JV 制造商/设备 ID (90h) 返回的设备 ID
+pub const DEVICE_ID: u8 = 0x17;
+/// W25Q128JV Page Size (256B) / W25Q128JV 页面大小 (256B)
+pub const PAGE_SIZE: usize = 256;
 /// W25Q128JV Sector Size (4KB) / W25Q128JV 扇区大小 (4KB)
 pub const SECTOR_SIZE: usize = 4096;
+/// W25Q128JV 32KB Block Size / W25Q128JV 32KB 块大小
+pub const BLOCK_32K_SIZE: usize = 32 * 1024;
+/// W25Q128JV 64KB Block Size / W25Q128JV 64KB 块大小
+pub const BLOCK_64K_SIZE: usize = 64 * 1024;
+/// W25Q128JV Total Capacity (16MB) / W25Q128JV 总容量 (16MB)
+pub const FLASH_SIZE: usize = 16 * 1024 * 1024;
 // 可以根据需要添加更多常量，例如页面大小、块大小等
 
 // --- Command Definitions / 命令定义 ---
@@ -39,88 +58,739 @@ pub const SECTOR_SIZE: usize = 4096;
 /// W25Q128JV 命令定义（依据数据手册第 8.1 节）
 mod commands {
     pub const READ_ID: u8 = 0x9F;              // Read JEDEC ID / 读取JEDEC ID
+    pub const MANUFACTURER_DEVICE_ID: u8 = 0x90; // Manufacturer/Device ID / 制造商/设备 ID
+    pub const READ_UNIQUE_ID: u8 = 0x4B;       // Read Unique ID Number / 读取唯一 ID
+    pub const READ_SFDP: u8 = 0x5A;            // Read SFDP Register / 读取 SFDP 寄存器
     pub const READ_STATUS_REG_1: u8 = 0x05;    // Read Status Register 1 / 读取状态寄存器1
+    pub const READ_STATUS_REG_2: u8 = 0x35;    // Read Status Register 2 / 读取状态寄存器2
+    pub const READ_STATUS_REG_3: u8 = 0x15;    // Read Status Register 3 / 读取状态寄存器3
+    pub const WRITE_STATUS_REG_1: u8 = 0x01;   // Write Status Register 1 / 写状态寄存器1
+    pub const WRITE_STATUS_REG_2: u8 = 0x31;   // Write Status Register 2 / 写状态寄存器2
+    pub const WRITE_STATUS_REG_3: u8 = 0x11;   // Write Status Register 3 / 写状态寄存器3
     pub const WRITE_ENABLE: u8 = 0x06;         // Write Enable (required before write/erase) / 写使能（写入/擦除前必需）
+    pub const WRITE_ENABLE_VOLATILE_SR: u8 = 0x50; // Write Enable for Volatile Status Register / 易失性状态寄存器写使能
     pub const READ_DATA: u8 = 0x03;            // Standard Read / 标准读取
     pub const FAST_READ: u8 = 0x0B;            // Fast Read / 快速读取
+    pub const FAST_READ_DUAL_OUTPUT: u8 = 0x3B; // Fast Read Dual Output / 双线输出快速读取
+    pub const FAST_READ_QUAD_OUTPUT: u8 = 0x6B; // Fast Read Quad Output / 四线输出快速读取
+    pub const FAST_READ_DUAL_IO: u8 = 0xBB;    // Fast Read Dual I/O / 双线 I/O 快速读取
+    pub const FAST_READ_QUAD_IO: u8 = 0xEB;    // Fast Read Quad I/O / 四线 I/O 快速读取
     pub const PAGE_PROGRAM: u8 = 0x02;         // Page Program / 页面编程
-    pub const SECTOR_ERASE: u8 = 0xD8;         // 4KB Sector Erase / 4KB 扇区擦除
-    // 可以根据需要添加更多命令，例如芯片擦除、块擦除等
+    pub const SECTOR_ERASE: u8 = 0x20;         // 4KB Sector Erase / 4KB 扇区擦除
+    pub const BLOCK_ERASE_32K: u8 = 0x52;      // 32KB Block Erase / 32KB 块擦除
+    pub const BLOCK_ERASE_64K: u8 = 0xD8;      // 64KB Block Erase / 64KB 块擦除
+    pub const CHIP_ERASE: u8 = 0xC7;           // Chip Erase (0x60 is equivalent) / 整片擦除（0x60 等效）
+    pub const SUSPEND: u8 = 0x75;              // Erase/Program Suspend / 擦除/编程暂停
+    pub const RESUME: u8 = 0x7A;               // Erase/Program Resume / 擦除/编程恢复
+    pub const POWER_DOWN: u8 = 0xB9;           // Power-down / 掉电
+    pub const RELEASE_POWER_DOWN: u8 = 0xAB;   // Release Power-down / Device ID / 释放掉电 / 设备 ID
+    pub const ENABLE_RESET: u8 = 0x66;         // Enable Reset / 复位使能
+    pub const RESET_DEVICE: u8 = 0x99;         // Reset Device / 复位器件
+    pub const ENTER_QPI: u8 = 0x38;            // Enable QPI Mode / 启用 QPI 模式
+    pub const EXIT_QPI: u8 = 0xFF;             // Exit QPI Mode / 退出 QPI 模式
+    pub const SET_READ_PARAMETERS: u8 = 0xC0;  // Set Read Parameters (QPI) / 设置读取参数（QPI）
+    pub const INDIVIDUAL_BLOCK_LOCK: u8 = 0x36;   // Individual Block/Sector Lock / 独立块/扇区锁定
+    pub const INDIVIDUAL_BLOCK_UNLOCK: u8 = 0x39; // Individual Block/Sector Unlock / 独立块/扇区解锁
+    pub const READ_BLOCK_LOCK: u8 = 0x3D;      // Read Block/Sector Lock / 读取块/扇区锁定状态
+    pub const GLOBAL_BLOCK_LOCK: u8 = 0x7E;    // Global Block/Sector Lock / 全局块/扇区锁定
+    pub const GLOBAL_BLOCK_UNLOCK: u8 = 0x98;  // Global Block/Sector Unlock / 全局块/扇区解锁
+    pub const READ_SECURITY_REGISTER: u8 = 0x48;    // Read Security Registers / 读取安全寄存器
+    pub const PROGRAM_SECURITY_REGISTER: u8 = 0x42; // Program Security Registers / 编程安全寄存器
+    pub const ERASE_SECURITY_REGISTER: u8 = 0x44;   // Erase Security Registers / 擦除安全寄存器
+    // Parts above 16MB / 容量超过 16MB 的器件
+    pub const ENTER_4BYTE_ADDRESS_MODE: u8 = 0xB7; // Enter 4-Byte Address Mode / 进入 4 字节地址模式
+    pub const EXIT_4BYTE_ADDRESS_MODE: u8 = 0xE9;  // Exit 4-Byte Address Mode / 退出 4 字节地址模式
+    pub const READ_EXTENDED_ADDRESS: u8 = 0xC8;    // Read Extended Address Register / 读取扩展地址寄存器
+    pub const WRITE_EXTENDED_ADDRESS: u8 = 0xC5;   // Write Extended Address Register / 写扩展地址寄存器
+    pub const READ_DATA_4B: u8 = 0x13;         // Read Data with 4-Byte Address / 4 字节地址读取
+    pub const FAST_READ_4B: u8 = 0x0C;         // Fast Read with 4-Byte Address / 4 字节地址快速读取
+    pub const PAGE_PROGRAM_4B: u8 = 0x12;      // Page Program with 4-Byte Address / 4 字节地址页面编程
+    pub const SECTOR_ERASE_4B: u8 = 0x21;      // 4KB Sector Erase with 4-Byte Address / 4 字节地址 4KB 扇区擦除
+    pub const BLOCK_ERASE_64K_4B: u8 = 0xDC;   // 64KB Block Erase with 4-Byte Address / 4 字节地址 64KB 块擦除
+    pub const FAST_READ_DUAL_OUTPUT_4B: u8 = 0x3C; // Fast Read Dual Output with 4-Byte Address / 4 字节地址双线输出快速读取
+    pub const FAST_READ_QUAD_OUTPUT_4B: u8 = 0x6C; // Fast Read Quad Output with 4-Byte Address / 4 字节地址四线输出快速读取
+    pub const FAST_READ_DUAL_IO_4B: u8 = 0xBC; // Fast Read Dual I/O with 4-Byte Address / 4 字节地址双线 I/O 快速读取
+    pub const FAST_READ_QUAD_IO_4B: u8 = 0xEC; // Fast Read Quad I/O with 4-Byte Address / 4 字节地址四线 I/O 快速读取
+
+    /// Dedicated 4-byte address variant of `cmd`, if the chip has one / `cmd` 的专用 4 字节地址版本（如果芯片提供）
+    pub fn four_byte(cmd: u8) -> Option<u8> {
+        match cmd {
+            READ_DATA => Some(READ_DATA_4B),
+            FAST_READ => Some(FAST_READ_4B),
+            PAGE_PROGRAM => Some(PAGE_PROGRAM_4B),
+            SECTOR_ERASE => Some(SECTOR_ERASE_4B),
+            BLOCK_ERASE_64K => Some(BLOCK_ERASE_64K_4B),
+            FAST_READ_DUAL_OUTPUT => Some(FAST_READ_DUAL_OUTPUT_4B),
+            FAST_READ_QUAD_OUTPUT => Some(FAST_READ_QUAD_OUTPUT_4B),
+            FAST_READ_DUAL_IO => Some(FAST_READ_DUAL_IO_4B),
+            FAST_READ_QUAD_IO => Some(FAST_READ_QUAD_IO_4B),
+            _ => None,
+        }
+    }
+}
+
+/// Address width taken by commands without a dedicated 4-byte opcode (SR3 ADS).
+/// 没有专用 4 字节操作码的命令所使用的地址宽度（SR3 ADS）。
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, defmt::Format)]
+pub enum AddressMode {
+    /// 24-bit addresses; A31-A24 come from the Extended Address Register / 24 位地址；A31-A24 取自扩展地址寄存器
+    #[default]
+    ThreeByte,
+    /// 32-bit addresses / 32 位地址
+    FourByte,
+}
+
+/// Power state tracked by the driver / 驱动跟踪的电源状态
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, defmt::Format)]
+pub enum PowerState {
+    /// Accepting commands / 正在接受命令
+    #[default]
+    Active,
+    /// Deep power-down (`B9h`); only Release Power-down (`ABh`) is decoded
+    /// 深度掉电（`B9h`）；只解析释放掉电命令（`ABh`）
+    PowerDown,
+}
+
+/// Command interface tracked by the driver / 驱动跟踪的命令接口
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, defmt::Format)]
+pub enum InterfaceMode {
+    /// Standard SPI: instructions on IO0 / 标准 SPI：指令通过 IO0 传输
+    #[default]
+    Spi,
+    /// QPI (`38h`): instruction, address and data on IO0-IO3 / QPI（`38h`）：指令、地址和数据通过 IO0-IO3 传输
+    Qpi,
+}
+
+/// Recovery steps `init` needed before the chip returned a valid JEDEC ID.
+/// `init` 在芯片返回有效 JEDEC ID 之前所需的恢复步骤。
+///
+/// Each step is only tried if the ID read after the previous one was still not a known W25Q part,
+/// in this order. All `false` means the chip answered straight away.
+/// 仅当上一步之后读到的 ID 仍不是已知的 W25Q 器件时，才按此顺序尝试下一步。全部为 `false` 表示芯片直接应答。
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, defmt::Format)]
+pub struct RecoverySteps {
+    /// Exit QPI Mode (`FFh`) was sent / 已发送退出 QPI 模式（`FFh`）
+    pub exit_qpi: bool,
+    /// Continuous Read Mode Reset (16 clocks of `FFh`) was sent / 已发送连续读取模式复位（16 个时钟的 `FFh`）
+    pub exit_continuous_read: bool,
+    /// Release Power-down (`ABh`) was sent / 已发送释放掉电（`ABh`）
+    pub release_power_down: bool,
+    /// A program or erase was still running (SR1 BUSY) and was waited for, up to the chip erase
+    /// timeout, instead of being aborted / 编程或擦除仍在进行（SR1 BUSY），已等待其完成（最长为整片擦除超时），而非中止
+    pub wait_busy: bool,
+    /// Enable Reset + Reset Device (`66h` + `99h`) were sent / 已发送复位使能 + 复位器件（`66h` + `99h`）
+    pub software_reset: bool,
+}
+
+impl RecoverySteps {
+    /// Returns `true` if any recovery step was needed / 需要任何恢复步骤时返回 `true`
+    pub fn any(&self) -> bool {
+        self.exit_qpi || self.exit_continuous_read || self.release_power_down || self.wait_busy || self.software_reset
+    }
+}
+
+// --- Error Type / 错误类型 ---
+/// W25Q128JV Driver Error / W25Q128JV 驱动错误
+///
+/// Wraps the bus error `E` of the underlying SPI device and adds driver-level failures.
+/// 包装底层 SPI 设备的总线错误 `E`，并增加驱动层面的错误。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, defmt::Format)]
+pub enum Error<E> {
+    /// SPI bus error / SPI 总线错误
+    Spi(E),
+    /// Address or length is outside the flash array / 地址或长度超出 Flash 范围
+    OutOfBounds,
+    /// Address is not aligned to the erase/program unit, or a page program crosses a page boundary
+    /// 地址未按擦除/编程单元对齐，或页面编程跨越了页面边界
+    NotAligned,
+    /// Device stayed busy longer than the allowed time / 设备忙状态超时
+    Timeout,
+    /// Program/erase target lies in a write-protected region / 编程/擦除目标位于写保护区域
+    Protected,
+    /// The protection table has no entry for the requested range / 保护表中没有与请求范围对应的条目
+    InvalidProtectionRange,
+    /// SFDP signature or JEDEC Basic Flash Parameter Table missing or malformed
+    /// SFDP 签名或 JEDEC 基本 Flash 参数表缺失或格式错误
+    InvalidSfdp,
+    /// SFDP density disagrees with the part detected from the JEDEC ID
+    /// SFDP 中的容量与根据 JEDEC ID 检测到的器件不一致
+    GeometryMismatch,
+    /// Not supported by the part, or not available in the current interface mode (e.g. Read
+    /// Unique ID in QPI mode, any command while memory-mapped, or a protection region while
+    /// individual block locks are in use)
+    /// 器件不支持，或在当前接口模式下不可用（例如 QPI 模式下的读取唯一 ID、内存映射期间的任何命令，
+    /// 或使用独立块锁定时设置保护区域）
+    Unsupported,
+    /// JEDEC ID does not match the expected (or any supported) part / JEDEC ID 与预期（或任何受支持的）器件不匹配
+    UnexpectedJedecId {
+        /// Manufacturer ID read back / 读取到的制造商 ID
+        manufacturer: u8,
+        /// Memory type read back / 读取到的内存类型
+        memory_type: u8,
+        /// Capacity ID read back / 读取到的容量 ID
+        capacity: u8,
+    },
+}
+
+impl<E> From<E> for Error<E> {
+    fn from(e: E) -> Self {
+        Error::Spi(e)
+    }
+}
+
+impl<E: core::fmt::Debug> NorFlashError for Error<E> {
+    fn kind(&self) -> NorFlashErrorKind {
+        match self {
+            Error::OutOfBounds => NorFlashErrorKind::OutOfBounds,
+            Error::NotAligned => NorFlashErrorKind::NotAligned,
+            _ => NorFlashErrorKind::Other,
+        }
+    }
+}
+
+// --- Driver Configuration / 驱动配置 ---
+/// W25Q128JV Driver Configuration / W25Q128JV 驱动配置
+///
+/// Busy-wait timeouts per operation. `Default` uses the datasheet maximums
+/// (Datasheet Section 9.6 AC Electrical Characteristics).
+/// 各操作的忙等待超时时间。`Default` 使用数据手册中的最大值（数据手册第 9.6 节 AC 电气特性）。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, defmt::Format)]
+pub struct Config {
+    /// Page Program timeout (tPP max 3ms) / 页面编程超时 (tPP 最大 3ms)
+    pub page_program_timeout: Duration,
+    /// 4KB Sector Erase timeout (tSE max 400ms) / 4KB 扇区擦除超时 (tSE 最大 400ms)
+    pub sector_erase_timeout: Duration,
+    /// 32KB/64KB Block Erase timeout (tBE2 max 2s) / 32KB/64KB 块擦除超时 (tBE2 最大 2s)
+    pub block_erase_timeout: Duration,
+    /// Chip Erase timeout (tCE max 200s) / 整片擦除超时 (tCE 最大 200s)
+    pub chip_erase_timeout: Duration,
+    /// Write Status Register timeout (tW max 15ms) / 写状态寄存器超时 (tW 最大 15ms)
+    pub status_write_timeout: Duration,
+    /// Timeout for an operation still in progress before a new command is issued.
+    /// Defaults to the chip erase maximum, the longest operation the device can be busy with.
+    /// 发出新命令前等待先前操作完成的超时时间。默认为整片擦除最大值，即器件可能处于忙状态的最长时间。
+    pub idle_timeout: Duration,
+    /// Time for a suspend to take effect (tSUS max 20µs) / 暂停生效所需的时间 (tSUS 最大 20µs)
+    pub suspend_latency: Duration,
+    /// Minimum run time after a resume before the next suspend (tRS), so a long erase keeps
+    /// making progress while reads keep interrupting it.
+    /// 恢复后到下一次暂停前的最短运行时间 (tRS)，保证频繁的读取打断时长时间的擦除仍能继续推进。
+    pub resume_to_suspend: Duration,
+    /// Cross-check the detected capacity against SFDP in `init` / 在 `init` 中用 SFDP 交叉校验检测到的容量
+    pub verify_sfdp: bool,
+    /// Time to enter deep power-down after `B9h` (tDP max 3µs) / 发送 `B9h` 后进入深度掉电所需的时间 (tDP 最大 3µs)
+    pub power_down_latency: Duration,
+    /// Time to leave deep power-down after `ABh` (tRES1 max 3µs) / 发送 `ABh` 后退出深度掉电所需的时间 (tRES1 最大 3µs)
+    pub release_latency: Duration,
+    /// Time for a software reset to complete (tRST max 30µs) / 软件复位完成所需的时间 (tRST 最大 30µs)
+    pub reset_latency: Duration,
+    /// Enter deep power-down once the chip has been idle this long; `None` keeps it awake.
+    /// Applied by `SharedFlash::run_auto_power_down`, or by calling `power_down_if_idle`.
+    /// 芯片空闲达到该时长后进入深度掉电；`None` 表示保持唤醒。
+    /// 由 `SharedFlash::run_auto_power_down` 执行，或通过调用 `power_down_if_idle` 执行。
+    pub auto_power_down: Option<Duration>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            page_program_timeout: Duration::from_millis(3),
+            sector_erase_timeout: Duration::from_millis(400),
+            block_erase_timeout: Duration::from_secs(2),
+            chip_erase_timeout: Duration::from_secs(200),
+            status_write_timeout: Duration::from_millis(15),
+            idle_timeout: Duration::from_secs(200),
+            suspend_latency: Duration::from_micros(20),
+            resume_to_suspend: Duration::from_micros(200),
+            verify_sfdp: false,
+            power_down_latency: Duration::from_micros(3),
+            release_latency: Duration::from_micros(3),
+            reset_latency: Duration::from_micros(30),
+            auto_power_down: None,
+        }
+    }
+}
+
+/// Status polling interval for an operation with the given timeout: 1/200 of it,
+/// kept between 10µs and 100ms.
+/// 给定超时时间的操作所用的状态轮询间隔：超时时间的 1/200，限制在 10µs 到 100ms 之间。
+fn poll_interval(timeout: Duration) -> Duration {
+    (timeout / 200).clamp(Duration::from_micros(10), Duration::from_millis(100))
+}
+
+/// Erase started with `start_erase_*` that has not been seen to finish yet.
+/// 通过 `start_erase_*` 启动、尚未确认完成的擦除操作。
+#[derive(Debug, Clone, Copy)]
+struct PendingErase {
+    address: u32,
+    size: usize,
+    timeout: Duration,
+    /// Suspended by this driver (SUS = 1) / 已被本驱动暂停（SUS = 1）
+    suspended: bool,
+}
+
+impl PendingErase {
+    /// Returns `true` if any of `len` bytes at `address` lies in the block being erased.
+    /// 如果 `address` 处 `len` 字节中有任何字节位于正在擦除的块内，返回 `true`。
+    fn overlaps(&self, address: u32, len: usize) -> bool {
+        let end = address as u64 + len as u64;
+        len > 0 && (address as u64) < self.address as u64 + self.size as u64 && end > self.address as u64
+    }
 }
 
 // --- Driver Struct / 驱动结构体 ---
 /// W25Q128JV Driver Instance / W25Q128JV 驱动实例
 ///
-/// Represents a connection to a W25Q128JV Flash chip via SPI.
-/// 代表通过 SPI 连接到 W25Q128JV Flash 芯片的实例。
-pub struct W25q128jv<'d, M: mode::Mode> {
-    spi: Spi<'d, M>,
-    cs: Output<'d>,
+/// Represents a connection to a W25Q128JV Flash chip via an SPI device.
+/// 代表通过 SPI 设备连接到 W25Q128JV Flash 芯片的实例。
+///
+/// `SPI` is any `embedded_hal_async::spi::SpiDevice`; each command is issued as one
+/// CS-framed transaction.
+/// `SPI` 可以是任意 `embedded_hal_async::spi::SpiDevice`；每条命令作为一次 CS 包围的事务发出。
+pub struct W25q128jv<SPI> {
+    spi: SPI,
+    config: Config,
+    geometry: FlashGeometry,
+    address_mode: AddressMode,
+    /// Cached Extended Address Register, `None` if unknown / 缓存的扩展地址寄存器，未知时为 `None`
+    extended_address: Option<u8>,
+    /// Cached write protection, `None` until read back from the chip / 缓存的写保护设置，从芯片读回前为 `None`
+    protection: Option<WriteProtection>,
+    /// Erase running in the background / 在后台运行的擦除操作
+    pending_erase: Option<PendingErase>,
+    /// Time of the last resume, for tRS / 上一次恢复的时间，用于 tRS
+    last_resume: Instant,
+    power_state: PowerState,
+    /// Cached QE bit, `None` until read back from the chip / 缓存的 QE 位，从芯片读回前为 `None`
+    quad_enabled: Option<bool>,
+    /// Non-volatile SR2 while a volatile SR2 write by this driver is in effect, `None` while SR2
+    /// reads back its non-volatile value
+    /// 本驱动的易失性 SR2 写入生效期间的非易失 SR2 值；SR2 读回的就是非易失值时为 `None`
+    nonvolatile_sr2: Option<StatusRegister2>,
+    /// End of the last SPI transaction, for `Config::auto_power_down` / 上一次 SPI 事务结束的时间，用于 `Config::auto_power_down`
+    last_activity: Instant,
+    interface: InterfaceMode,
+    /// Read parameters sent on entering QPI mode / 进入 QPI 模式时发送的读取参数
+    read_parameters: ReadParameters,
+    /// Window the backend serves reads from, while memory-mapped / 内存映射期间后端响应读取的窗口
+    mapped_window: Option<&'static [u8]>,
 }
 
 // --- Driver Implementation / 驱动实现 ---
-impl<'d, M: mode::Mode> W25q128jv<'d, M> {
+impl<SPI: embedded_hal::spi::SpiDevice> W25q128jv<BlockingSpi<SPI>> {
+    /// Creates a new driver instance from a blocking `embedded_hal::spi::SpiDevice`.
+    /// 使用阻塞式 `embedded_hal::spi::SpiDevice` 创建新的驱动实例。
+    ///
+    /// # Arguments / 参数
+    /// * `spi`: A blocking SPI device (bus + CS). / 阻塞式 SPI 设备（总线 + CS）。
+    ///
+    pub fn new_blocking(spi: SPI) -> Self {
+        Self::new(BlockingSpi::new(spi))
+    }
+}
+
+impl<SPI: SpiDevice> W25q128jv<SPI> {
     /// Creates a new W25Q128JV driver instance.
     /// 创建一个新的 W25Q128JV 驱动实例。
     ///
     /// # Arguments / 参数
-    /// * `spi`: A configured SPI instance. / 已配置好的 SPI 实例。
-    /// * `cs`: A GPIO output pin for /CS. / 用于 /CS 的 GPIO 输出引脚。
+    /// * `spi`: An SPI device that owns the /CS line. / 管理 /CS 线的 SPI 设备。
+    ///
+    pub fn new(spi: SPI) -> Self {
+        Self::new_with_config(spi, Config::default())
+    }
+
+    /// Creates a new W25Q128JV driver instance with a custom configuration.
+    /// 使用自定义配置创建一个新的 W25Q128JV 驱动实例。
+    ///
+    /// # Arguments / 参数
+    /// * `spi`: An SPI device that owns the /CS line. / 管理 /CS 线的 SPI 设备。
+    /// * `config`: Busy-wait timeouts. / 忙等待超时配置。
+    ///
+    pub fn new_with_config(spi: SPI, config: Config) -> Self {
+        Self {
+            spi,
+            config,
+            geometry: FlashGeometry::W25Q128,
+            address_mode: AddressMode::ThreeByte,
+            extended_address: None,
+            protection: None,
+            pending_erase: None,
+            last_resume: Instant::MIN,
+            power_state: PowerState::Active,
+            quad_enabled: None,
+            nonvolatile_sr2: None,
+            last_activity: Instant::MIN,
+            interface: InterfaceMode::Spi,
+            read_parameters: ReadParameters::default(),
+            mapped_window: None,
+        }
+    }
+
+    /// Returns the current configuration / 返回当前配置
+    pub fn config(&self) -> &Config {
+        &self.config
+    }
+
+    /// Replaces the configuration / 替换配置
+    pub fn set_config(&mut self, config: Config) {
+        self.config = config;
+    }
+
+    /// Returns the geometry of the detected part (W25Q128 until `init` has run).
+    /// 返回检测到的器件几何参数（`init` 运行前为 W25Q128）。
+    pub fn geometry(&self) -> &FlashGeometry {
+        &self.geometry
+    }
+
+    /// Returns the tracked address mode / 返回跟踪的地址模式
+    pub fn address_mode(&self) -> AddressMode {
+        self.address_mode
+    }
+
+    /// Returns the tracked power state / 返回跟踪的电源状态
+    pub fn power_state(&self) -> PowerState {
+        self.power_state
+    }
+
+    /// Returns the tracked command interface / 返回跟踪的命令接口
+    pub fn interface_mode(&self) -> InterfaceMode {
+        self.interface
+    }
+
+    /// Returns the read parameters used in QPI mode / 返回 QPI 模式下使用的读取参数
+    pub fn read_parameters(&self) -> ReadParameters {
+        self.read_parameters
+    }
+
+    /// Returns `true` between `enter_memory_mapped` and `exit_memory_mapped` / 在 `enter_memory_mapped` 和 `exit_memory_mapped` 之间返回 `true`
+    pub fn is_memory_mapped(&self) -> bool {
+        self.mapped_window.is_some()
+    }
+
+    /// Releases the underlying SPI device.
+    /// 释放底层 SPI 设备。
     ///
-    pub fn new(spi: Spi<'d, M>, cs: Output<'d>) -> Self {
-        Self { spi, cs }
+    /// Returns the driver unchanged while memory-mapped, since the backend still serves the
+    /// window returned by `enter_memory_mapped`.
+    /// 内存映射期间原样返回驱动，因为后端仍在响应 `enter_memory_mapped` 返回的窗口。
+    // Handed back by value: there is no allocator to box it / 按值返回：没有分配器可用于装箱
+    #[allow(clippy::result_large_err)]
+    pub fn release(self) -> Result<SPI, Self> {
+        if self.is_memory_mapped() {
+            return Err(self);
+        }
+        Ok(self.spi)
     }
 
-    /// Initializes the device: ensures CS transitions from high to low (per Datasheet Section 4.1).
-    /// 初始化设备：确保CS经历高->低跳变（依据数据手册第4.1节）。
+    /// Initializes the device: ensures CS transitions from high to low (per Datasheet Section 4.1),
+    /// then identifies the part from its JEDEC ID and fills in `geometry()`.
+    /// 初始化设备：确保CS经历高->低跳变（依据数据手册第4.1节），然后根据 JEDEC ID 识别器件并填充 `geometry()`。
+    ///
+    /// An MCU reset can leave the chip in QPI mode, in continuous read mode, powered down or in the
+    /// middle of a program. While the JEDEC ID is not a known part, `init` exits QPI, exits continuous
+    /// read, releases power-down, waits for a running program/erase to finish and finally issues a
+    /// software reset, reading the ID after each step, and returns the steps that were needed.
+    /// The reset, which would leave the interrupted page or sector undefined, is only sent if BUSY
+    /// does not clear within `Config::chip_erase_timeout`.
+    /// MCU 复位可能使芯片停留在 QPI 模式、连续读取模式、掉电状态或编程过程中。只要 JEDEC ID 不是已知器件，
+    /// `init` 就依次退出 QPI、退出连续读取、释放掉电、等待正在进行的编程/擦除完成，最后发出软件复位，
+    /// 每一步之后都重新读取 ID，并返回实际需要的步骤。软件复位会使被中断的页或扇区内容不确定，
+    /// 因此仅在 BUSY 未能在 `Config::chip_erase_timeout` 内清零时才发送。
     ///
-    /// This step is often required for Flash chips to wake up or enter a known state.
-    /// 这个步骤对于某些 Flash 芯片是必需的，用于唤醒或进入已知状态。
-    pub async fn init(&mut self) {
-        // Force CS high (deselected) / 强制CS为高电平（未选中状态）
-        self.cs.set_high();
+    /// Returns `Error::UnexpectedJedecId` for parts other than W25Q16/32/64/128/256/512 JV/JW/FV,
+    /// and `Error::GeometryMismatch` if `Config::verify_sfdp` is set and SFDP reports another size.
+    /// 对于 W25Q16/32/64/128/256/512 JV/JW/FV 以外的器件返回 `Error::UnexpectedJedecId`；
+    /// 如果设置了 `Config::verify_sfdp` 且 SFDP 报告的容量不同，返回 `Error::GeometryMismatch`。
+    ///
+    /// Parts above 16MB are then addressed with 4 bytes: read, program, sector and 64KB block erase
+    /// use their dedicated 4-byte opcodes, and the remaining address commands follow the address
+    /// mode read back from SR3 (ADS).
+    /// 之后对容量超过 16MB 的器件使用 4 字节寻址：读取、编程、扇区和 64KB 块擦除使用专用的 4 字节操作码，
+    /// 其余地址命令遵循从 SR3 (ADS) 读回的地址模式。
+    pub async fn init(&mut self) -> Result<RecoverySteps, Error<SPI::Error>> {
+        // CS is deselected by the SPI device between transactions / SPI 设备在事务之间保持 CS 为高电平
         Timer::after_micros(10).await; // Wait for stability / 等待稳定
-        // Generate high->low transition to activate the device / 产生高->低跳变，激活设备
-        self.cs.set_low();
-        Timer::after_micros(10).await; // Wait tCHSL (Datasheet 9.5 AC Characteristics) / 等待 tCHSL (数据手册 9.5 AC Characteristics)
-        self.cs.set_high();
+        // Generate high->low->high transition without clocks to activate the device
+        // 产生不带时钟的高->低->高跳变，激活设备
+        // Hold CS low for tCHSL (Datasheet 9.5 AC Characteristics) / 保持 CS 低电平 tCHSL (数据手册 9.5 AC Characteristics)
+        self.transaction(&mut [Operation::DelayNs(10_000)]).await?;
         Timer::after_micros(10).await; // Wait tSHSL1/SHSL2 (Datasheet 9.5 AC Characteristics) / 等待 tSHSL1/SHSL2 (数据手册 9.5 AC Characteristics)
-        // Note: Logging here might not be available in a library context.
-        // 注意：实际库中可能不直接打印日志。
-        // info!("Device initialized, CS pin activated");
+
+        // Identify the part, recovering from a leftover state if needed / 识别器件，必要时从遗留状态中恢复
+        let mut steps = RecoverySteps::default();
+        let mut id = self.read_jedec_id().await?;
+        for step in 0..5 {
+            if FlashGeometry::from_jedec_id(id.0, id.1, id.2).is_some() {
+                break;
+            }
+            match step {
+                0 => {
+                    self.command(commands::EXIT_QPI).await?;
+                    self.interface = InterfaceMode::Spi;
+                    steps.exit_qpi = true;
+                }
+                1 => {
+                    // Mode bits M5-4 != 10 on IO0 for up to 16 clocks / 在 IO0 上输入最多 16 个时钟，使模式位 M5-4 != 10
+                    self.transaction(&mut [Operation::Write(&[0xFF; 2])]).await?;
+                    steps.exit_continuous_read = true;
+                }
+                2 => {
+                    self.release_from_power_down().await?;
+                    steps.release_power_down = true;
+                }
+                3 => {
+                    // 9Fh is ignored while busy, but 05h is still decoded / 忙时忽略 9Fh，但仍解析 05h
+                    if self.is_busy().await? {
+                        steps.wait_busy = true;
+                        match self.wait_idle(self.config.chip_erase_timeout).await {
+                            Ok(()) | Err(Error::Timeout) => {}
+                            Err(e) => return Err(e),
+                        }
+                    }
+                }
+                _ => {
+                    self.software_reset().await?;
+                    steps.software_reset = true;
+                }
+            }
+            id = self.read_jedec_id().await?;
+        }
+        let (manufacturer, memory_type, capacity) = id;
+        let geometry = FlashGeometry::from_jedec_id(manufacturer, memory_type, capacity)
+            .ok_or(Error::UnexpectedJedecId { manufacturer, memory_type, capacity })?;
+        if self.config.verify_sfdp && self.read_sfdp_parameters().await?.capacity != geometry.capacity as u64 {
+            return Err(Error::GeometryMismatch);
+        }
+        self.geometry = geometry;
+        self.protection = None; // Protection layout depends on the geometry / 保护布局取决于几何参数
+        self.extended_address = None;
+        self.quad_enabled = None;
+        self.address_mode = self.read_address_mode().await?;
+        Ok(steps)
     }
 
     // --- Private Helper Functions / 私有辅助函数 ---
 
+    /// Checks that `len` bytes starting at `address` lie inside the flash array.
+    /// 检查从 `address` 开始的 `len` 字节是否位于 Flash 范围内。
+    fn check_bounds(&self, address: u32, len: usize) -> Result<(), Error<SPI::Error>> {
+        if self.geometry.contains(address, len) {
+            Ok(())
+        } else {
+            Err(Error::OutOfBounds)
+        }
+    }
+
+    /// Rejects commands the chip only decodes in SPI mode / 拒绝芯片仅在 SPI 模式下解析的命令
+    fn check_spi_mode(&self) -> Result<(), Error<SPI::Error>> {
+        match self.interface {
+            InterfaceMode::Spi => Ok(()),
+            InterfaceMode::Qpi => Err(Error::Unsupported),
+        }
+    }
+
+    /// Rejects commands while the backend serves the memory-mapped window / 在后端响应内存映射窗口期间拒绝命令
+    fn check_unmapped(&self) -> Result<(), Error<SPI::Error>> {
+        if self.is_memory_mapped() {
+            Err(Error::Unsupported)
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Reads the address mode from SR3 (ADS) on parts above 16MB / 在容量超过 16MB 的器件上从 SR3 (ADS) 读取地址模式
+    async fn read_address_mode(&mut self) -> Result<AddressMode, Error<SPI::Error>> {
+        if self.geometry.address_bytes == 4 && self.read_status_register_3().await?.ads {
+            Ok(AddressMode::FourByte)
+        } else {
+            Ok(AddressMode::ThreeByte)
+        }
+    }
+
+    /// Releases the chip from deep power-down if the driver put it there.
+    /// 如果芯片是被本驱动置于深度掉电的，使其退出掉电。
+    async fn wake(&mut self) -> Result<(), Error<SPI::Error>> {
+        if self.power_state == PowerState::PowerDown {
+            self.release_from_power_down().await?;
+        }
+        Ok(())
+    }
+
+    /// Runs one CS-framed transaction, first releasing the chip from deep power-down if needed.
+    /// 执行一次 CS 包围的事务；如有需要，先使芯片退出深度掉电。
+    async fn transaction(&mut self, operations: &mut [Operation<'_, u8>]) -> Result<(), Error<SPI::Error>> {
+        self.check_unmapped()?;
+        self.wake().await?;
+        self.spi.transaction(operations).await?;
+        self.last_activity = Instant::now();
+        Ok(())
+    }
+
     /// Sends a single-byte command with no data.
     /// 发送单字节命令（无数据）。
-    async fn command(&mut self, cmd: u8) -> Result<(), spi::Error> {
-        self.cs.set_low();
-        let result = self.spi.write(&[cmd]);
-        self.cs.set_high();
-        result
+    async fn command(&mut self, cmd: u8) -> Result<(), Error<SPI::Error>> {
+        self.transaction(&mut [Operation::Write(&[cmd])]).await
     }
 
     /// Sends a command and reads a single-byte response.
     /// 发送命令并读取响应（1字节）。
-    async fn command_read_byte(&mut self, cmd: u8) -> Result<u8, spi::Error> {
-        self.cs.set_low();
-        self.spi.write(&[cmd])?; // Send command / 发送命令
+    async fn command_read_byte(&mut self, cmd: u8) -> Result<u8, Error<SPI::Error>> {
         let mut buf = [0u8; 1];
-        self.spi.read(&mut buf)?; // Read response immediately / 紧接着读取响应
-        self.cs.set_high(); // Complete instruction, raise CS / 指令完成，拉高 CS
+        // Send command, then read response in the same CS frame / 发送命令，并在同一 CS 周期内读取响应
+        self
+            .transaction(&mut [Operation::Write(&[cmd]), Operation::Read(&mut buf)])
+            .await?;
         Ok(buf[0])
     }
 
-    /// Waits for the device to become idle (BUSY bit = 0).
-    /// 等待设备空闲 (BUSY 位 = 0)。
-    async fn wait_idle(&mut self) -> Result<(), spi::Error> {
+    /// Waits for the device to become idle (BUSY bit = 0), giving up after `timeout`.
+    /// 等待设备空闲 (BUSY 位 = 0)，超过 `timeout` 后放弃。
+    ///
+    /// Returns `Error::Timeout` if BUSY is still set when the timeout expires
+    /// (e.g. a dead chip or a stuck MISO line reading 0xFF).
+    /// 超时后 BUSY 仍置位时返回 `Error::Timeout`（例如芯片损坏或 MISO 线卡在 0xFF）。
+    ///
+    /// An erase suspended for a read is resumed first; a background erase counts as finished
+    /// once BUSY clears.
+    /// 为读取而暂停的擦除会先被恢复；BUSY 清零后，后台擦除即视为完成。
+    async fn wait_idle(&mut self, timeout: Duration) -> Result<(), Error<SPI::Error>> {
+        if self.pending_erase.is_some_and(|erase| erase.suspended) {
+            self.resume().await?;
+        }
+        let deadline = Instant::now() + timeout;
+        let interval = poll_interval(timeout);
         while self.is_busy().await? {
-            Timer::after_micros(100).await; // Periodic check to avoid blocking / 周期性检查，避免长时间阻塞
+            if Instant::now() >= deadline {
+                return Err(Error::Timeout);
+            }
+            Timer::after(interval).await; // Periodic check to avoid blocking / 周期性检查，避免长时间阻塞
         }
+        self.pending_erase = None;
+        Ok(())
+    }
+
+    /// Writes one Status Register byte with the enable command matching `mode`, then
+    /// waits for the write to complete.
+    /// 使用与 `mode` 对应的使能命令写入一个状态寄存器字节，然后等待写入完成。
+    async fn write_status(&mut self, cmd: u8, value: u8, mode: StatusWriteMode) -> Result<(), Error<SPI::Error>> {
+        self.wait_idle(self.config.idle_timeout).await?; // Wait for device to be idle / 等待设备空闲
+        let enable = match mode {
+            StatusWriteMode::NonVolatile => commands::WRITE_ENABLE,
+            StatusWriteMode::Volatile => commands::WRITE_ENABLE_VOLATILE_SR,
+        };
+        self.command(enable).await?;
+        self.protection = None; // Re-read protection on next use / 下次使用时重新读取保护设置
+        self.transaction(&mut [Operation::Write(&[cmd, value])]).await?; // Send command + new value / 发送命令+新值
+        self.wait_idle(self.config.status_write_timeout).await?; // Wait for write to complete / 等待写入完成
         Ok(())
     }
 
+    /// Rejects program/erase requests touching the protected region, which the chip
+    /// would otherwise silently ignore.
+    /// 拒绝涉及受保护区域的编程/擦除请求，否则芯片会静默忽略这些请求。
+    async fn check_unprotected(&mut self, address: u32, len: usize) -> Result<(), Error<SPI::Error>> {
+        let protection = match self.protection {
+            Some(protection) => protection,
+            None => self.read_write_protection().await?,
+        };
+        if protection.overlaps(address, len, &self.geometry) {
+            return Err(Error::Protected);
+        }
+        Ok(())
+    }
+
+    /// Reads the active write protection scheme (WPS) and its state, and caches it.
+    /// 读取当前的写保护方式 (WPS) 及其状态，并缓存。
+    async fn read_write_protection(&mut self) -> Result<WriteProtection, Error<SPI::Error>> {
+        let region = self.protected_region().await?;
+        let protection = if self.read_status_register_3().await?.wps {
+            WriteProtection::BlockLocks(self.read_block_locks().await?)
+        } else {
+            WriteProtection::Region(region)
+        };
+        self.protection = Some(protection);
+        Ok(protection)
+    }
+
+    /// Builds `cmd` followed by `address` in the width the part expects: the dedicated 4-byte
+    /// opcode where one exists, otherwise the current address mode, updating the Extended
+    /// Address Register first if needed. Returns the frame and its length; four zero bytes are
+    /// left after it for commands with dummy bytes.
+    /// 按器件要求的宽度构造 `cmd` 及其后的 `address`：有专用 4 字节操作码时使用它，否则按当前地址模式，
+    /// 必要时先更新扩展地址寄存器。返回帧及其长度；其后保留四个零字节，供带虚拟字节的命令使用。
+    async fn address_frame(&mut self, cmd: u8, address: u32) -> Result<([u8; 9], usize), Error<SPI::Error>> {
+        let [a3, a2, a1, a0] = address.to_be_bytes();
+        if let Some(cmd) = commands::four_byte(cmd).filter(|_| self.geometry.address_bytes == 4) {
+            return Ok(([cmd, a3, a2, a1, a0, 0, 0, 0, 0], 5));
+        }
+        if self.address_mode == AddressMode::FourByte {
+            return Ok(([cmd, a3, a2, a1, a0, 0, 0, 0, 0], 5));
+        }
+        if self.geometry.address_bytes == 4 && self.extended_address != Some(a3) {
+            self.write_extended_address_register(a3).await?;
+        }
+        Ok(([cmd, a2, a1, a0, 0, 0, 0, 0, 0], 4))
+    }
+
+    /// Sends a command followed by an address, with Write Enable first.
+    /// 先发送写使能，然后发送命令和地址。
+    async fn write_enabled_address_command(&mut self, cmd: u8, address: u32) -> Result<(), Error<SPI::Error>> {
+        self.check_bounds(address, 1)?;
+        self.wait_idle(self.config.idle_timeout).await?; // Wait for device to be idle / 等待设备空闲
+        let (frame, len) = self.address_frame(cmd, address).await?;
+        self.command(commands::WRITE_ENABLE).await?; // Send Write Enable / 发送写使能
+        self.transaction(&mut [Operation::Write(&frame[..len])]).await?;
+        Ok(())
+    }
+
+    /// Sends an address-based erase command after checking alignment, bounds and protection,
+    /// then waits up to `timeout` for it to complete.
+    /// 检查对齐、范围和保护后发送基于地址的擦除命令，并最多等待 `timeout` 直至完成。
+    async fn erase(&mut self, cmd: u8, address: u32, size: usize, timeout: Duration) -> Result<(), Error<SPI::Error>> {
+        self.start_erase(cmd, address, size, timeout).await?;
+        self.wait_idle(timeout).await // Wait for erase to complete / 等待擦除完成
+    }
+
+    /// Sends an address-based erase command after checking alignment, bounds and protection,
+    /// and records it as running in the background.
+    /// 检查对齐、范围和保护后发送基于地址的擦除命令，并将其记录为在后台运行。
+    async fn start_erase(&mut self, cmd: u8, address: u32, size: usize, timeout: Duration) -> Result<(), Error<SPI::Error>> {
+        // Alignment check for the erase unit / 擦除单元地址对齐检查
+        if !address.is_multiple_of(size as u32) {
+            return Err(Error::NotAligned);
+        }
+        self.check_bounds(address, size)?;
+        self.check_unprotected(address, size).await?;
+
+        self.wait_idle(self.config.idle_timeout).await?; // Wait for device to be idle / 等待设备空闲
+        let (frame, len) = self.address_frame(cmd, address).await?;
+        self.command(commands::WRITE_ENABLE).await?; // Send Write Enable / 发送写使能
+        // Send command + address / 发送命令+地址
+        self.transaction(&mut [Operation::Write(&frame[..len])]).await?;
+        self.pending_erase = Some(PendingErase { address, size, timeout, suspended: false });
+        Ok(())
+    }
+
+    /// Gets the chip ready to read `len` bytes at `address`. A background erase elsewhere is
+    /// suspended (returns `true`, resume after the read); one covering the range is waited for.
+    /// 使芯片准备好读取 `address` 处的 `len` 字节。位于其他位置的后台擦除会被暂停（返回 `true`，读取后需恢复）；
+    /// 覆盖该范围的擦除则等待其完成。
+    async fn prepare_read(&mut self, address: u32, len: usize) -> Result<bool, Error<SPI::Error>> {
+        // Data in a block being erased is undefined / 正在擦除的块中的数据不确定
+        if let Some(erase) = self.pending_erase.filter(|erase| !erase.overlaps(address, len)) {
+            // Suspended by the caller; leave it that way / 由调用者暂停；保持原状
+            if erase.suspended {
+                return Ok(false);
+            }
+            if self.suspend().await? {
+                return Ok(true);
+            }
+        }
+        self.wait_idle(self.config.idle_timeout).await?; // Wait for device to be idle / 等待设备空闲
+        Ok(false)
+    }
+
     // --- Public API Functions / 公共 API 函数 ---
 
     /// Reads the JEDEC ID (per Datasheet Section 8.2.27).
@@ -128,78 +798,287 @@ impl<'d, M: mode::Mode> W25q128jv<'d, M> {
     ///
     /// Returns (Manufacturer ID, Memory Type, Capacity).
     /// 返回 (制造商 ID, 内存类型, 容量)。
-    pub async fn read_jedec_id(&mut self) -> Result<(u8, u8, u8), spi::Error> {
-        self.cs.set_low();
-        // Send READ_ID command (0x9F) / 发送READ_ID命令（0x9F）
-        self.spi.write(&[commands::READ_ID])?;
+    pub async fn read_jedec_id(&mut self) -> Result<(u8, u8, u8), Error<SPI::Error>> {
         // Read 3-byte response (Manufacturer ID + Memory Type + Capacity) / 读取3字节响应（制造商ID + 内存类型 + 容量）
         let mut buf = [0u8; 3];
-        self.spi.read(&mut buf)?; // Read immediately after command / 紧接着读取3字节ID
-        self.cs.set_high(); // Complete instruction, raise CS / 指令完成，拉高 CS
+        // Send READ_ID command (0x9F), then read immediately / 发送READ_ID命令（0x9F），紧接着读取3字节ID
+        self
+            .transaction(&mut [Operation::Write(&[commands::READ_ID]), Operation::Read(&mut buf)])
+            .await?;
         Ok((buf[0], buf[1], buf[2]))
     }
 
-    /// Reads Status Register 1 (per Datasheet Section 7.1.1).
-    /// 读取状态寄存器1（依据数据手册第7.1.1节）。
-    pub async fn read_status_register(&mut self) -> Result<u8, spi::Error> {
+    /// Reads the Manufacturer / Device ID (per Datasheet Section 8.2.24).
+    /// 读取制造商/设备 ID（依据数据手册第8.2.24节）。
+    ///
+    /// Returns (Manufacturer ID, Device ID), `(JEDEC_MAN_ID, DEVICE_ID)` for a W25Q128JV.
+    /// 返回 (制造商 ID, 设备 ID)，W25Q128JV 为 `(JEDEC_MAN_ID, DEVICE_ID)`。
+    pub async fn read_manufacturer_device_id(&mut self) -> Result<(u8, u8), Error<SPI::Error>> {
+        let mut buf = [0u8; 2];
+        // Address 000000h returns the Manufacturer ID first / 地址 000000h 时先返回制造商 ID
+        self
+            .transaction(&mut [
+                Operation::Write(&[commands::MANUFACTURER_DEVICE_ID, 0x00, 0x00, 0x00]),
+                Operation::Read(&mut buf),
+            ])
+            .await?;
+        Ok((buf[0], buf[1]))
+    }
+
+    /// Reads the factory-programmed 64-bit Unique ID (per Datasheet Section 8.2.26).
+    /// 读取出厂编程的 64 位唯一 ID（依据数据手册第8.2.26节）。
+    ///
+    /// The ID is returned MSB first, as the chip sends it. / ID 按芯片发送的顺序（高位在前）返回。
+    /// Not available in QPI mode. / QPI 模式下不可用。
+    pub async fn read_unique_id(&mut self) -> Result<u64, Error<SPI::Error>> {
+        self.check_spi_mode()?;
+        let mut buf = [0u8; 8];
+        // Command + 4 dummy bytes, then 8 ID bytes / 命令 + 4 个虚拟字节，然后是 8 个 ID 字节
+        self
+            .transaction(&mut [
+                Operation::Write(&[commands::READ_UNIQUE_ID, 0x00, 0x00, 0x00, 0x00]),
+                Operation::Read(&mut buf),
+            ])
+            .await?;
+        Ok(u64::from_be_bytes(buf))
+    }
+
+    /// Reads raw bytes from the SFDP space (per Datasheet Section 8.2.28).
+    /// 从 SFDP 空间读取原始字节（依据数据手册第8.2.28节）。
+    ///
+    /// Not available in QPI mode. / QPI 模式下不可用。
+    ///
+    /// # Arguments / 参数
+    /// * `address`: SFDP address (24-bit). / SFDP 地址（24 位）。
+    /// * `buf`: The buffer to read data into. / 用于存储读取数据的缓冲区。
+    pub async fn read_sfdp(&mut self, address: u32, buf: &mut [u8]) -> Result<(), Error<SPI::Error>> {
+        self.check_spi_mode()?;
+        let [_, a2, a1, a0] = address.to_be_bytes(); // A23-A0
+        // Command + address + 1 dummy byte, then data / 命令 + 地址 + 1 个虚拟字节，然后是数据
+        self
+            .transaction(&mut [Operation::Write(&[commands::READ_SFDP, a2, a1, a0, 0x00]), Operation::Read(buf)])
+            .await?;
+        Ok(())
+    }
+
+    /// Reads the SFDP header and parses the JEDEC Basic Flash Parameter Table.
+    /// 读取 SFDP 头并解析 JEDEC 基本 Flash 参数表。
+    ///
+    /// Returns `Error::InvalidSfdp` if the signature is wrong or the table is missing or too short.
+    /// 如果签名错误或参数表缺失/过短，返回 `Error::InvalidSfdp`。
+    pub async fn read_sfdp_parameters(&mut self) -> Result<SfdpParameters, Error<SPI::Error>> {
+        let mut header = [0u8; 8];
+        self.read_sfdp(0, &mut header).await?;
+        if u32::from_le_bytes([header[0], header[1], header[2], header[3]]) != SFDP_SIGNATURE {
+            return Err(Error::InvalidSfdp);
+        }
+        // NPH is zero-based; parameter headers follow the SFDP header / NPH 从 0 开始计数；参数头紧随 SFDP 头
+        let mut bfpt = None;
+        for index in 0..=header[6] as u32 {
+            let mut raw = [0u8; 8];
+            self.read_sfdp(8 + 8 * index, &mut raw).await?;
+            let parameter = ParameterHeader::parse(raw);
+            if parameter.id == BFPT_ID {
+                bfpt = Some(parameter);
+                break;
+            }
+        }
+        let bfpt = bfpt.ok_or(Error::InvalidSfdp)?;
+
+        let mut table = [0u8; BFPT_MAX_DWORDS * 4];
+        let len = bfpt.dwords.min(BFPT_MAX_DWORDS) * 4;
+        self.read_sfdp(bfpt.pointer, &mut table[..len]).await?;
+        SfdpParameters::parse((bfpt.major, bfpt.minor), &table[..len]).ok_or(Error::InvalidSfdp)
+    }
+
+    /// Reads the JEDEC ID and checks it against `geometry()`: the part detected by `init`, or the
+    /// W25Q128JV before `init`.
+    /// 读取 JEDEC ID 并与 `geometry()` 进行比对：即 `init` 检测到的器件，`init` 之前为 W25Q128JV。
+    ///
+    /// Returns `Error::UnexpectedJedecId` if a different (or no) part answers.
+    /// 如果应答的是其他器件（或无器件应答），返回 `Error::UnexpectedJedecId`。
+    pub async fn verify_jedec_id(&mut self) -> Result<(), Error<SPI::Error>> {
+        let (manufacturer, memory_type, capacity) = self.read_jedec_id().await?;
+        if (manufacturer, memory_type, capacity) == self.geometry.jedec_id {
+            Ok(())
+        } else {
+            Err(Error::UnexpectedJedecId { manufacturer, memory_type, capacity })
+        }
+    }
+
+    /// Reads Status Register 1 as a raw byte (per Datasheet Section 7.1.1).
+    /// 以原始字节读取状态寄存器1（依据数据手册第7.1.1节）。
+    ///
+    /// See `read_status_register_1` for the decoded value. / 解码后的值见 `read_status_register_1`。
+    pub async fn read_status_register(&mut self) -> Result<u8, Error<SPI::Error>> {
         self.command_read_byte(commands::READ_STATUS_REG_1).await
     }
 
+    /// Reads and decodes Status Register 1 (per Datasheet Section 7.1).
+    /// 读取并解码状态寄存器1（依据数据手册第7.1节）。
+    pub async fn read_status_register_1(&mut self) -> Result<StatusRegister1, Error<SPI::Error>> {
+        Ok(self.command_read_byte(commands::READ_STATUS_REG_1).await?.into())
+    }
+
+    /// Reads and decodes Status Register 2 (per Datasheet Section 7.1).
+    /// 读取并解码状态寄存器2（依据数据手册第7.1节）。
+    pub async fn read_status_register_2(&mut self) -> Result<StatusRegister2, Error<SPI::Error>> {
+        Ok(self.command_read_byte(commands::READ_STATUS_REG_2).await?.into())
+    }
+
+    /// Reads and decodes Status Register 3 (per Datasheet Section 7.1).
+    /// 读取并解码状态寄存器3（依据数据手册第7.1节）。
+    pub async fn read_status_register_3(&mut self) -> Result<StatusRegister3, Error<SPI::Error>> {
+        Ok(self.command_read_byte(commands::READ_STATUS_REG_3).await?.into())
+    }
+
+    /// Writes Status Register 1 (per Datasheet Section 8.2.5). BUSY and WEL are read-only and ignored.
+    /// 写状态寄存器1（依据数据手册第8.2.5节）。BUSY 和 WEL 为只读位，写入时被忽略。
+    ///
+    /// # Arguments / 参数
+    /// * `value`: New register value. / 新的寄存器值。
+    /// * `mode`: Non-volatile or volatile write. / 非易失或易失写入。
+    pub async fn write_status_register_1(
+        &mut self,
+        value: StatusRegister1,
+        mode: StatusWriteMode,
+    ) -> Result<(), Error<SPI::Error>> {
+        self.write_status(commands::WRITE_STATUS_REG_1, value.into(), mode).await
+    }
+
+    /// Writes Status Register 2 (per Datasheet Section 8.2.5). SUS is read-only and ignored.
+    /// LB1-LB3 are one-time programmable, so they are kept as read back from the chip; only
+    /// `lock_security_register` sets them.
+    /// 写状态寄存器2（依据数据手册第8.2.5节）。SUS 为只读位，写入时被忽略。LB1-LB3 只能编程一次，
+    /// 因此保持从芯片读回的值；只有 `lock_security_register` 才会置位它们。
+    ///
+    /// # Arguments / 参数
+    /// * `value`: New register value. / 新的寄存器值。
+    /// * `mode`: Non-volatile or volatile write. / 非易失或易失写入。
+    pub async fn write_status_register_2(
+        &mut self,
+        mut value: StatusRegister2,
+        mode: StatusWriteMode,
+    ) -> Result<(), Error<SPI::Error>> {
+        let current = self.read_status_register_2().await?;
+        value.lb = current.lb;
+        self.write_status_2(value, mode, current).await
+    }
+
+    /// Writes Status Register 2 as given, LB1-LB3 included, and tracks the non-volatile value
+    /// across volatile writes. `current` is the value read before the write.
+    /// 按给定值写状态寄存器2（包括 LB1-LB3），并在易失性写入期间跟踪非易失值。`current` 为写入前读取的值。
+    async fn write_status_2(
+        &mut self,
+        value: StatusRegister2,
+        mode: StatusWriteMode,
+        current: StatusRegister2,
+    ) -> Result<(), Error<SPI::Error>> {
+        self.write_status(commands::WRITE_STATUS_REG_2, value.into(), mode).await?;
+        self.quad_enabled = Some(value.qe);
+        match mode {
+            // The first volatile write leaves the non-volatile value behind / 第一次易失性写入之后，非易失值不再可读
+            StatusWriteMode::Volatile => {
+                self.nonvolatile_sr2.get_or_insert(current);
+            }
+            // Sets both copies / 同时写入两份副本
+            StatusWriteMode::NonVolatile => self.nonvolatile_sr2 = None,
+        }
+        Ok(())
+    }
+
+    /// Sets or clears the QE bit in Status Register 2, leaving the other bits unchanged.
+    /// 置位或清除状态寄存器2中的 QE 位，其他位保持不变。
+    ///
+    /// With QE = 1, /WP and /HOLD become IO2 and IO3 and lose their pin functions.
+    /// QE = 1 时，/WP 和 /HOLD 成为 IO2 和 IO3，失去原有的引脚功能。
+    pub async fn set_quad_enable(&mut self, enabled: bool, mode: StatusWriteMode) -> Result<(), Error<SPI::Error>> {
+        let mut sr2 = self.read_status_register_2().await?;
+        if sr2.qe != enabled {
+            let current = sr2;
+            sr2.qe = enabled;
+            self.write_status_2(sr2, mode, current).await?;
+        }
+        self.quad_enabled = Some(enabled);
+        Ok(())
+    }
+
+    /// Writes Status Register 3 (per Datasheet Section 8.2.5).
+    /// 写状态寄存器3（依据数据手册第8.2.5节）。
+    ///
+    /// # Arguments / 参数
+    /// * `value`: New register value. / 新的寄存器值。
+    /// * `mode`: Non-volatile or volatile write. / 非易失或易失写入。
+    pub async fn write_status_register_3(
+        &mut self,
+        value: StatusRegister3,
+        mode: StatusWriteMode,
+    ) -> Result<(), Error<SPI::Error>> {
+        self.write_status(commands::WRITE_STATUS_REG_3, value.into(), mode).await
+    }
+
     /// Checks if the device is busy (BUSY bit in Status Register, per Datasheet Section 7.1.1).
     /// 检查设备是否忙（状态寄存器中的 BUSY 位，依据数据手册第7.1.1节）。
-    pub async fn is_busy(&mut self) -> Result<bool, spi::Error> {
-        let status = self.read_status_register().await?;
-        Ok((status & 0x01) != 0) // BUSY=1 means busy / BUSY=1表示忙
+    pub async fn is_busy(&mut self) -> Result<bool, Error<SPI::Error>> {
+        Ok(self.read_status_register_1().await?.busy) // BUSY=1 means busy / BUSY=1表示忙
     }
 
     /// Standard Read data (per Datasheet Section 8.2.6).
     /// 标准读取数据（依据数据手册第8.2.6节）。
     ///
+    /// Read Data (`03h`) is not decoded in QPI mode, where this uses `fast_read` instead.
+    /// QPI 模式下不解析读取数据（`03h`），此时改用 `fast_read`。
+    ///
     /// # Arguments / 参数
-    /// * `address`: The 24-bit address to start reading from. / 开始读取的 24 位地址。
+    /// * `address`: The address to start reading from. / 开始读取的地址。
     /// * `buf`: The buffer to read data into. / 用于存储读取数据的缓冲区。
-    pub async fn read_data(&mut self, address: u32, buf: &mut [u8]) -> Result<(), spi::Error> {
-        self.wait_idle().await?; // Wait for device to be idle / 等待设备空闲
+    pub async fn read_data(&mut self, address: u32, buf: &mut [u8]) -> Result<(), Error<SPI::Error>> {
+        if self.interface == InterfaceMode::Qpi {
+            return self.fast_read(address, buf).await;
+        }
+        self.check_bounds(address, buf.len())?; // Reject reads past the end of the array / 拒绝超出存储阵列的读取
+        let suspended = self.prepare_read(address, buf.len()).await?; // Wait for idle or suspend an erase / 等待空闲或暂停擦除
 
-        let cmd = commands::READ_DATA;
-        // Pack 24-bit address / 打包 24 位地址
-        let addr_bytes = [
-            ((address >> 16) & 0xFF) as u8, // A23-A16
-            ((address >> 8) & 0xFF) as u8,  // A15-A8
-            (address & 0xFF) as u8,         // A7-A0
-        ];
+        // Pack 24- or 32-bit address / 打包 24 或 32 位地址
+        let (frame, len) = self.address_frame(commands::READ_DATA, address).await?;
 
-        self.cs.set_low();
-        // Send command + 24-bit address / 发送命令+24位地址
-        self.spi.write(&[cmd, addr_bytes[0], addr_bytes[1], addr_bytes[2]])?;
-        // Read data / 读取数据
-        self.spi.read(buf)?;
-        self.cs.set_high(); // Complete instruction, raise CS / 指令完成，拉高 CS
+        // Send command + address, then read data / 发送命令+地址，然后读取数据
+        self
+            .transaction(&mut [Operation::Write(&frame[..len]), Operation::Read(buf)])
+            .await?;
+        if suspended {
+            self.resume().await?; // Let the erase continue / 让擦除继续进行
+        }
         Ok(())
     }
 
     /// Fast Read data with dummy cycles (per Datasheet Section 8.2.7).
     /// 快速读取数据（带虚拟周期，依据数据手册第8.2.7节）。
     ///
+    /// In QPI mode the dummy clocks follow `read_parameters()`. / QPI 模式下虚拟时钟数遵循 `read_parameters()`。
+    ///
     /// # Arguments / 参数
-    /// * `address`: The 24-bit address to start reading from. / 开始读取的 24 位地址。
+    /// * `address`: The address to start reading from. / 开始读取的地址。
     /// * `buf`: The buffer to read data into. / 用于存储读取数据的缓冲区。
-    pub async fn fast_read(&mut self, address: u32, buf: &mut [u8]) -> Result<(), spi::Error> {
-        self.wait_idle().await?; // Wait for device to be idle / 等待设备空闲
+    pub async fn fast_read(&mut self, address: u32, buf: &mut [u8]) -> Result<(), Error<SPI::Error>> {
+        self.check_bounds(address, buf.len())?; // Reject reads past the end of the array / 拒绝超出存储阵列的读取
+        let suspended = self.prepare_read(address, buf.len()).await?; // Wait for idle or suspend an erase / 等待空闲或暂停擦除
 
-        let cmd = commands::FAST_READ;
-        // Pack 24-bit address / 打包 24 位地址
-        let addr_bytes = [
-            ((address >> 16) & 0xFF) as u8, // A23-A16
-            ((address >> 8) & 0xFF) as u8,  // A15-A8
-            (address & 0xFF) as u8,         // A7-A0
-        ];
+        // Pack 24- or 32-bit address / 打包 24 或 32 位地址
+        let (frame, len) = self.address_frame(commands::FAST_READ, address).await?;
 
-        self.cs.set_low();
-        // Send command + address + 1 dummy byte (8 clocks) / 发送命令+地址+1字节虚拟周期（8个时钟）
-        self.spi.write(&[cmd, addr_bytes[0], addr_bytes[1], addr_bytes[2], 0x00])?;
-        self.spi.read(buf)?; // Read data / 读取数据
-        self.cs.set_high(); // Complete instruction, raise CS / 指令完成，拉高 CS
+        // Send command + address + dummy bytes (8 clocks in SPI mode), then read data
+        // 发送命令+地址+虚拟字节（SPI 模式下为 8 个时钟），然后读取数据
+        let dummy = match self.interface {
+            InterfaceMode::Spi => 1,
+            InterfaceMode::Qpi => self.read_parameters.dummy_bytes(),
+        };
+        self
+            .transaction(&mut [Operation::Write(&frame[..len + dummy]), Operation::Read(buf)])
+            .await?;
+        if suspended {
+            self.resume().await?; // Let the erase continue / 让擦除继续进行
+        }
         Ok(())
     }
 
@@ -208,32 +1087,144 @@ impl<'d, M: mode::Mode> W25q128jv<'d, M> {
     ///
     /// **Note**: The target area must be erased (set to 0xFF) before writing.
     /// **注意**: 写入前目标地址区域必须已被擦除（为 0xFF）。
-    /// Data length must not exceed page size (typically 256 bytes) and must not cross page boundaries.
-    /// 数据长度不能超过页面大小（通常 256 字节），且不能跨页面写入。
+    /// Data must fit inside one page; use `program` for longer or unaligned data.
+    /// 数据必须位于同一个页面内；更长或未对齐的数据请使用 `program`。
+    ///
+    /// Returns `Error::NotAligned` if the data would cross a page boundary (the chip would
+    /// otherwise wrap around inside the page).
+    /// 如果数据会跨越页面边界则返回 `Error::NotAligned`（否则芯片会在页面内回绕写入）。
     ///
     /// # Arguments / 参数
-    /// * `address`: The 24-bit address to start writing to. Must be page-aligned. / 开始写入的 24 位地址。必须按页面对齐。
-    /// * `data`: The data slice to write. / 要写入的数据切片。
-    pub async fn write_data(&mut self, address: u32, data: &[u8]) -> Result<(), spi::Error> {
-        // Optional: Add length check for page size (e.g., 256 bytes)
-        // 可选：添加长度检查 (例如，不超过 256 字节)
-        // if data.len() > 256 { return Err(spi::Error::Other); }
+    /// * `address`: The address to start writing to. / 开始写入的地址。
+    /// * `data`: The data slice to write (at most one page). / 要写入的数据切片（最多一页）。
+    pub async fn write_data(&mut self, address: u32, data: &[u8]) -> Result<(), Error<SPI::Error>> {
+        // Data must stay inside one page / 数据必须位于同一页面内
+        let page_size = self.geometry.page_size;
+        if (address as usize % page_size) + data.len() > page_size {
+            return Err(Error::NotAligned);
+        }
+        self.check_bounds(address, data.len())?; // Reject writes past the end of the array / 拒绝超出存储阵列的写入
+        if data.is_empty() {
+            return Ok(());
+        }
+        self.check_unprotected(address, data.len()).await?; // Reject writes to protected pages / 拒绝写入受保护的页面
 
-        self.wait_idle().await?; // Wait for device to be idle / 等待设备空闲
+        self.wait_idle(self.config.idle_timeout).await?; // Wait for device to be idle / 等待设备空闲
+        // Pack 24- or 32-bit address / 打包 24 或 32 位地址
+        let (frame, len) = self.address_frame(commands::PAGE_PROGRAM, address).await?;
         self.command(commands::WRITE_ENABLE).await?; // Send Write Enable / 发送写使能
-        let cmd = commands::PAGE_PROGRAM;
-        // Pack 24-bit address / 打包 24 位地址
-        let addr_bytes = [
-            ((address >> 16) & 0xFF) as u8,
-            ((address >> 8) & 0xFF) as u8,
-            (address & 0xFF) as u8,
-        ];
-        self.cs.set_low();
         // Send command + address + data / 发送命令+地址+数据
-        self.spi.write(&[cmd, addr_bytes[0], addr_bytes[1], addr_bytes[2]])?;
-        self.spi.write(data)?; // Write data / 写入数据
-        self.cs.set_high();
-        self.wait_idle().await?; // Wait for write to complete / 等待写入完成
+        self
+            .transaction(&mut [Operation::Write(&frame[..len]), Operation::Write(data)])
+            .await?;
+        self.wait_idle(self.config.page_program_timeout).await?; // Wait for write to complete / 等待写入完成
+        Ok(())
+    }
+
+    /// Program data of any length and offset, split into page-aligned Page Programs.
+    /// 编程任意长度和偏移的数据，自动拆分为按页面对齐的页面编程操作。
+    ///
+    /// **Note**: The target area must be erased (set to 0xFF) before writing.
+    /// **注意**: 写入前目标地址区域必须已被擦除（为 0xFF）。
+    ///
+    /// # Arguments / 参数
+    /// * `address`: The address to start writing to. / 开始写入的地址。
+    /// * `data`: The data slice to write. / 要写入的数据切片。
+    pub async fn program(&mut self, address: u32, data: &[u8]) -> Result<(), Error<SPI::Error>> {
+        self.check_bounds(address, data.len())?; // Reject writes past the end of the array / 拒绝超出存储阵列的写入
+        self.check_unprotected(address, data.len()).await?; // Fail before writing any page / 在写入任何页面之前失败
+
+        let mut address = address;
+        let mut data = data;
+        while !data.is_empty() {
+            // Write up to the end of the current page / 写到当前页面末尾为止
+            let page_remaining = self.geometry.page_size - (address as usize % self.geometry.page_size);
+            let (chunk, rest) = data.split_at(data.len().min(page_remaining));
+            self.write_data(address, chunk).await?;
+            address += chunk.len() as u32;
+            data = rest;
+        }
+        Ok(())
+    }
+
+    /// Write data of any length and offset over existing contents (read-modify-write).
+    /// 在已有内容上写入任意长度和偏移的数据（读-改-写）。
+    ///
+    /// Each affected 4KB sector is read first. If the new bytes only clear bits, the bytes that
+    /// change are programmed in place; otherwise the sector is erased and reprogrammed with its
+    /// old contents merged with `data`. The rest of the sector is preserved either way.
+    /// 先读取每个受影响的 4KB 扇区。如果新字节只会清零位，则直接编程发生变化的字节；否则擦除该扇区，
+    /// 并写回合并了 `data` 的原有内容。两种情况下扇区的其余部分都保持不变。
+    ///
+    /// The sector buffer lives in the returned future; use `write_with_buffer` to provide it instead.
+    /// `NorFlash::write` keeps the plain program semantics of `program`.
+    /// 扇区缓冲区位于返回的 future 中；如需自行提供，请使用 `write_with_buffer`。
+    /// `NorFlash::write` 保持与 `program` 相同的纯编程语义。
+    ///
+    /// # Arguments / 参数
+    /// * `address`: The address to start writing to. / 开始写入的地址。
+    /// * `data`: The data slice to write. / 要写入的数据切片。
+    pub async fn write(&mut self, address: u32, data: &[u8]) -> Result<(), Error<SPI::Error>> {
+        let mut buffer = [0u8; SECTOR_SIZE];
+        self.write_with_buffer(address, data, &mut buffer).await
+    }
+
+    /// `write` using a caller-provided sector buffer / 使用调用者提供的扇区缓冲区的 `write`
+    ///
+    /// # Arguments / 参数
+    /// * `address`: The address to start writing to. / 开始写入的地址。
+    /// * `data`: The data slice to write. / 要写入的数据切片。
+    /// * `buffer`: Scratch space for one sector; its contents are overwritten. / 一个扇区大小的临时空间，其内容会被覆盖。
+    pub async fn write_with_buffer(
+        &mut self,
+        address: u32,
+        data: &[u8],
+        buffer: &mut [u8; SECTOR_SIZE],
+    ) -> Result<(), Error<SPI::Error>> {
+        self.check_bounds(address, data.len())?; // Reject writes past the end of the array / 拒绝超出存储阵列的写入
+        if data.is_empty() {
+            return Ok(());
+        }
+        // Whole sectors may be erased, so check all of them before changing any / 可能擦除整个扇区，因此在修改前检查所有扇区
+        let sector_size = self.geometry.sector_size;
+        let first_sector = address - address % sector_size as u32;
+        let end = address as usize + data.len();
+        let span = end.div_ceil(sector_size) * sector_size - first_sector as usize;
+        self.check_unprotected(first_sector, span).await?;
+
+        let mut address = address;
+        let mut data = data;
+        while !data.is_empty() {
+            // Handle up to the end of the current sector / 处理到当前扇区末尾为止
+            let sector = address - address % sector_size as u32;
+            let offset = (address - sector) as usize;
+            let (chunk, rest) = data.split_at(data.len().min(sector_size - offset));
+            let contents = &mut buffer[..sector_size];
+            self.fast_read(sector, contents).await?;
+
+            let current = &contents[offset..offset + chunk.len()];
+            if current.iter().zip(chunk).all(|(&old, &new)| old & new == new) {
+                // Programming can only clear bits: rewrite the changed bytes without erasing
+                // 编程只能清零位：无需擦除，只重写发生变化的字节
+                let mut changed = current.iter().zip(chunk).enumerate().filter(|(_, (old, new))| old != new);
+                if let Some((first, _)) = changed.next() {
+                    let last = changed.next_back().map_or(first, |(last, _)| last);
+                    self.program(address + first as u32, &chunk[first..=last]).await?;
+                }
+            } else {
+                contents[offset..offset + chunk.len()].copy_from_slice(chunk);
+                self.erase_sector_4k(sector).await?;
+                // Erased pages already read 0xFF / 擦除后的页面已为 0xFF
+                let page_size = self.geometry.page_size;
+                for (index, page) in contents.chunks(page_size).enumerate() {
+                    if page.iter().any(|&byte| byte != 0xFF) {
+                        self.write_data(sector + (index * page_size) as u32, page).await?;
+                    }
+                }
+            }
+            address += chunk.len() as u32;
+            data = rest;
+        }
         Ok(())
     }
 
@@ -244,34 +1235,734 @@ impl<'d, M: mode::Mode> W25q128jv<'d, M> {
     /// **注意**: 此操作会将扇区内的所有位设置为 1 (0xFF)。
     ///
     /// # Arguments / 参数
-    /// * `sector_address`: The 24-bit address of the sector to erase. Must be 4KB-aligned. / 要擦除的扇区的 24 位地址。必须按 4KB 对齐。
-    pub async fn erase_sector(&mut self, sector_address: u32) -> Result<(), spi::Error> {
-        // Optional: Add alignment check for sector size (4KB)
-        // 可选：添加地址对齐检查 (4KB)
-        // if sector_address % SECTOR_SIZE as u32 != 0 { return Err(spi::Error::Other); }
+    /// * `sector_address`: The address of the sector to erase. Must be 4KB-aligned. / 要擦除的扇区的地址。必须按 4KB 对齐。
+    pub async fn erase_sector_4k(&mut self, sector_address: u32) -> Result<(), Error<SPI::Error>> {
+        self.erase(commands::SECTOR_ERASE, sector_address, self.geometry.sector_size, self.config.sector_erase_timeout).await
+    }
+
+    /// Erase a 32KB block (per Datasheet Section 8.2.16).
+    /// 擦除一个 32KB 块（依据数据手册第8.2.16节）。
+    ///
+    /// # Arguments / 参数
+    /// * `block_address`: The address of the block to erase. Must be 32KB-aligned. / 要擦除的块的地址。必须按 32KB 对齐。
+    pub async fn erase_block_32k(&mut self, block_address: u32) -> Result<(), Error<SPI::Error>> {
+        self.erase(commands::BLOCK_ERASE_32K, block_address, self.geometry.block_32k_size, self.config.block_erase_timeout).await
+    }
 
-        self.wait_idle().await?; // Wait for device to be idle / 等待设备空闲
+    /// Erase a 64KB block (per Datasheet Section 8.2.17).
+    /// 擦除一个 64KB 块（依据数据手册第8.2.17节）。
+    ///
+    /// # Arguments / 参数
+    /// * `block_address`: The address of the block to erase. Must be 64KB-aligned. / 要擦除的块的地址。必须按 64KB 对齐。
+    pub async fn erase_block_64k(&mut self, block_address: u32) -> Result<(), Error<SPI::Error>> {
+        self.erase(commands::BLOCK_ERASE_64K, block_address, self.geometry.block_64k_size, self.config.block_erase_timeout).await
+    }
+
+    /// Erase the whole chip (per Datasheet Section 8.2.18).
+    /// 擦除整个芯片（依据数据手册第8.2.18节）。
+    ///
+    /// **Note**: This can take tens of seconds (up to 200s per datasheet).
+    /// **注意**: 此操作可能耗时数十秒（数据手册最大值为 200 秒）。
+    ///
+    /// Returns `Error::Protected` if any part of the array is protected (the chip ignores Chip Erase then).
+    /// 如果阵列的任何部分受保护则返回 `Error::Protected`（此时芯片会忽略整片擦除）。
+    pub async fn erase_chip(&mut self) -> Result<(), Error<SPI::Error>> {
+        self.check_unprotected(0, self.geometry.capacity).await?;
+        self.wait_idle(self.config.idle_timeout).await?; // Wait for device to be idle / 等待设备空闲
         self.command(commands::WRITE_ENABLE).await?; // Send Write Enable / 发送写使能
-        let cmd = commands::SECTOR_ERASE;
-        // Pack 24-bit address / 打包 24 位地址
-        let addr_bytes = [
-            ((sector_address >> 16) & 0xFF) as u8,
-            ((sector_address >> 8) & 0xFF) as u8,
-            (sector_address & 0xFF) as u8,
-        ];
-        self.cs.set_low();
-        // Send command + address / 发送命令+地址
-        self.spi.write(&[cmd, addr_bytes[0], addr_bytes[1], addr_bytes[2]])?;
-        self.cs.set_high();
-        self.wait_idle().await?; // Wait for erase to complete / 等待擦除完成
+        self.command(commands::CHIP_ERASE).await?; // Send Chip Erase / 发送整片擦除
+        self.wait_idle(self.config.chip_erase_timeout).await?; // Wait for erase to complete / 等待擦除完成
+        Ok(())
+    }
+
+    /// Erase an arbitrary 4KB-aligned range, using the largest aligned erase unit for each part.
+    /// 擦除任意 4KB 对齐的范围，每一部分都使用可用的最大对齐擦除单元。
+    ///
+    /// The range is covered with 64KB blocks where possible, then 32KB blocks, then 4KB
+    /// sectors. A range covering the whole device uses Chip Erase.
+    /// 范围内尽可能使用 64KB 块擦除，其次 32KB 块擦除，最后 4KB 扇区擦除。
+    /// 覆盖整个器件的范围会使用整片擦除。
+    ///
+    /// # Arguments / 参数
+    /// * `start`: Start address. Must be 4KB-aligned. / 起始地址。必须按 4KB 对齐。
+    /// * `len`: Length in bytes. Must be a multiple of 4KB. / 长度（字节）。必须是 4KB 的整数倍。
+    pub async fn erase_range(&mut self, start: u32, len: usize) -> Result<(), Error<SPI::Error>> {
+        let FlashGeometry { capacity, sector_size, block_32k_size, block_64k_size, .. } = self.geometry;
+        if !start.is_multiple_of(sector_size as u32) || !len.is_multiple_of(sector_size) {
+            return Err(Error::NotAligned);
+        }
+        self.check_bounds(start, len)?;
+        self.check_unprotected(start, len).await?; // Fail before erasing anything / 在擦除任何内容之前失败
+        if start == 0 && len == capacity {
+            return self.erase_chip().await;
+        }
+
+        let mut address = start as usize;
+        let end = address + len;
+        while address < end {
+            let remaining = end - address;
+            // Pick the largest unit aligned at `address` that fits / 选择在 `address` 处对齐且不超出范围的最大单元
+            let size = if address.is_multiple_of(block_64k_size) && remaining >= block_64k_size {
+                self.erase_block_64k(address as u32).await?;
+                block_64k_size
+            } else if address.is_multiple_of(block_32k_size) && remaining >= block_32k_size {
+                self.erase_block_32k(address as u32).await?;
+                block_32k_size
+            } else {
+                self.erase_sector_4k(address as u32).await?;
+                sector_size
+            };
+            address += size;
+        }
+        Ok(())
+    }
+
+    /// Starts a 4KB Sector Erase and returns without waiting for it; see `start_erase_block_64k`.
+    /// 启动 4KB 扇区擦除并立即返回，不等待完成；见 `start_erase_block_64k`。
+    pub async fn start_erase_sector_4k(&mut self, sector_address: u32) -> Result<(), Error<SPI::Error>> {
+        self.start_erase(commands::SECTOR_ERASE, sector_address, self.geometry.sector_size, self.config.sector_erase_timeout).await
+    }
+
+    /// Starts a 32KB Block Erase and returns without waiting for it; see `start_erase_block_64k`.
+    /// 启动 32KB 块擦除并立即返回，不等待完成；见 `start_erase_block_64k`。
+    pub async fn start_erase_block_32k(&mut self, block_address: u32) -> Result<(), Error<SPI::Error>> {
+        self.start_erase(commands::BLOCK_ERASE_32K, block_address, self.geometry.block_32k_size, self.config.block_erase_timeout).await
+    }
+
+    /// Starts a 64KB Block Erase and returns without waiting for it.
+    /// 启动 64KB 块擦除并立即返回，不等待完成。
+    ///
+    /// While it runs, `read_data` and `fast_read` outside the block suspend the erase (`75h`),
+    /// read, and resume it (`7Ah`), honouring tSUS and tRS (`Config::suspend_latency` and
+    /// `Config::resume_to_suspend`). Reads inside the block wait for the erase to finish; any other
+    /// command waits as well. Call `finish_erase` to wait for completion explicitly.
+    /// 擦除进行期间，块外的 `read_data` 和 `fast_read` 会暂停擦除（`75h`）、读取，然后恢复擦除（`7Ah`），
+    /// 并遵守 tSUS 和 tRS（`Config::suspend_latency` 和 `Config::resume_to_suspend`）。块内的读取会等待擦除完成；
+    /// 其他命令同样会等待。调用 `finish_erase` 可显式等待完成。
+    pub async fn start_erase_block_64k(&mut self, block_address: u32) -> Result<(), Error<SPI::Error>> {
+        self.start_erase(commands::BLOCK_ERASE_64K, block_address, self.geometry.block_64k_size, self.config.block_erase_timeout).await
+    }
+
+    /// Address range of the erase started with `start_erase_*`, until it is seen to finish.
+    /// 通过 `start_erase_*` 启动的擦除的地址范围，直到确认其完成为止。
+    pub fn erase_in_progress(&self) -> Option<Range<u32>> {
+        self.pending_erase.map(|erase| erase.address..erase.address + erase.size as u32)
+    }
+
+    /// Waits for the erase started with `start_erase_*` to complete, resuming it if needed.
+    /// 等待通过 `start_erase_*` 启动的擦除完成，必要时先恢复它。
+    pub async fn finish_erase(&mut self) -> Result<(), Error<SPI::Error>> {
+        let timeout = self.pending_erase.map_or(self.config.idle_timeout, |erase| erase.timeout);
+        self.wait_idle(timeout).await
+    }
+
+    /// Suspends the erase or program in progress (Erase/Program Suspend, per Datasheet Section 8.2.19).
+    /// 暂停正在进行的擦除或编程（擦除/编程暂停，依据数据手册第8.2.19节）。
+    ///
+    /// Waits at least `Config::resume_to_suspend` (tRS) after the last resume, then tSUS for the
+    /// suspend to take effect. Returns `true` if SUS is now set, `false` if nothing was running
+    /// (or it could not be suspended, e.g. Chip Erase).
+    /// 在上一次恢复后至少等待 `Config::resume_to_suspend` (tRS)，然后等待 tSUS 使暂停生效。
+    /// SUS 已置位时返回 `true`；没有正在运行的操作（或无法暂停，例如整片擦除）时返回 `false`。
+    pub async fn suspend(&mut self) -> Result<bool, Error<SPI::Error>> {
+        if !self.is_busy().await? {
+            return Ok(false);
+        }
+        Timer::at(self.last_resume + self.config.resume_to_suspend).await; // tRS
+        self.command(commands::SUSPEND).await?;
+        Timer::after(self.config.suspend_latency).await; // tSUS
+        let suspended = self.read_status_register_2().await?.sus;
+        if let Some(erase) = &mut self.pending_erase {
+            erase.suspended = suspended;
+        }
+        Ok(suspended)
+    }
+
+    /// Resumes a suspended erase or program if SUS is set (Erase/Program Resume, per Datasheet Section 8.2.20).
+    /// 如果 SUS 已置位，恢复被暂停的擦除或编程（擦除/编程恢复，依据数据手册第8.2.20节）。
+    pub async fn resume(&mut self) -> Result<(), Error<SPI::Error>> {
+        if !self.read_status_register_2().await?.sus {
+            return Ok(());
+        }
+        self.command(commands::RESUME).await?;
+        self.last_resume = Instant::now();
+        if let Some(erase) = &mut self.pending_erase {
+            erase.suspended = false;
+        }
+        Ok(())
+    }
+
+    /// Reads the current block protection from Status Registers 1 and 2 (per Datasheet Section 7.1.8).
+    /// 从状态寄存器1和2读取当前的块保护设置（依据数据手册第7.1.8节）。
+    ///
+    /// Use `ProtectionRegion::range` for the protected addresses. The region only applies while
+    /// WPS = 0; see `set_individual_block_lock`.
+    /// 受保护的地址范围见 `ProtectionRegion::range`。该区域仅在 WPS = 0 时生效；见 `set_individual_block_lock`。
+    pub async fn protected_region(&mut self) -> Result<ProtectionRegion, Error<SPI::Error>> {
+        let sr1 = self.read_status_register_1().await?;
+        let sr2 = self.read_status_register_2().await?;
+        Ok(ProtectionRegion::from_status(sr1, sr2, &self.geometry))
+    }
+
+    /// Protects exactly `range` against program/erase, using non-volatile Status Register writes.
+    /// 使用非易失状态寄存器写入，对恰好 `range` 范围进行编程/擦除保护。
+    ///
+    /// Returns `Error::InvalidProtectionRange` if the protection table cannot express `range`
+    /// (see `ProtectionRegion`), and `Error::Unsupported` while individual block locks are in use
+    /// (WPS = 1), where the chip ignores BP/TB/SEC/CMP.
+    /// 如果保护表无法表示 `range`，返回 `Error::InvalidProtectionRange`（见 `ProtectionRegion`）；
+    /// 使用独立块锁定（WPS = 1）时芯片忽略 BP/TB/SEC/CMP，此时返回 `Error::Unsupported`。
+    ///
+    /// # Arguments / 参数
+    /// * `range`: Address range to protect, e.g. `0xFC_0000..0x100_0000`. / 要保护的地址范围，例如 `0xFC_0000..0x100_0000`。
+    pub async fn protect(&mut self, range: Range<u32>) -> Result<(), Error<SPI::Error>> {
+        let region = ProtectionRegion::from_range(range, &self.geometry).ok_or(Error::InvalidProtectionRange)?;
+        self.set_protection(region).await
+    }
+
+    /// Removes all block protection (BP2-BP0 = 0, CMP = 0).
+    /// 解除所有块保护 (BP2-BP0 = 0, CMP = 0)。
+    ///
+    /// Returns `Error::Unsupported` while individual block locks are in use (WPS = 1); see
+    /// `global_unlock` instead.
+    /// 使用独立块锁定（WPS = 1）时返回 `Error::Unsupported`；此时请使用 `global_unlock`。
+    pub async fn unprotect_all(&mut self) -> Result<(), Error<SPI::Error>> {
+        self.set_protection(ProtectionRegion::NONE).await
+    }
+
+    /// Writes the protection bits, keeping the other Status Register bits unchanged.
+    /// 写入保护位，保持状态寄存器其他位不变。
+    ///
+    /// When CMP changes, SR1 and SR2 are two separate non-volatile writes and the chip applies
+    /// (and, on power loss, keeps) a mix of old and new bits in between. Of the two orders, the
+    /// one whose in-between region shares more bytes with the old and the new region is used,
+    /// so that mix widens rather than narrows protection; ties write SR1 first.
+    /// CMP 变化时，SR1 和 SR2 是两次独立的非易失写入，芯片在两次写入之间会应用（掉电时会保留）新旧位的混合状态。
+    /// 两种顺序中，选择中间状态与旧区域和新区域共享字节更多的那一种，使该混合状态扩大而不是缩小保护范围；
+    /// 两者相同时先写 SR1。
+    async fn set_protection(&mut self, region: ProtectionRegion) -> Result<(), Error<SPI::Error>> {
+        // The chip ignores the region bits with WPS = 1 / WPS = 1 时芯片忽略保护区域位
+        if self.read_status_register_3().await?.wps {
+            return Err(Error::Unsupported);
+        }
+        let (old_sr1, old_sr2) = (self.read_status_register_1().await?, self.read_status_register_2().await?);
+        let old = ProtectionRegion::from_status(old_sr1, old_sr2, &self.geometry);
+        if old == region {
+            return Ok(());
+        }
+        let (mut sr1, mut sr2) = (old_sr1, old_sr2);
+        region.apply(&mut sr1, &mut sr2, &self.geometry);
+        if sr2.cmp == old_sr2.cmp {
+            return self.write_status_register_1(sr1, StatusWriteMode::NonVolatile).await;
+        }
+        let covered = |mid: ProtectionRegion| {
+            mid.shared_len(&old, &self.geometry) as u64 + mid.shared_len(&region, &self.geometry) as u64
+        };
+        let sr1_first = ProtectionRegion::from_status(sr1, old_sr2, &self.geometry);
+        let sr2_first = ProtectionRegion::from_status(old_sr1, sr2, &self.geometry);
+        if covered(sr2_first) > covered(sr1_first) {
+            self.write_status_2(sr2, StatusWriteMode::NonVolatile, old_sr2).await?;
+            self.write_status_register_1(sr1, StatusWriteMode::NonVolatile).await
+        } else {
+            self.write_status_register_1(sr1, StatusWriteMode::NonVolatile).await?;
+            self.write_status_2(sr2, StatusWriteMode::NonVolatile, old_sr2).await
+        }
+    }
+
+    /// Selects the write protection scheme (WPS bit in Status Register 3, per Datasheet Section 7.1.9).
+    /// 选择写保护方式（状态寄存器3中的 WPS 位，依据数据手册第7.1.9节）。
+    ///
+    /// With `individual = true` the BP/TB/SEC/CMP bits are ignored and every block/sector has
+    /// its own volatile lock bit, all set (locked) at power-up.
+    /// `individual = true` 时 BP/TB/SEC/CMP 位被忽略，每个块/扇区都有独立的易失性锁定位，上电时全部置位（锁定）。
+    ///
+    /// # Arguments / 参数
+    /// * `individual`: Use individual block locks (WPS = 1). / 使用独立块锁定 (WPS = 1)。
+    /// * `mode`: Non-volatile or volatile write. / 非易失或易失写入。
+    pub async fn set_individual_block_lock(
+        &mut self,
+        individual: bool,
+        mode: StatusWriteMode,
+    ) -> Result<(), Error<SPI::Error>> {
+        let mut sr3 = self.read_status_register_3().await?;
+        sr3.wps = individual;
+        self.write_status_register_3(sr3, mode).await
+    }
+
+    /// Locks the block/sector holding `address` (Individual Block/Sector Lock, per Datasheet Section 8.2.38).
+    /// 锁定包含 `address` 的块/扇区（独立块/扇区锁定，依据数据手册第8.2.38节）。
+    ///
+    /// The bottom and top 64KB blocks lock per 4KB sector, every other block per 64KB.
+    /// Only effective while WPS = 1.
+    /// 底部和顶部 64KB 块按 4KB 扇区锁定，其余块按 64KB 锁定。仅在 WPS = 1 时生效。
+    pub async fn lock_block(&mut self, address: u32) -> Result<(), Error<SPI::Error>> {
+        self.write_enabled_address_command(commands::INDIVIDUAL_BLOCK_LOCK, address).await?;
+        if let Some(WriteProtection::BlockLocks(locks)) = &mut self.protection {
+            locks.set_address(address, true);
+        }
+        Ok(())
+    }
+
+    /// Unlocks the block/sector holding `address` (Individual Block/Sector Unlock, per Datasheet Section 8.2.39).
+    /// 解锁包含 `address` 的块/扇区（独立块/扇区解锁，依据数据手册第8.2.39节）。
+    pub async fn unlock_block(&mut self, address: u32) -> Result<(), Error<SPI::Error>> {
+        self.write_enabled_address_command(commands::INDIVIDUAL_BLOCK_UNLOCK, address).await?;
+        if let Some(WriteProtection::BlockLocks(locks)) = &mut self.protection {
+            locks.set_address(address, false);
+        }
+        Ok(())
+    }
+
+    /// Reads the lock bit of the block/sector holding `address` (per Datasheet Section 8.2.40).
+    /// 读取包含 `address` 的块/扇区的锁定位（依据数据手册第8.2.40节）。
+    pub async fn read_block_lock(&mut self, address: u32) -> Result<bool, Error<SPI::Error>> {
+        self.check_bounds(address, 1)?;
+        self.wait_idle(self.config.idle_timeout).await?; // Wait for device to be idle / 等待设备空闲
+        self.read_lock_bit(address).await
+    }
+
+    /// Reads the lock bit of every block/sector into a bitmap.
+    /// 读取所有块/扇区的锁定位，生成位图。
+    pub async fn read_block_locks(&mut self) -> Result<BlockLockMap, Error<SPI::Error>> {
+        self.wait_idle(self.config.idle_timeout).await?; // Wait for device to be idle / 等待设备空闲
+        let mut locks = BlockLockMap::unlocked(self.geometry);
+        for index in 0..self.geometry.block_lock_units() {
+            let locked = self.read_lock_bit(self.geometry.block_lock_unit(index).start).await?;
+            locks.set(index, locked);
+        }
+        Ok(locks)
+    }
+
+    /// Sends Read Block Lock for `address` and returns bit 0 of the response.
+    /// 对 `address` 发送读取块锁定命令，并返回响应的第 0 位。
+    async fn read_lock_bit(&mut self, address: u32) -> Result<bool, Error<SPI::Error>> {
+        let (frame, len) = self.address_frame(commands::READ_BLOCK_LOCK, address).await?;
+        let mut buf = [0u8; 1];
+        self
+            .transaction(&mut [Operation::Write(&frame[..len]), Operation::Read(&mut buf)])
+            .await?;
+        Ok(buf[0] & 0x01 != 0)
+    }
+
+    /// Locks every block/sector (Global Block/Sector Lock, per Datasheet Section 8.2.41).
+    /// 锁定所有块/扇区（全局块/扇区锁定，依据数据手册第8.2.41节）。
+    pub async fn global_lock(&mut self) -> Result<(), Error<SPI::Error>> {
+        self.global_lock_command(commands::GLOBAL_BLOCK_LOCK, BlockLockMap::locked(self.geometry)).await
+    }
+
+    /// Unlocks every block/sector (Global Block/Sector Unlock, per Datasheet Section 8.2.42).
+    /// 解锁所有块/扇区（全局块/扇区解锁，依据数据手册第8.2.42节）。
+    pub async fn global_unlock(&mut self) -> Result<(), Error<SPI::Error>> {
+        self.global_lock_command(commands::GLOBAL_BLOCK_UNLOCK, BlockLockMap::unlocked(self.geometry)).await
+    }
+
+    /// Sends a global lock/unlock command and updates the cached lock bitmap.
+    /// 发送全局锁定/解锁命令并更新缓存的锁定位图。
+    async fn global_lock_command(&mut self, cmd: u8, locks: BlockLockMap) -> Result<(), Error<SPI::Error>> {
+        self.wait_idle(self.config.idle_timeout).await?; // Wait for device to be idle / 等待设备空闲
+        self.command(commands::WRITE_ENABLE).await?; // Send Write Enable / 发送写使能
+        self.command(cmd).await?;
+        if let Some(WriteProtection::BlockLocks(cached)) = &mut self.protection {
+            *cached = locks;
+        }
         Ok(())
     }
 
-    // 可以根据需要添加更多 API 函数，例如：
-    // pub async fn read_unique_id(&mut self) -> Result<[u8; 8], spi::Error> { ... }
-    // pub async fn chip_erase(&mut self) -> Result<(), spi::Error> { ... } // 注意：耗时很长
-    // pub async fn block_erase_32k(&mut self, address: u32) -> Result<(), spi::Error> { ... }
-    // pub async fn block_erase_64k(&mut self, address: u32) -> Result<(), spi::Error> { ... }
-    // pub async fn deep_power_down(&mut self) -> Result<(), spi::Error> { ... }
-    // pub async fn release_from_power_down(&mut self) -> Result<(), spi::Error> { ... }
-}
\ No newline at end of file
+    /// Switches commands without a dedicated 4-byte opcode to 32-bit addresses
+    /// (Enter 4-Byte Address Mode, parts above 16MB only).
+    /// 将没有专用 4 字节操作码的命令切换为 32 位地址（进入 4 字节地址模式，仅容量超过 16MB 的器件）。
+    ///
+    /// `init` already picks the right opcodes for the detected capacity; this is only needed
+    /// when other software expects the chip in 4-byte mode.
+    /// `init` 已根据检测到的容量选择正确的操作码；只有当其他软件期望芯片处于 4 字节模式时才需要调用。
+    pub async fn enter_4byte_address_mode(&mut self) -> Result<(), Error<SPI::Error>> {
+        self.wait_idle(self.config.idle_timeout).await?; // Wait for device to be idle / 等待设备空闲
+        self.command(commands::ENTER_4BYTE_ADDRESS_MODE).await?;
+        self.address_mode = AddressMode::FourByte;
+        Ok(())
+    }
+
+    /// Returns to 24-bit addresses extended by the Extended Address Register (Exit 4-Byte Address Mode).
+    /// 返回由扩展地址寄存器扩展的 24 位地址（退出 4 字节地址模式）。
+    pub async fn exit_4byte_address_mode(&mut self) -> Result<(), Error<SPI::Error>> {
+        self.wait_idle(self.config.idle_timeout).await?; // Wait for device to be idle / 等待设备空闲
+        self.command(commands::EXIT_4BYTE_ADDRESS_MODE).await?;
+        self.address_mode = AddressMode::ThreeByte;
+        self.extended_address = None; // Rewritten on next use / 下次使用时重新写入
+        Ok(())
+    }
+
+    /// Reads the Extended Address Register (A31-A24 in 3-byte address mode).
+    /// 读取扩展地址寄存器（3 字节地址模式下的 A31-A24）。
+    pub async fn read_extended_address_register(&mut self) -> Result<u8, Error<SPI::Error>> {
+        let value = self.command_read_byte(commands::READ_EXTENDED_ADDRESS).await?;
+        self.extended_address = Some(value);
+        Ok(value)
+    }
+
+    /// Writes the Extended Address Register (volatile, cleared at power-up).
+    /// 写扩展地址寄存器（易失，上电时清零）。
+    pub async fn write_extended_address_register(&mut self, value: u8) -> Result<(), Error<SPI::Error>> {
+        self.wait_idle(self.config.idle_timeout).await?; // Wait for device to be idle / 等待设备空闲
+        self.command(commands::WRITE_ENABLE).await?; // Send Write Enable / 发送写使能
+        self.transaction(&mut [Operation::Write(&[commands::WRITE_EXTENDED_ADDRESS, value])]).await?;
+        self.extended_address = Some(value);
+        Ok(())
+    }
+
+    /// Reads `buf.len()` bytes of security register `index` (1-3) starting at `offset`
+    /// (Read Security Registers, per Datasheet Section 8.2.31).
+    /// 从 `offset` 开始读取安全寄存器 `index`（1-3）的 `buf.len()` 个字节（读取安全寄存器，依据数据手册第8.2.31节）。
+    ///
+    /// Returns `Error::OutOfBounds` for other indices or reads past the end of the register, and
+    /// `Error::Unsupported` in QPI mode.
+    /// 对于其他索引或超出寄存器末尾的读取，返回 `Error::OutOfBounds`；QPI 模式下返回 `Error::Unsupported`。
+    pub async fn read_security_register(&mut self, index: u8, offset: usize, buf: &mut [u8]) -> Result<(), Error<SPI::Error>> {
+        let address = security_register_address(index, offset, buf.len()).ok_or(Error::OutOfBounds)?;
+        self.check_spi_mode()?;
+        self.wait_idle(self.config.idle_timeout).await?; // Wait for device to be idle / 等待设备空闲
+        let (frame, len) = self.address_frame(commands::READ_SECURITY_REGISTER, address).await?;
+        // Send command + address + 1 dummy byte, then read data / 发送命令+地址+1个虚拟字节，然后读取数据
+        self
+            .transaction(&mut [Operation::Write(&frame[..len + 1]), Operation::Read(buf)])
+            .await?;
+        Ok(())
+    }
+
+    /// Programs `data` into security register `index` (1-3) at `offset`
+    /// (Program Security Registers, per Datasheet Section 8.2.30).
+    /// 将 `data` 编程到安全寄存器 `index`（1-3）的 `offset` 处（编程安全寄存器，依据数据手册第8.2.30节）。
+    ///
+    /// Like Page Program this can only clear bits. Returns `Error::OutOfBounds` if `data` does not
+    /// fit in the register, `Error::Protected` if the register is locked and `Error::Unsupported` in QPI mode.
+    /// 与页面编程一样只能清零位。`data` 超出寄存器时返回 `Error::OutOfBounds`，寄存器已锁定时返回 `Error::Protected`，
+    /// QPI 模式下返回 `Error::Unsupported`。
+    pub async fn program_security_register(&mut self, index: u8, offset: usize, data: &[u8]) -> Result<(), Error<SPI::Error>> {
+        let address = security_register_address(index, offset, data.len()).ok_or(Error::OutOfBounds)?;
+        self.check_spi_mode()?;
+        if data.is_empty() {
+            return Ok(());
+        }
+        self.check_security_register_unlocked(index).await?;
+
+        self.wait_idle(self.config.idle_timeout).await?; // Wait for device to be idle / 等待设备空闲
+        let (frame, len) = self.address_frame(commands::PROGRAM_SECURITY_REGISTER, address).await?;
+        self.command(commands::WRITE_ENABLE).await?; // Send Write Enable / 发送写使能
+        // Send command + address + data / 发送命令+地址+数据
+        self
+            .transaction(&mut [Operation::Write(&frame[..len]), Operation::Write(data)])
+            .await?;
+        self.wait_idle(self.config.page_program_timeout).await?; // Wait for write to complete / 等待写入完成
+        Ok(())
+    }
+
+    /// Erases security register `index` (1-3) to 0xFF (Erase Security Registers, per Datasheet Section 8.2.29).
+    /// 将安全寄存器 `index`（1-3）擦除为 0xFF（擦除安全寄存器，依据数据手册第8.2.29节）。
+    ///
+    /// Returns `Error::Protected` if the register is locked and `Error::Unsupported` in QPI mode.
+    /// 寄存器已锁定时返回 `Error::Protected`，QPI 模式下返回 `Error::Unsupported`。
+    pub async fn erase_security_register(&mut self, index: u8) -> Result<(), Error<SPI::Error>> {
+        let address = security_register_address(index, 0, 0).ok_or(Error::OutOfBounds)?;
+        self.check_spi_mode()?;
+        self.check_security_register_unlocked(index).await?;
+
+        self.wait_idle(self.config.idle_timeout).await?; // Wait for device to be idle / 等待设备空闲
+        let (frame, len) = self.address_frame(commands::ERASE_SECURITY_REGISTER, address).await?;
+        self.command(commands::WRITE_ENABLE).await?; // Send Write Enable / 发送写使能
+        self.transaction(&mut [Operation::Write(&frame[..len])]).await?;
+        self.wait_idle(self.config.sector_erase_timeout).await?; // Wait for erase to complete / 等待擦除完成
+        Ok(())
+    }
+
+    /// Returns `true` if security register `index` (1-3) is locked (LB bit in Status Register 2).
+    /// 安全寄存器 `index`（1-3）已锁定（状态寄存器2中的 LB 位）时返回 `true`。
+    pub async fn is_security_register_locked(&mut self, index: u8) -> Result<bool, Error<SPI::Error>> {
+        security_register_address(index, 0, 0).ok_or(Error::OutOfBounds)?;
+        Ok(self.read_status_register_2().await?.lb[index as usize - 1])
+    }
+
+    /// Permanently locks security register `index` (1-3) by setting its LB bit in Status Register 2.
+    /// 通过置位状态寄存器2中对应的 LB 位，永久锁定安全寄存器 `index`（1-3）。
+    ///
+    /// **Irreversible**: LB1-LB3 are one-time programmable; the register can never be programmed
+    /// or erased again. `_confirmation` only exists to make the call deliberate.
+    /// **不可逆**：LB1-LB3 为一次性可编程位；该寄存器将再也无法编程或擦除。`_confirmation` 仅用于确保调用是有意为之。
+    ///
+    /// The LB bit needs a non-volatile write of the whole register. SR2 reads back volatile bits
+    /// (e.g. QE set by `enter_memory_mapped`), so the other bits are taken from the non-volatile
+    /// value this driver saw before its first volatile SR2 write, and the volatile bits are
+    /// restored afterwards with a volatile write. Volatile writes made before this driver instance
+    /// was created (without a power cycle or `software_reset` since) cannot be told apart.
+    /// LB 位需要对整个寄存器进行非易失写入。SR2 读回的是易失位（例如 `enter_memory_mapped` 置位的 QE），
+    /// 因此其他位取自本驱动第一次易失性 SR2 写入之前看到的非易失值，之后再通过易失性写入恢复易失位。
+    /// 无法区分本驱动实例创建之前（且此后未经过上电复位或 `software_reset`）所做的易失性写入。
+    pub async fn lock_security_register(
+        &mut self,
+        index: u8,
+        _confirmation: OtpLockConfirmation,
+    ) -> Result<(), Error<SPI::Error>> {
+        security_register_address(index, 0, 0).ok_or(Error::OutOfBounds)?;
+        let current = self.read_status_register_2().await?;
+        if current.lb[index as usize - 1] {
+            return Ok(());
+        }
+        let mut nonvolatile = self.nonvolatile_sr2.unwrap_or(current);
+        nonvolatile.lb = current.lb;
+        nonvolatile.lb[index as usize - 1] = true;
+        nonvolatile.sus = false;
+        self.write_status_2(nonvolatile, StatusWriteMode::NonVolatile, current).await?;
+        // The non-volatile write also replaced the volatile bits / 非易失写入同时覆盖了易失位
+        let volatile = StatusRegister2 { lb: nonvolatile.lb, sus: false, ..current };
+        if volatile != nonvolatile {
+            self.write_status_2(volatile, StatusWriteMode::Volatile, nonvolatile).await?;
+        }
+        Ok(())
+    }
+
+    /// Rejects program/erase of a locked security register, which the chip would silently ignore.
+    /// 拒绝对已锁定安全寄存器的编程/擦除，否则芯片会静默忽略这些请求。
+    async fn check_security_register_unlocked(&mut self, index: u8) -> Result<(), Error<SPI::Error>> {
+        if self.is_security_register_locked(index).await? {
+            return Err(Error::Protected);
+        }
+        Ok(())
+    }
+
+    /// Puts the chip into deep power-down (Power-down, per Datasheet Section 8.2.21).
+    /// 使芯片进入深度掉电（掉电，依据数据手册第8.2.21节）。
+    ///
+    /// Waits for a running operation first, since `B9h` is ignored while BUSY is set. The next
+    /// command releases the chip again automatically.
+    /// 先等待正在运行的操作完成，因为 BUSY 置位时 `B9h` 会被忽略。下一条命令会自动使芯片退出掉电。
+    pub async fn deep_power_down(&mut self) -> Result<(), Error<SPI::Error>> {
+        self.wait_idle(self.config.idle_timeout).await?; // Wait for device to be idle / 等待设备空闲
+        self.command(commands::POWER_DOWN).await?;
+        Timer::after(self.config.power_down_latency).await; // tDP
+        self.power_state = PowerState::PowerDown;
+        Ok(())
+    }
+
+    /// Releases the chip from deep power-down (Release Power-down, per Datasheet Section 8.2.22).
+    /// 使芯片退出深度掉电（释放掉电，依据数据手册第8.2.22节）。
+    ///
+    /// Always sends `ABh`, so it also wakes a chip left powered down by an earlier boot.
+    /// 总是发送 `ABh`，因此也能唤醒由先前启动遗留在掉电状态的芯片。
+    pub async fn release_from_power_down(&mut self) -> Result<(), Error<SPI::Error>> {
+        self.check_unmapped()?;
+        self.spi.write(&[commands::RELEASE_POWER_DOWN]).await?;
+        Timer::after(self.config.release_latency).await; // tRES1
+        self.power_state = PowerState::Active;
+        self.last_activity = Instant::now();
+        Ok(())
+    }
+
+    /// Resets the chip (Enable Reset + Reset Device, per Datasheet Section 8.2.43) and waits tRST.
+    /// 复位芯片（复位使能 + 复位器件，依据数据手册第8.2.43节）并等待 tRST。
+    ///
+    /// A program or erase in progress is aborted, leaving its data undefined. Volatile status bits,
+    /// block locks, the address mode and the Extended Address Register return to their power-up values,
+    /// and the chip leaves QPI mode.
+    /// 正在进行的编程或擦除会被中止，其数据处于不确定状态。易失性状态位、块锁定、地址模式和扩展地址寄存器恢复为上电值，
+    /// 芯片退出 QPI 模式。
+    pub async fn software_reset(&mut self) -> Result<(), Error<SPI::Error>> {
+        self.command(commands::ENABLE_RESET).await?;
+        self.command(commands::RESET_DEVICE).await?;
+        Timer::after(self.config.reset_latency).await; // tRST
+        self.interface = InterfaceMode::Spi;
+        self.pending_erase = None;
+        self.protection = None;
+        self.extended_address = None;
+        self.quad_enabled = None;
+        self.nonvolatile_sr2 = None; // Reloaded from the non-volatile bits / 已从非易失位重新加载
+        self.address_mode = self.read_address_mode().await?;
+        Ok(())
+    }
+
+    /// Time at which `power_down_if_idle` will put the chip to sleep, or `None` if
+    /// `Config::auto_power_down` is off, the chip is already powered down, an erase is running or
+    /// the flash is memory-mapped.
+    /// `power_down_if_idle` 将使芯片进入睡眠的时间；如果 `Config::auto_power_down` 关闭、芯片已掉电、擦除正在运行
+    /// 或 Flash 处于内存映射模式，返回 `None`。
+    pub fn power_down_deadline(&self) -> Option<Instant> {
+        let timeout = self.config.auto_power_down?;
+        let idle = self.power_state == PowerState::Active && self.pending_erase.is_none() && self.mapped_window.is_none();
+        idle.then(|| self.last_activity + timeout)
+    }
+
+    /// Enters deep power-down if the chip has been idle for `Config::auto_power_down`.
+    /// Call it periodically, or after `Timer::at(power_down_deadline)`; `SharedFlash::run_auto_power_down`
+    /// does the latter. Returns `true` if the chip was put to sleep.
+    /// 如果芯片已空闲 `Config::auto_power_down`，进入深度掉电。可以周期性调用，或在
+    /// `Timer::at(power_down_deadline)` 之后调用；`SharedFlash::run_auto_power_down` 即采用后者。使芯片进入睡眠时返回 `true`。
+    pub async fn power_down_if_idle(&mut self) -> Result<bool, Error<SPI::Error>> {
+        match self.power_down_deadline() {
+            Some(deadline) if Instant::now() >= deadline => {
+                self.deep_power_down().await?;
+                Ok(true)
+            }
+            _ => Ok(false),
+        }
+    }
+}
+
+impl<SPI: MultiIoSpiDevice> W25q128jv<SPI> {
+    /// Reads data with a single, dual or quad line Fast Read (per Datasheet Section 8.2.8-8.2.12).
+    /// 使用单线、双线或四线快速读取读取数据（依据数据手册第8.2.8-8.2.12节）。
+    ///
+    /// The quad modes set QE first if it is not already set, with a volatile write so the
+    /// non-volatile bit is not worn. Parts above 16MB use the 4-byte opcodes. In QPI mode every
+    /// read is already 4-4-4, so this falls back to `fast_read`.
+    /// 四线模式会在 QE 尚未置位时先将其置位，使用易失性写入以避免磨损非易失位。容量超过 16MB 的器件使用 4 字节操作码。
+    /// QPI 模式下所有读取本身就是 4-4-4，因此改用 `fast_read`。
+    ///
+    /// # Arguments / 参数
+    /// * `mode`: Instruction and line widths. / 指令及线宽。
+    /// * `address`: The address to start reading from. / 开始读取的地址。
+    /// * `buf`: The buffer to read data into. / 用于存储读取数据的缓冲区。
+    pub async fn read_multi_io(&mut self, mode: ReadMode, address: u32, buf: &mut [u8]) -> Result<(), Error<SPI::Error>> {
+        if self.interface == InterfaceMode::Qpi {
+            return self.fast_read(address, buf).await;
+        }
+        self.check_bounds(address, buf.len())?; // Reject reads past the end of the array / 拒绝超出存储阵列的读取
+        if mode.needs_quad_enable() && self.quad_enabled != Some(true) {
+            self.set_quad_enable(true, StatusWriteMode::Volatile).await?;
+        }
+        let suspended = self.prepare_read(address, buf.len()).await?; // Wait for idle or suspend an erase / 等待空闲或暂停擦除
+
+        let transfer = match commands::four_byte(mode.opcode()).filter(|_| self.geometry.address_bytes == 4) {
+            Some(opcode) => mode.transfer(opcode, address, 4),
+            None => mode.transfer(mode.opcode(), address, 3),
+        };
+        self.wake().await?;
+        self.spi.read_multi_io(transfer, buf).await?;
+        self.last_activity = Instant::now();
+        if suspended {
+            self.resume().await?; // Let the erase continue / 让擦除继续进行
+        }
+        Ok(())
+    }
+}
+
+impl<SPI: QpiSpiDevice> W25q128jv<SPI> {
+    /// Enters QPI mode (Enable QPI `38h`), after which every command runs on
+    /// IO0-IO3. QE is set first with a volatile write, and `read_parameters()` are sent once in QPI.
+    /// 进入 QPI 模式（启用 QPI `38h`），之后所有命令都通过 IO0-IO3 执行。先以易失性写入置位 QE，
+    /// 进入 QPI 后发送一次 `read_parameters()`。
+    ///
+    /// The JEDEC ID is read back on four lines to confirm the switch; a part without QPI (such as the
+    /// W25Q128JV) ignores `38h`, in which case the backend is returned to SPI mode and
+    /// `Error::Unsupported` is returned.
+    /// 通过四线读回 JEDEC ID 以确认切换成功；不支持 QPI 的器件（例如 W25Q128JV）会忽略 `38h`，
+    /// 此时后端会恢复为 SPI 模式，并返回 `Error::Unsupported`。
+    pub async fn enter_qpi(&mut self) -> Result<(), Error<SPI::Error>> {
+        if self.interface == InterfaceMode::Qpi {
+            return Ok(());
+        }
+        // QPI needs IO2/IO3, which QE = 1 frees from /WP and /HOLD / QPI 需要 IO2/IO3，QE = 1 使其不再作为 /WP 和 /HOLD
+        if self.quad_enabled != Some(true) {
+            self.set_quad_enable(true, StatusWriteMode::Volatile).await?;
+        }
+        self.wait_idle(self.config.idle_timeout).await?; // Wait for device to be idle / 等待设备空闲
+        self.command(commands::ENTER_QPI).await?;
+        self.interface = InterfaceMode::Qpi;
+
+        // The memory type may change in QPI mode (60h on the W25Q128FV), the capacity does not
+        // QPI 模式下内存类型可能改变（W25Q128FV 为 60h），容量不变
+        let (manufacturer, memory_type, capacity) = self.read_jedec_id().await?;
+        let confirmed = FlashGeometry::from_jedec_id(manufacturer, memory_type, capacity)
+            .is_some_and(|geometry| geometry.capacity == self.geometry.capacity);
+        if !confirmed {
+            self.command(commands::EXIT_QPI).await?; // Switch the backend back / 使后端切换回来
+            self.interface = InterfaceMode::Spi;
+            return Err(Error::Unsupported);
+        }
+        self.send_read_parameters().await
+    }
+
+    /// Leaves QPI mode (Disable QPI `FFh`). / 退出 QPI 模式（禁用 QPI `FFh`）。
+    pub async fn exit_qpi(&mut self) -> Result<(), Error<SPI::Error>> {
+        if self.interface == InterfaceMode::Spi {
+            return Ok(());
+        }
+        self.wait_idle(self.config.idle_timeout).await?; // Wait for device to be idle / 等待设备空闲
+        self.command(commands::EXIT_QPI).await?;
+        self.interface = InterfaceMode::Spi;
+        Ok(())
+    }
+
+    /// Sets the dummy clocks of Fast Read in QPI mode and the wrap length (Set Read Parameters `C0h`).
+    /// 设置 QPI 模式下快速读取的虚拟时钟数和回绕长度（设置读取参数 `C0h`）。
+    ///
+    /// `C0h` is only decoded in QPI mode; in SPI mode the parameters are kept and sent by `enter_qpi`.
+    /// `C0h` 仅在 QPI 模式下被解析；SPI 模式下参数会被保存，并由 `enter_qpi` 发送。
+    pub async fn set_read_parameters(&mut self, parameters: ReadParameters) -> Result<(), Error<SPI::Error>> {
+        self.read_parameters = parameters;
+        if self.interface == InterfaceMode::Qpi {
+            self.send_read_parameters().await?;
+        }
+        Ok(())
+    }
+
+    /// Sends the tracked read parameters / 发送跟踪的读取参数
+    async fn send_read_parameters(&mut self) -> Result<(), Error<SPI::Error>> {
+        self.wait_idle(self.config.idle_timeout).await?; // Wait for device to be idle / 等待设备空闲
+        let bits = self.read_parameters.bits();
+        self.transaction(&mut [Operation::Write(&[commands::SET_READ_PARAMETERS, bits])]).await
+    }
+}
+
+impl<SPI: MemoryMappedSpiDevice> W25q128jv<SPI> {
+    /// Maps the whole array into the backend's address window for execute-in-place (XIP) and
+    /// returns it, using continuous Fast Read Quad I/O (`EBh` with M7-0 = 20h).
+    /// 使用连续四线 I/O 快速读取（`EBh`，M7-0 = 20h）将整个阵列映射到后端的地址窗口以就地执行（XIP），并返回该窗口。
+    ///
+    /// A background erase is finished and QE is set (volatile) first. Until `exit_memory_mapped`
+    /// every other command returns `Error::Unsupported`, so program/erase need the flash unmapped.
+    /// Not available in QPI mode.
+    /// 先完成后台擦除并（易失性地）置位 QE。在 `exit_memory_mapped` 之前其他所有命令都返回 `Error::Unsupported`，
+    /// 因此编程/擦除需要先解除映射。QPI 模式下不可用。
+    ///
+    /// While mapped, `release` returns the driver unchanged. The backend keeps the window readable
+    /// until `exit_memory_mapped`, even if the driver is dropped.
+    /// 映射期间 `release` 原样返回驱动。后端在 `exit_memory_mapped` 之前保持窗口可读，即使驱动被丢弃。
+    pub async fn enter_memory_mapped(&mut self) -> Result<&'static [u8], Error<SPI::Error>> {
+        if let Some(window) = self.mapped_window {
+            return Ok(window);
+        }
+        self.check_spi_mode()?;
+        if self.quad_enabled != Some(true) {
+            self.set_quad_enable(true, StatusWriteMode::Volatile).await?;
+        }
+        self.wait_idle(self.config.idle_timeout).await?; // Wait for device to be idle / 等待设备空闲
+        self.wake().await?;
+        let mode = ReadMode::QuadIo;
+        let transfer = match commands::four_byte(mode.opcode()).filter(|_| self.geometry.address_bytes == 4) {
+            Some(opcode) => mode.transfer(opcode, 0, 4),
+            None => mode.transfer(mode.opcode(), 0, 3),
+        };
+        // M5-4 = 10 keeps the chip in continuous read mode / M5-4 = 10 使芯片保持连续读取模式
+        let read = MultiIoRead { mode_bits: Some(0x20), ..transfer };
+        let window = self.spi.map(read, self.geometry.capacity)?;
+        self.mapped_window = Some(window);
+        Ok(window)
+    }
+
+    /// Leaves memory-mapped mode and takes the chip out of continuous read mode, so every command
+    /// is available again.
+    /// 退出内存映射模式并使芯片退出连续读取模式，从而恢复所有命令。
+    ///
+    /// # Safety
+    /// No slice returned by `enter_memory_mapped` may be used afterwards, and no code may still
+    /// execute from the window.
+    /// 之后不得再使用 `enter_memory_mapped` 返回的任何切片，也不得仍有代码在窗口中执行。
+    pub async unsafe fn exit_memory_mapped(&mut self) -> Result<(), Error<SPI::Error>> {
+        if self.mapped_window.is_none() {
+            return Ok(());
+        }
+        // SAFETY: guaranteed by the caller / 由调用者保证
+        unsafe { self.spi.unmap()? };
+        self.mapped_window = None;
+        // Mode bits M5-4 != 10 on IO0 / 在 IO0 上输入模式位 M5-4 != 10
+        self.transaction(&mut [Operation::Write(&[0xFF; 2])]).await
+    }
+}
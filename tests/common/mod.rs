@@ -0,0 +1,306 @@
+// tests/common/mod.rs
+
+//! Command-level SPI mock shared by the host test suites.
+//! 主机测试共用的命令级 SPI 模拟总线。
+//!
+//! `MockSpi` records every CS-framed transaction as a list of `Op`s and replays scripted
+//! MISO bytes into the reads of successive frames. Unscripted reads return 0x00, which
+//! also reads back as an idle Status Register 1.
+//! `MockSpi` 将每个 CS 包围的事务记录为 `Op` 列表，并把预设的 MISO 字节依次填入后续帧的读操作。
+//! 未预设的读操作返回 0x00，即状态寄存器1为空闲。
+
+#![allow(dead_code)]
+
+use std::collections::VecDeque;
+#[cfg(feature = "shared-bus")]
+use std::{cell::RefCell, convert::Infallible, rc::Rc};
+
+#[cfg(feature = "shared-bus")]
+use embassy_embedded_hal::SetConfig;
+use embedded_hal::spi::{ErrorKind, ErrorType, Operation};
+use w25q128::{MemoryMappedSpiDevice, MultiIoRead, MultiIoSpiDevice, QpiSpiDevice};
+
+/// One operation inside a CS frame / CS 帧内的一个操作
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Op {
+    /// Bytes sent on MOSI / MOSI 上发送的字节
+    Write(Vec<u8>),
+    /// Number of bytes read from MISO / 从 MISO 读取的字节数
+    Read(usize),
+    /// Delay while CS is held low / CS 保持低电平期间的延时
+    Delay(u32),
+    /// Dual/quad read of the given length / 给定长度的双线/四线读取
+    MultiIo(MultiIoRead, usize),
+    /// Memory-mapped window of the given length opened / 打开给定长度的内存映射窗口
+    Map(MultiIoRead, usize),
+    /// Memory-mapped window closed / 关闭内存映射窗口
+    Unmap,
+}
+
+/// Shorthand for `Op::Write` / `Op::Write` 简写
+pub fn w(bytes: &[u8]) -> Op {
+    Op::Write(bytes.to_vec())
+}
+
+/// Shorthand for `Op::Read` / `Op::Read` 简写
+pub fn r(len: usize) -> Op {
+    Op::Read(len)
+}
+
+/// Recording, scriptable SPI device / 可记录、可预设响应的 SPI 设备
+#[derive(Default)]
+pub struct MockSpi {
+    frames: Vec<Vec<Op>>,
+    responses: VecDeque<Vec<u8>>,
+    fill: u8,
+    fail_after: Option<usize>,
+}
+
+impl MockSpi {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Queues the MISO bytes for the next frame that contains a read.
+    /// 为下一个包含读操作的帧预设 MISO 字节。
+    pub fn respond(&mut self, miso: &[u8]) -> &mut Self {
+        self.responses.push_back(miso.to_vec());
+        self
+    }
+
+    /// Sets the byte returned by unscripted reads, e.g. 0xFF for a stuck MISO line.
+    /// 设置未预设读操作返回的字节，例如 0xFF 模拟卡住的 MISO 线。
+    pub fn fill(&mut self, miso: u8) -> &mut Self {
+        self.fill = miso;
+        self
+    }
+
+    /// Makes the transaction after `frames` successful ones fail with a bus error.
+    /// 在成功完成 `frames` 个事务后，使下一个事务返回总线错误。
+    pub fn fail_after(&mut self, frames: usize) -> &mut Self {
+        self.fail_after = Some(frames);
+        self
+    }
+
+    /// Returns and clears the recorded frames / 返回并清空已记录的帧
+    pub fn take_frames(&mut self) -> Vec<Vec<Op>> {
+        std::mem::take(&mut self.frames)
+    }
+
+    fn run(&mut self, operations: &mut [Operation<'_, u8>]) -> Result<(), ErrorKind> {
+        if self.fail_after == Some(self.frames.len()) {
+            self.fail_after = None;
+            return Err(ErrorKind::Other);
+        }
+        let has_read = operations
+            .iter()
+            .any(|op| matches!(op, Operation::Read(_) | Operation::Transfer(..) | Operation::TransferInPlace(_)));
+        let mut miso = if has_read { self.responses.pop_front().unwrap_or_default() } else { Vec::new() }.into_iter();
+        let mut frame = Vec::new();
+        for op in operations {
+            match op {
+                Operation::Write(data) => frame.push(Op::Write(data.to_vec())),
+                Operation::Read(buf) => {
+                    buf.iter_mut().for_each(|b| *b = miso.next().unwrap_or(self.fill));
+                    frame.push(Op::Read(buf.len()));
+                }
+                Operation::Transfer(read, write) => {
+                    frame.push(Op::Write(write.to_vec()));
+                    read.iter_mut().for_each(|b| *b = miso.next().unwrap_or(self.fill));
+                    frame.push(Op::Read(read.len()));
+                }
+                Operation::TransferInPlace(buf) => {
+                    frame.push(Op::Write(buf.to_vec()));
+                    buf.iter_mut().for_each(|b| *b = miso.next().unwrap_or(self.fill));
+                    frame.push(Op::Read(buf.len()));
+                }
+                Operation::DelayNs(ns) => frame.push(Op::Delay(*ns)),
+            }
+        }
+        self.frames.push(frame);
+        Ok(())
+    }
+}
+
+impl MultiIoSpiDevice for MockSpi {
+    async fn read_multi_io(&mut self, read: MultiIoRead, buf: &mut [u8]) -> Result<(), Self::Error> {
+        if self.fail_after == Some(self.frames.len()) {
+            self.fail_after = None;
+            return Err(ErrorKind::Other);
+        }
+        let mut miso = self.responses.pop_front().unwrap_or_default().into_iter();
+        buf.iter_mut().for_each(|b| *b = miso.next().unwrap_or(self.fill));
+        self.frames.push(vec![Op::MultiIo(read, buf.len())]);
+        Ok(())
+    }
+}
+
+impl QpiSpiDevice for MockSpi {}
+
+impl MemoryMappedSpiDevice for MockSpi {
+    fn map(&mut self, read: MultiIoRead, len: usize) -> Result<&'static [u8], Self::Error> {
+        self.frames.push(vec![Op::Map(read, len)]);
+        Ok(vec![self.fill; len].leak())
+    }
+
+    unsafe fn unmap(&mut self) -> Result<(), Self::Error> {
+        self.frames.push(vec![Op::Unmap]);
+        Ok(())
+    }
+}
+
+impl ErrorType for MockSpi {
+    type Error = ErrorKind;
+}
+
+impl embedded_hal_async::spi::SpiDevice for MockSpi {
+    async fn transaction(&mut self, operations: &mut [Operation<'_, u8>]) -> Result<(), Self::Error> {
+        self.run(operations)
+    }
+}
+
+impl embedded_hal::spi::SpiDevice for MockSpi {
+    fn transaction(&mut self, operations: &mut [Operation<'_, u8>]) -> Result<(), Self::Error> {
+        self.run(operations)
+    }
+}
+
+/// Event seen on a shared bus, in order / 共享总线上依次发生的事件
+#[cfg(feature = "shared-bus")]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BusEvent {
+    /// CS of the named device driven low / 指定设备的 CS 拉低
+    Select(&'static str),
+    /// CS of the named device driven high / 指定设备的 CS 拉高
+    Deselect(&'static str),
+    /// Bus reconfigured to the given frequency / 总线重新配置为给定频率
+    Config(u32),
+    /// Bytes sent on MOSI / MOSI 上发送的字节
+    Write(Vec<u8>),
+    /// Number of bytes read from MISO / 从 MISO 读取的字节数
+    Read(usize),
+}
+
+/// Log shared by a `MockBus` and its `MockCs` pins / `MockBus` 与其 `MockCs` 引脚共用的日志
+#[cfg(feature = "shared-bus")]
+pub type BusLog = Rc<RefCell<Vec<BusEvent>>>;
+
+/// Recording `SpiBus` for `embassy_embedded_hal` shared-bus devices; its config is a frequency.
+/// 供 `embassy_embedded_hal` 共享总线设备使用的可记录 `SpiBus`，其配置为频率。
+#[cfg(feature = "shared-bus")]
+pub struct MockBus {
+    log: BusLog,
+    miso: VecDeque<u8>,
+}
+
+#[cfg(feature = "shared-bus")]
+impl MockBus {
+    pub fn new(log: BusLog) -> Self {
+        Self { log, miso: VecDeque::new() }
+    }
+
+    /// Queues MISO bytes for the following reads / 为后续读操作预设 MISO 字节
+    pub fn respond(&mut self, miso: &[u8]) -> &mut Self {
+        self.miso.extend(miso);
+        self
+    }
+
+    fn read_into(&mut self, buf: &mut [u8]) {
+        buf.iter_mut().for_each(|b| *b = self.miso.pop_front().unwrap_or(0));
+        self.log.borrow_mut().push(BusEvent::Read(buf.len()));
+    }
+}
+
+#[cfg(feature = "shared-bus")]
+impl ErrorType for MockBus {
+    type Error = Infallible;
+}
+
+#[cfg(feature = "shared-bus")]
+impl SetConfig for MockBus {
+    type Config = u32;
+    type ConfigError = ();
+
+    fn set_config(&mut self, config: &u32) -> Result<(), ()> {
+        self.log.borrow_mut().push(BusEvent::Config(*config));
+        Ok(())
+    }
+}
+
+#[cfg(feature = "shared-bus")]
+impl embedded_hal_async::spi::SpiBus for MockBus {
+    async fn read(&mut self, words: &mut [u8]) -> Result<(), Infallible> {
+        self.read_into(words);
+        Ok(())
+    }
+
+    async fn write(&mut self, words: &[u8]) -> Result<(), Infallible> {
+        self.log.borrow_mut().push(BusEvent::Write(words.to_vec()));
+        Ok(())
+    }
+
+    async fn transfer(&mut self, read: &mut [u8], write: &[u8]) -> Result<(), Infallible> {
+        self.log.borrow_mut().push(BusEvent::Write(write.to_vec()));
+        self.read_into(read);
+        Ok(())
+    }
+
+    async fn transfer_in_place(&mut self, words: &mut [u8]) -> Result<(), Infallible> {
+        self.log.borrow_mut().push(BusEvent::Write(words.to_vec()));
+        self.read_into(words);
+        Ok(())
+    }
+
+    async fn flush(&mut self) -> Result<(), Infallible> {
+        Ok(())
+    }
+}
+
+/// CS pin that logs its edges to the bus log / 将电平变化记录到总线日志的 CS 引脚
+#[cfg(feature = "shared-bus")]
+pub struct MockCs {
+    name: &'static str,
+    log: BusLog,
+}
+
+#[cfg(feature = "shared-bus")]
+impl MockCs {
+    pub fn new(name: &'static str, log: BusLog) -> Self {
+        Self { name, log }
+    }
+}
+
+#[cfg(feature = "shared-bus")]
+impl embedded_hal::digital::ErrorType for MockCs {
+    type Error = Infallible;
+}
+
+#[cfg(feature = "shared-bus")]
+impl embedded_hal::digital::OutputPin for MockCs {
+    fn set_low(&mut self) -> Result<(), Infallible> {
+        self.log.borrow_mut().push(BusEvent::Select(self.name));
+        Ok(())
+    }
+
+    fn set_high(&mut self) -> Result<(), Infallible> {
+        self.log.borrow_mut().push(BusEvent::Deselect(self.name));
+        Ok(())
+    }
+}
+
+// `embassy-sync` logs through defmt; host test binaries have no RTT transport, so drop the frames
+// `embassy-sync` 通过 defmt 输出日志；主机测试程序没有 RTT 传输，因此丢弃这些日志帧
+#[defmt::global_logger]
+struct NoopLogger;
+
+unsafe impl defmt::Logger for NoopLogger {
+    fn acquire() {}
+    unsafe fn flush() {}
+    unsafe fn release() {}
+    unsafe fn write(_bytes: &[u8]) {}
+}
+
+#[defmt::panic_handler]
+fn defmt_panic() -> ! {
+    panic!("defmt panic")
+}
@@ -0,0 +1,1164 @@
+// tests/protocol.rs
+
+//! Command-level protocol tests: checks the exact bytes `W25q128jv` puts on the bus.
+//! 命令级协议测试：检查 `W25q128jv` 在总线上发送的确切字节。
+
+mod common;
+
+#[cfg(feature = "shared-bus")]
+use common::{BusEvent, BusLog, MockBus, MockCs};
+use common::{r, w, MockSpi, Op};
+#[cfg(feature = "shared-bus")]
+use embassy_embedded_hal::shared_bus::asynch::spi::SpiDeviceWithConfig;
+use embassy_futures::{block_on, join::join};
+#[cfg(feature = "shared-bus")]
+use embassy_futures::yield_now;
+use embassy_sync::blocking_mutex::raw::NoopRawMutex;
+// `embassy-embedded-hal` 0.3 shares the bus through an embassy-sync 0.6 mutex
+// `embassy-embedded-hal` 0.3 通过 embassy-sync 0.6 的互斥锁共享总线
+#[cfg(feature = "shared-bus")]
+use embassy_sync_06::{blocking_mutex::raw::NoopRawMutex as BusRawMutex, mutex::Mutex as BusMutex};
+#[cfg(feature = "shared-bus")]
+use embedded_hal_async::spi::SpiDevice;
+use embassy_time::Duration;
+use embedded_hal::spi::ErrorKind;
+use w25q128::{
+    AddressMode, Config, DriveStrength, DummyClocks, Error, FlashGeometry, InterfaceMode, MultiIoRead, OtpLockConfirmation, PowerState, ProtectionRegion, ReadMode, RecoverySteps, StatusRegister1, StatusRegister2, StatusRegister3, StatusWriteMode,
+    ReadParameters, SharedFlash, W25q128jv, WrapLength, FLASH_SIZE, PAGE_SIZE, SECTOR_SIZE,
+};
+
+/// Read Status Register 1 frame issued by `wait_idle` / `wait_idle` 发出的读状态寄存器1帧
+fn status() -> Vec<Op> {
+    vec![w(&[0x05]), r(1)]
+}
+
+/// Status Register 1, 2 and 3 reads that fetch the write protection before the first program/erase
+/// 首次编程/擦除前读取写保护设置的状态寄存器1、2、3读取帧
+fn protection_reads() -> Vec<Vec<Op>> {
+    vec![status(), vec![w(&[0x35]), r(1)], vec![w(&[0x15]), r(1)]]
+}
+
+/// Write Enable frame / 写使能帧
+fn write_enable() -> Vec<Op> {
+    vec![w(&[0x06])]
+}
+
+#[test]
+fn init_toggles_cs_without_clocks_then_reads_jedec_id() {
+    let mut spi = MockSpi::new();
+    spi.respond(&[0xEF, 0x40, 0x18]);
+    block_on(W25q128jv::new(&mut spi).init()).unwrap();
+    assert_eq!(spi.take_frames(), vec![vec![Op::Delay(10_000)], vec![w(&[0x9F]), r(3)]]);
+}
+
+#[test]
+fn init_detects_geometry_from_jedec_id() {
+    for (id, capacity) in [(0x15, 2), (0x16, 4), (0x17, 8), (0x18, 16), (0x19, 32), (0x20, 64)] {
+        let mut spi = MockSpi::new();
+        spi.respond(&[0xEF, 0x70, id]);
+        let mut flash = W25q128jv::new(&mut spi);
+        block_on(flash.init()).unwrap();
+        let geometry = *flash.geometry();
+        assert_eq!(geometry.jedec_id, (0xEF, 0x70, id));
+        assert_eq!(geometry.capacity, capacity * 1024 * 1024);
+        assert_eq!(geometry.address_bytes, if capacity > 16 { 4 } else { 3 });
+    }
+}
+
+#[test]
+fn init_rejects_unknown_parts() {
+    for id in [[0xC2, 0x20, 0x18], [0xEF, 0x50, 0x18], [0xEF, 0x40, 0x14]] {
+        let mut spi = MockSpi::new();
+        // Another part answers the same after every recovery step / 其他器件在每个恢复步骤之后都给出相同的应答
+        for _ in 0..4 {
+            spi.respond(&id);
+        }
+        spi.respond(&[0x00]).respond(&id).respond(&id);
+        let mut flash = W25q128jv::new(&mut spi);
+        let result = block_on(flash.init());
+        assert_eq!(result, Err(Error::UnexpectedJedecId { manufacturer: id[0], memory_type: id[1], capacity: id[2] }));
+        assert_eq!(*flash.geometry(), FlashGeometry::W25Q128);
+    }
+}
+
+#[test]
+fn init_recovers_until_the_jedec_id_is_valid() {
+    let mut spi = MockSpi::new();
+    spi.respond(&[0xFF; 3]).respond(&[0xFF; 3]).respond(&[0x00; 3]).respond(&[0xFF; 3]);
+    // Not busy, so nothing to wait for / 不忙，无需等待
+    spi.respond(&[0x00]).respond(&[0xFF; 3]).respond(&[0xEF, 0x40, 0x18]);
+    let steps = block_on(W25q128jv::new(&mut spi).init()).unwrap();
+    assert_eq!(steps, RecoverySteps { exit_qpi: true, exit_continuous_read: true, release_power_down: true, wait_busy: false, software_reset: true });
+    let jedec = vec![w(&[0x9F]), r(3)];
+    assert_eq!(
+        spi.take_frames(),
+        vec![
+            vec![Op::Delay(10_000)],
+            jedec.clone(),
+            vec![w(&[0xFF])],
+            jedec.clone(),
+            vec![w(&[0xFF, 0xFF])],
+            jedec.clone(),
+            vec![w(&[0xAB])],
+            jedec.clone(),
+            status(),
+            jedec.clone(),
+            vec![w(&[0x66])],
+            vec![w(&[0x99])],
+            jedec,
+        ]
+    );
+
+    // Stops at the first step that helps / 在第一个起作用的步骤处停止
+    let mut spi = MockSpi::new();
+    spi.respond(&[0xFF; 3]).respond(&[0xEF, 0x40, 0x18]);
+    let steps = block_on(W25q128jv::new(&mut spi).init()).unwrap();
+    assert_eq!(steps, RecoverySteps { exit_qpi: true, ..RecoverySteps::default() });
+    assert!(steps.any());
+
+    // A running program/erase is waited for instead of reset / 等待正在进行的编程/擦除完成，而不是复位
+    let mut spi = MockSpi::new();
+    for _ in 0..4 {
+        spi.respond(&[0xFF; 3]);
+    }
+    spi.respond(&[0x01]).respond(&[0x01]).respond(&[0x00]).respond(&[0xEF, 0x40, 0x18]);
+    let steps = block_on(W25q128jv::new(&mut spi).init()).unwrap();
+    assert_eq!(steps, RecoverySteps { exit_qpi: true, exit_continuous_read: true, release_power_down: true, wait_busy: true, software_reset: false });
+    let frames = spi.take_frames();
+    assert_eq!(&frames[frames.len() - 4..], &[status(), status(), status(), vec![w(&[0x9F]), r(3)]]);
+    assert!(!frames.contains(&vec![w(&[0x66])]));
+}
+
+#[test]
+fn software_reset_sends_enable_then_reset() {
+    let mut spi = MockSpi::new();
+    block_on(W25q128jv::new(&mut spi).software_reset()).unwrap();
+    assert_eq!(spi.take_frames(), vec![vec![w(&[0x66])], vec![w(&[0x99])]]);
+}
+
+#[test]
+fn init_cross_checks_sfdp_density() {
+    let mut spi = MockSpi::new();
+    // W25Q64 JEDEC ID, but the BFPT reports 128Mbit / W25Q64 的 JEDEC ID，但 BFPT 报告 128Mbit
+    spi.respond(&[0xEF, 0x40, 0x17]);
+    spi.respond(&[0x53, 0x46, 0x44, 0x50, 0x05, 0x01, 0x00, 0xFF]);
+    spi.respond(&[0x00, 0x05, 0x01, 0x09, 0x80, 0x00, 0x00, 0xFF]);
+    let bfpt: Vec<u8> = [0xFFF9_20E5u32, 0x07FF_FFFF, 0x6B08_EB44, 0xBB42_3B08, 0xFFFF_FFEE, 0x0000_FFFF, 0x0000_FFFF, 0x520F_200C, 0x0000_D810]
+        .iter()
+        .flat_map(|dword| dword.to_le_bytes())
+        .collect();
+    spi.respond(&bfpt);
+    let config = Config { verify_sfdp: true, ..Config::default() };
+    let mut flash = W25q128jv::new_with_config(&mut spi, config);
+    assert_eq!(block_on(flash.init()), Err(Error::GeometryMismatch));
+    assert_eq!(*flash.geometry(), FlashGeometry::W25Q128);
+}
+
+#[test]
+fn detected_geometry_bounds_operations() {
+    let mut spi = MockSpi::new();
+    spi.respond(&[0xEF, 0x40, 0x17]);
+    let mut flash = W25q128jv::new(&mut spi);
+    block_on(flash.init()).unwrap();
+    let mut buf = [0u8; 1];
+    assert_eq!(block_on(flash.read_data(0x80_0000, &mut buf)), Err(Error::OutOfBounds));
+    assert_eq!(block_on(flash.erase_sector_4k(0x80_0000)), Err(Error::OutOfBounds));
+    assert_eq!(block_on(flash.erase_range(0, 0x100_0000)), Err(Error::OutOfBounds));
+}
+
+#[test]
+fn read_jedec_id_sends_9f_and_reads_three_bytes() {
+    let mut spi = MockSpi::new();
+    spi.respond(&[0xEF, 0x40, 0x18]);
+    let id = block_on(W25q128jv::new(&mut spi).read_jedec_id()).unwrap();
+    assert_eq!(id, (0xEF, 0x40, 0x18));
+    assert_eq!(spi.take_frames(), vec![vec![w(&[0x9F]), r(3)]]);
+}
+
+#[test]
+fn read_manufacturer_device_id_sends_90_and_zero_address() {
+    let mut spi = MockSpi::new();
+    spi.respond(&[0xEF, 0x17]);
+    let ids = block_on(W25q128jv::new(&mut spi).read_manufacturer_device_id()).unwrap();
+    assert_eq!(ids, (0xEF, 0x17));
+    assert_eq!(spi.take_frames(), vec![vec![w(&[0x90, 0x00, 0x00, 0x00]), r(2)]]);
+}
+
+#[test]
+fn read_unique_id_sends_4b_and_four_dummy_bytes() {
+    let mut spi = MockSpi::new();
+    spi.respond(&[0x01, 0x23, 0x45, 0x67, 0x89, 0xAB, 0xCD, 0xEF]);
+    let id = block_on(W25q128jv::new(&mut spi).read_unique_id()).unwrap();
+    assert_eq!(id, 0x0123_4567_89AB_CDEF);
+    assert_eq!(spi.take_frames(), vec![vec![w(&[0x4B, 0, 0, 0, 0]), r(8)]]);
+}
+
+#[test]
+fn read_sfdp_sends_5a_address_and_dummy_byte() {
+    let mut spi = MockSpi::new();
+    let mut buf = [0u8; 4];
+    block_on(W25q128jv::new(&mut spi).read_sfdp(0x80, &mut buf)).unwrap();
+    assert_eq!(spi.take_frames(), vec![vec![w(&[0x5A, 0x00, 0x00, 0x80, 0x00]), r(4)]]);
+}
+
+#[test]
+fn read_sfdp_parameters_rejects_bad_signature() {
+    let mut spi = MockSpi::new();
+    spi.fill(0xFF);
+    let result = block_on(W25q128jv::new(&mut spi).read_sfdp_parameters());
+    assert_eq!(result, Err(Error::InvalidSfdp));
+}
+
+#[test]
+fn verify_jedec_id_accepts_w25q128jv() {
+    let mut spi = MockSpi::new();
+    spi.respond(&[0xEF, 0x40, 0x18]);
+    block_on(W25q128jv::new(&mut spi).verify_jedec_id()).unwrap();
+}
+
+#[test]
+fn verify_jedec_id_rejects_other_parts() {
+    let mut spi = MockSpi::new();
+    spi.respond(&[0xC2, 0x20, 0x17]);
+    let result = block_on(W25q128jv::new(&mut spi).verify_jedec_id());
+    assert_eq!(
+        result,
+        Err(Error::UnexpectedJedecId { manufacturer: 0xC2, memory_type: 0x20, capacity: 0x17 })
+    );
+}
+
+#[test]
+fn read_status_register_sends_05() {
+    let mut spi = MockSpi::new();
+    spi.respond(&[0x02]);
+    let value = block_on(W25q128jv::new(&mut spi).read_status_register()).unwrap();
+    assert_eq!(value, 0x02);
+    assert_eq!(spi.take_frames(), vec![status()]);
+}
+
+#[test]
+fn status_registers_are_decoded() {
+    let mut spi = MockSpi::new();
+    spi.respond(&[0b1011_0110]).respond(&[0b1100_1010]).respond(&[0b0100_0100]);
+    let mut flash = W25q128jv::new(&mut spi);
+    let sr1 = block_on(flash.read_status_register_1()).unwrap();
+    let sr2 = block_on(flash.read_status_register_2()).unwrap();
+    let sr3 = block_on(flash.read_status_register_3()).unwrap();
+    assert_eq!(sr1, StatusRegister1 { busy: false, wel: true, bp: 0b101, tb: true, sec: false, srp: true });
+    assert_eq!(sr2, StatusRegister2 { srl: false, qe: true, lb: [true, false, false], cmp: true, sus: true });
+    assert_eq!(sr3, StatusRegister3 { ads: false, adp: false, wps: true, drv: DriveStrength::Percent50 });
+    assert_eq!(
+        spi.take_frames(),
+        vec![vec![w(&[0x05]), r(1)], vec![w(&[0x35]), r(1)], vec![w(&[0x15]), r(1)]]
+    );
+}
+
+#[test]
+fn status_registers_round_trip_through_bytes() {
+    for bits in 0..=u8::MAX {
+        assert_eq!(u8::from(StatusRegister1::from(bits)), bits);
+        assert_eq!(u8::from(StatusRegister2::from(bits)), bits & !0x04); // S10 is reserved / S10 为保留位
+        assert_eq!(u8::from(StatusRegister3::from(bits)), bits & 0x67);
+    }
+}
+
+#[test]
+fn write_status_registers_use_matching_enable() {
+    let mut spi = MockSpi::new();
+    let mut flash = W25q128jv::new(&mut spi);
+    let sr1 = StatusRegister1 { busy: true, wel: true, bp: 0b111, ..Default::default() };
+    block_on(flash.write_status_register_1(sr1, StatusWriteMode::NonVolatile)).unwrap();
+    let sr2 = StatusRegister2 { qe: true, ..Default::default() };
+    block_on(flash.write_status_register_2(sr2, StatusWriteMode::Volatile)).unwrap();
+    let sr3 = StatusRegister3 { wps: true, drv: DriveStrength::Percent100, ..Default::default() };
+    block_on(flash.write_status_register_3(sr3, StatusWriteMode::NonVolatile)).unwrap();
+    assert_eq!(
+        spi.take_frames(),
+        vec![
+            status(),
+            write_enable(),
+            vec![w(&[0x01, 0x1F])],
+            status(),
+            vec![w(&[0x35]), r(1)],
+            status(),
+            vec![w(&[0x50])],
+            vec![w(&[0x31, 0x02])],
+            status(),
+            status(),
+            write_enable(),
+            vec![w(&[0x11, 0x04])],
+            status(),
+        ]
+    );
+}
+
+#[test]
+fn is_busy_decodes_busy_bit() {
+    let mut spi = MockSpi::new();
+    spi.respond(&[0x01]).respond(&[0xFE]);
+    let mut flash = W25q128jv::new(&mut spi);
+    assert!(block_on(flash.is_busy()).unwrap());
+    assert!(!block_on(flash.is_busy()).unwrap());
+}
+
+#[test]
+fn read_data_sends_03_and_msb_first_address() {
+    let mut spi = MockSpi::new();
+    spi.respond(&[0x00]).respond(&[1, 2, 3, 4]);
+    let mut buf = [0u8; 4];
+    block_on(W25q128jv::new(&mut spi).read_data(0x12_3456, &mut buf)).unwrap();
+    assert_eq!(buf, [1, 2, 3, 4]);
+    assert_eq!(spi.take_frames(), vec![status(), vec![w(&[0x03, 0x12, 0x34, 0x56]), r(4)]]);
+}
+
+#[test]
+fn fast_read_sends_0b_address_and_dummy_byte() {
+    let mut spi = MockSpi::new();
+    let mut buf = [0u8; 2];
+    block_on(W25q128jv::new(&mut spi).fast_read(0xAB_CDEF, &mut buf)).unwrap();
+    assert_eq!(spi.take_frames(), vec![status(), vec![w(&[0x0B, 0xAB, 0xCD, 0xEF, 0x00]), r(2)]]);
+}
+
+#[test]
+fn reads_wait_while_busy() {
+    let mut spi = MockSpi::new();
+    spi.respond(&[0x01]).respond(&[0x03]).respond(&[0x00]);
+    let mut buf = [0u8; 1];
+    block_on(W25q128jv::new(&mut spi).read_data(0, &mut buf)).unwrap();
+    let frames = spi.take_frames();
+    assert_eq!(frames[..3], [status(), status(), status()]);
+    assert_eq!(frames[3], vec![w(&[0x03, 0, 0, 0]), r(1)]);
+}
+
+#[test]
+fn stuck_busy_times_out() {
+    let mut spi = MockSpi::new();
+    spi.fill(0xFF);
+    let config = Config { idle_timeout: Duration::from_millis(2), ..Config::default() };
+    let mut buf = [0u8; 1];
+    let result = block_on(W25q128jv::new_with_config(&mut spi, config).read_data(0, &mut buf));
+    assert_eq!(result, Err(Error::Timeout));
+    // Polled more than once, never issued the read / 轮询了多次，且从未发出读取命令
+    let frames = spi.take_frames();
+    assert!(frames.len() > 1);
+    assert!(frames.iter().all(|frame| *frame == status()));
+}
+
+#[test]
+fn write_data_enables_write_before_page_program() {
+    let mut spi = MockSpi::new();
+    block_on(W25q128jv::new(&mut spi).write_data(0x00_0100, &[0xAA, 0xBB])).unwrap();
+    assert_eq!(
+        spi.take_frames(),
+        [
+            protection_reads(),
+            vec![
+                status(),
+                write_enable(),
+                vec![w(&[0x02, 0x00, 0x01, 0x00]), w(&[0xAA, 0xBB])],
+                status(),
+            ],
+        ]
+        .concat()
+    );
+}
+
+#[test]
+fn write_data_rejects_page_crossing() {
+    let mut spi = MockSpi::new();
+    let result = block_on(W25q128jv::new(&mut spi).write_data(0xFF, &[0, 0]));
+    assert_eq!(result, Err(Error::NotAligned));
+    assert!(spi.take_frames().is_empty());
+}
+
+#[test]
+fn program_splits_at_page_boundaries() {
+    let mut spi = MockSpi::new();
+    block_on(W25q128jv::new(&mut spi).program(0x00_01FE, &[1, 2, 3, 4])).unwrap();
+    assert_eq!(
+        spi.take_frames(),
+        [
+            protection_reads(),
+            vec![
+                status(),
+                write_enable(),
+                vec![w(&[0x02, 0x00, 0x01, 0xFE]), w(&[1, 2])],
+                status(),
+                status(),
+                write_enable(),
+                vec![w(&[0x02, 0x00, 0x02, 0x00]), w(&[3, 4])],
+                status(),
+            ],
+        ]
+        .concat()
+    );
+}
+
+#[test]
+fn write_programs_only_changed_bytes_when_bits_are_cleared() {
+    let mut spi = MockSpi::new();
+    let mut sector = vec![0xFF; SECTOR_SIZE];
+    sector[0x11] = 0xF0;
+    spi.respond(&[0x00]).respond(&[0x00]).respond(&[0x00]).respond(&[0x00]).respond(&sector);
+    block_on(W25q128jv::new(&mut spi).write(0x00_0010, &[0x0F, 0xF0, 0xFF])).unwrap();
+    assert_eq!(
+        spi.take_frames(),
+        [
+            protection_reads(),
+            vec![
+                status(),
+                vec![w(&[0x0B, 0x00, 0x00, 0x00, 0x00]), r(SECTOR_SIZE)],
+                status(),
+                write_enable(),
+                vec![w(&[0x02, 0x00, 0x00, 0x10]), w(&[0x0F])],
+                status(),
+            ],
+        ]
+        .concat()
+    );
+}
+
+#[test]
+fn write_erases_and_restores_a_sector_when_bits_are_set() {
+    let mut spi = MockSpi::new();
+    let mut sector = vec![0xFF; SECTOR_SIZE];
+    sector[0x100] = 0x55;
+    sector[0xFFF] = 0x12;
+    spi.respond(&[0x00]).respond(&[0x00]).respond(&[0x00]).respond(&[0x00]).respond(&sector);
+    block_on(W25q128jv::new(&mut spi).write(0x00_0100, &[0xAA])).unwrap();
+
+    let mut first_page = vec![0xFF; PAGE_SIZE];
+    first_page[0] = 0xAA;
+    let mut last_page = vec![0xFF; PAGE_SIZE];
+    last_page[PAGE_SIZE - 1] = 0x12;
+    assert_eq!(
+        spi.take_frames(),
+        [
+            protection_reads(),
+            vec![status(), vec![w(&[0x0B, 0x00, 0x00, 0x00, 0x00]), r(SECTOR_SIZE)]],
+            erase_frames(0x20, 0x00_0000),
+            // Pages left at 0xFF are not programmed / 保持为 0xFF 的页面不编程
+            vec![status(), write_enable(), vec![w(&[0x02, 0x00, 0x01, 0x00]), w(&first_page)], status()],
+            vec![status(), write_enable(), vec![w(&[0x02, 0x00, 0x0F, 0x00]), w(&last_page)], status()],
+        ]
+        .concat()
+    );
+}
+
+#[test]
+fn write_checks_every_sector_it_may_erase() {
+    let mut spi = MockSpi::new();
+    // BP0 = 1 protects the top 256KB / BP0 = 1 保护顶部 256KB
+    spi.respond(&[0x04]);
+    let result = block_on(W25q128jv::new(&mut spi).write(FLASH_SIZE as u32 - 256 * 1024 - 1, &[0, 0]));
+    assert_eq!(result, Err(Error::Protected));
+    assert_eq!(spi.take_frames(), protection_reads());
+}
+
+/// Frames of an address-based erase / 基于地址的擦除命令的帧
+fn erase_frames(opcode: u8, address: u32) -> Vec<Vec<Op>> {
+    let [_, a2, a1, a0] = address.to_be_bytes();
+    vec![status(), write_enable(), vec![w(&[opcode, a2, a1, a0])], status()]
+}
+
+#[test]
+fn erase_commands_use_their_opcodes() {
+    let mut spi = MockSpi::new();
+    let mut flash = W25q128jv::new(&mut spi);
+    block_on(flash.erase_sector_4k(0x00_1000)).unwrap();
+    block_on(flash.erase_block_32k(0x00_8000)).unwrap();
+    block_on(flash.erase_block_64k(0x01_0000)).unwrap();
+    let expected = [
+        protection_reads(),
+        erase_frames(0x20, 0x00_1000),
+        erase_frames(0x52, 0x00_8000),
+        erase_frames(0xD8, 0x01_0000),
+    ]
+    .concat();
+    assert_eq!(spi.take_frames(), expected);
+}
+
+#[test]
+fn reads_suspend_a_background_erase_outside_its_block() {
+    let mut spi = MockSpi::new();
+    // Protection and idle reads, then BUSY, SUS, data, SUS, then BUSY until the erase ends
+    // 保护设置和空闲读取，然后是 BUSY、SUS、数据、SUS，然后 BUSY 直到擦除结束
+    spi.respond(&[0]).respond(&[0]).respond(&[0]).respond(&[0]);
+    spi.respond(&[0x01]).respond(&[0x80]).respond(&[1, 2]).respond(&[0x80]);
+    spi.respond(&[0x01]).respond(&[0x00]);
+    let mut flash = W25q128jv::new(&mut spi);
+    block_on(flash.start_erase_block_64k(0x1_0000)).unwrap();
+    assert_eq!(flash.erase_in_progress(), Some(0x1_0000..0x2_0000));
+    let mut buf = [0u8; 2];
+    block_on(flash.read_data(0x0_FFFE, &mut buf)).unwrap();
+    assert_eq!(buf, [1, 2]);
+    assert_eq!(flash.erase_in_progress(), Some(0x1_0000..0x2_0000));
+    // Inside the block: wait for the erase instead / 位于块内：改为等待擦除完成
+    block_on(flash.fast_read(0x1_FFFF, &mut buf[..1])).unwrap();
+    assert_eq!(flash.erase_in_progress(), None);
+    assert_eq!(
+        spi.take_frames(),
+        [
+            protection_reads(),
+            vec![status(), write_enable(), vec![w(&[0xD8, 0x01, 0x00, 0x00])]],
+            vec![status(), vec![w(&[0x75])], vec![w(&[0x35]), r(1)], vec![w(&[0x03, 0x00, 0xFF, 0xFE]), r(2)]],
+            vec![vec![w(&[0x35]), r(1)], vec![w(&[0x7A])]],
+            vec![status(), status(), vec![w(&[0x0B, 0x01, 0xFF, 0xFF, 0x00]), r(1)]],
+        ]
+        .concat()
+    );
+}
+
+#[test]
+fn suspend_and_resume_follow_busy_and_sus() {
+    let mut spi = MockSpi::new();
+    let mut flash = W25q128jv::new(&mut spi);
+    // Nothing running, nothing suspended / 没有运行中的操作，也没有被暂停的操作
+    assert!(!block_on(flash.suspend()).unwrap());
+    block_on(flash.resume()).unwrap();
+    assert_eq!(spi.take_frames(), vec![status(), vec![w(&[0x35]), r(1)]]);
+}
+
+#[test]
+fn deep_power_down_wakes_on_the_next_command() {
+    let mut spi = MockSpi::new();
+    let mut flash = W25q128jv::new(&mut spi);
+    block_on(flash.deep_power_down()).unwrap();
+    assert_eq!(flash.power_state(), PowerState::PowerDown);
+    block_on(flash.read_status_register_1()).unwrap();
+    assert_eq!(flash.power_state(), PowerState::Active);
+    block_on(flash.release_from_power_down()).unwrap();
+    assert_eq!(
+        spi.take_frames(),
+        vec![status(), vec![w(&[0xB9])], vec![w(&[0xAB])], status(), vec![w(&[0xAB])]]
+    );
+}
+
+#[test]
+fn power_down_if_idle_follows_auto_power_down() {
+    let mut spi = MockSpi::new();
+    let mut flash = W25q128jv::new(&mut spi);
+    // Off by default / 默认关闭
+    assert_eq!(flash.power_down_deadline(), None);
+    assert!(!block_on(flash.power_down_if_idle()).unwrap());
+
+    flash.set_config(Config { auto_power_down: Some(Duration::from_secs(3600)), ..Config::default() });
+    assert!(flash.power_down_deadline().is_some());
+    assert!(!block_on(flash.power_down_if_idle()).unwrap());
+
+    flash.set_config(Config { auto_power_down: Some(Duration::from_ticks(0)), ..Config::default() });
+    assert!(block_on(flash.power_down_if_idle()).unwrap());
+    assert_eq!(flash.power_state(), PowerState::PowerDown);
+    assert_eq!(flash.power_down_deadline(), None);
+    assert_eq!(spi.take_frames(), vec![status(), vec![w(&[0xB9])]]);
+}
+
+#[test]
+fn multi_io_reads_use_their_opcodes_and_clocks() {
+    let cases = [
+        (ReadMode::Single, 0x0B, 1, 1, None, 8),
+        (ReadMode::DualOutput, 0x3B, 1, 2, None, 8),
+        (ReadMode::DualIo, 0xBB, 2, 2, Some(0x00), 0),
+        (ReadMode::QuadOutput, 0x6B, 1, 4, None, 8),
+        (ReadMode::QuadIo, 0xEB, 4, 4, Some(0x00), 4),
+    ];
+    for (mode, opcode, address_lines, data_lines, mode_bits, dummy_clocks) in cases {
+        let mut spi = MockSpi::new();
+        // QE already set for the quad modes / 四线模式下 QE 已置位
+        spi.respond(&[0x02]);
+        let mut flash = W25q128jv::new(&mut spi);
+        block_on(flash.read_multi_io(mode, 0x12_3456, &mut [0u8; 2])).unwrap();
+        let read = MultiIoRead { opcode, address: 0x12_3456, address_bytes: 3, address_lines, mode_bits, dummy_clocks, data_lines };
+        assert_eq!(spi.take_frames().last(), Some(&vec![Op::MultiIo(read, 2)]));
+    }
+
+    // Parts above 16MB use the 4-byte opcodes / 容量超过 16MB 的器件使用 4 字节操作码
+    let mut spi = MockSpi::new();
+    let mut flash = w25q256(&mut spi, 0x00);
+    block_on(flash.read_multi_io(ReadMode::DualOutput, 0x100_0000, &mut [0u8; 1])).unwrap();
+    let read = MultiIoRead { opcode: 0x3C, address: 0x100_0000, address_bytes: 4, address_lines: 1, mode_bits: None, dummy_clocks: 8, data_lines: 2 };
+    assert_eq!(spi.take_frames().last(), Some(&vec![Op::MultiIo(read, 1)]));
+}
+
+#[test]
+fn quad_reads_set_qe_once() {
+    let mut spi = MockSpi::new();
+    let mut flash = W25q128jv::new(&mut spi);
+    let mut buf = [0u8; 4];
+    block_on(flash.read_multi_io(ReadMode::QuadIo, 0, &mut buf)).unwrap();
+    block_on(flash.read_multi_io(ReadMode::QuadOutput, 0, &mut buf)).unwrap();
+    let frames = spi.take_frames();
+    // SR2 with QE = 0 gets a volatile write setting it / QE = 0 的状态寄存器2通过易失性写入置位 QE
+    assert_eq!(
+        frames[..6],
+        [vec![w(&[0x35]), r(1)], status(), vec![w(&[0x50])], vec![w(&[0x31, 0x02])], status(), status()]
+    );
+    assert!(matches!(frames[6][..], [Op::MultiIo(MultiIoRead { opcode: 0xEB, .. }, 4)]));
+    assert_eq!(frames[7], status());
+    assert!(matches!(frames[8][..], [Op::MultiIo(MultiIoRead { opcode: 0x6B, .. }, 4)]));
+    assert_eq!(frames.len(), 9);
+}
+
+#[test]
+fn qpi_mode_is_confirmed_and_follows_read_parameters() {
+    let mut spi = MockSpi::new();
+    // SR2 and three idle polls, then the JEDEC ID read on four lines / 状态寄存器2和三次空闲轮询，然后是通过四线读取的 JEDEC ID
+    spi.respond(&[0x00]).respond(&[0x00]).respond(&[0x00]).respond(&[0x00]).respond(&[0xEF, 0x60, 0x18]);
+    let mut flash = W25q128jv::new(&mut spi);
+    block_on(flash.enter_qpi()).unwrap();
+    assert_eq!(flash.interface_mode(), InterfaceMode::Qpi);
+    let parameters = ReadParameters { dummy_clocks: DummyClocks::Six, wrap_length: WrapLength::Bytes64 };
+    block_on(flash.set_read_parameters(parameters)).unwrap();
+    let mut buf = [0u8; 4];
+    block_on(flash.read_data(0x12_3456, &mut buf)).unwrap();
+    assert_eq!(block_on(flash.read_unique_id()), Err(Error::Unsupported));
+    block_on(flash.exit_qpi()).unwrap();
+    let frames = spi.take_frames();
+    assert_eq!(frames[6..8], [vec![w(&[0x38])], vec![w(&[0x9F]), r(3)]]);
+    assert_eq!(frames[9], vec![w(&[0xC0, 0x00])]);
+    assert_eq!(frames[11], vec![w(&[0xC0, 0x23])]);
+    // Read Data becomes Fast Read with 6 dummy clocks (3 bytes) / 读取数据变为带 6 个虚拟时钟（3 字节）的快速读取
+    assert_eq!(frames[13], vec![w(&[0x0B, 0x12, 0x34, 0x56, 0x00, 0x00, 0x00]), r(4)]);
+    assert_eq!(frames[14..], [status(), vec![w(&[0xFF])]]);
+}
+
+#[test]
+fn enter_qpi_backs_out_on_parts_without_qpi() {
+    let mut spi = MockSpi::new();
+    // A W25Q128JV ignores 38h, so the four-line JEDEC ID read returns noise
+    // W25Q128JV 忽略 38h，因此通过四线读取的 JEDEC ID 为噪声
+    spi.respond(&[0x02]).fill(0x00);
+    let mut flash = W25q128jv::new(&mut spi);
+    assert_eq!(block_on(flash.enter_qpi()), Err(Error::Unsupported));
+    assert_eq!(flash.interface_mode(), InterfaceMode::Spi);
+    let frames = spi.take_frames();
+    assert_eq!(frames[frames.len() - 3..], [vec![w(&[0x38])], vec![w(&[0x9F]), r(3)], vec![w(&[0xFF])]]);
+}
+
+#[test]
+fn memory_mapped_mode_uses_continuous_quad_io() {
+    let mut spi = MockSpi::new();
+    let mut flash = W25q128jv::new(&mut spi);
+    let window = block_on(flash.enter_memory_mapped()).unwrap();
+    assert_eq!(window.len(), FLASH_SIZE);
+    assert!(flash.is_memory_mapped());
+    // Mapped again: the same window, nothing sent / 再次映射：同一窗口，不发送任何内容
+    assert_eq!(block_on(flash.enter_memory_mapped()).unwrap().as_ptr(), window.as_ptr());
+    assert_eq!(block_on(flash.erase_sector_4k(0)), Err(Error::Unsupported));
+    assert_eq!(block_on(flash.release_from_power_down()), Err(Error::Unsupported));
+    unsafe { block_on(flash.exit_memory_mapped()) }.unwrap();
+    assert!(!flash.is_memory_mapped());
+    let frames = spi.take_frames();
+    let read = MultiIoRead { opcode: 0xEB, address: 0, address_bytes: 3, address_lines: 4, mode_bits: Some(0x20), dummy_clocks: 4, data_lines: 4 };
+    assert_eq!(frames[frames.len() - 3..], [vec![Op::Map(read, FLASH_SIZE)], vec![Op::Unmap], vec![w(&[0xFF, 0xFF])]]);
+}
+
+#[test]
+fn erase_commands_check_alignment() {
+    let mut spi = MockSpi::new();
+    let mut flash = W25q128jv::new(&mut spi);
+    assert_eq!(block_on(flash.erase_sector_4k(0x0800)), Err(Error::NotAligned));
+    assert_eq!(block_on(flash.erase_block_32k(0x1000)), Err(Error::NotAligned));
+    assert_eq!(block_on(flash.erase_block_64k(0x8000)), Err(Error::NotAligned));
+    assert_eq!(block_on(flash.erase_sector_4k(FLASH_SIZE as u32)), Err(Error::OutOfBounds));
+    assert!(spi.take_frames().is_empty());
+}
+
+#[test]
+fn erase_chip_sends_c7() {
+    let mut spi = MockSpi::new();
+    block_on(W25q128jv::new(&mut spi).erase_chip()).unwrap();
+    assert_eq!(
+        spi.take_frames(),
+        [protection_reads(), vec![status(), write_enable(), vec![w(&[0xC7])], status()]].concat()
+    );
+}
+
+#[test]
+fn erase_range_uses_largest_aligned_units() {
+    let mut spi = MockSpi::new();
+    block_on(W25q128jv::new(&mut spi).erase_range(0x00_7000, 0x1_9000)).unwrap();
+    let erases: Vec<_> = spi
+        .take_frames()
+        .into_iter()
+        .filter_map(|frame| match frame.as_slice() {
+            [Op::Write(bytes)] if bytes.len() == 4 => Some(bytes.clone()),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(erases, vec![vec![0x20, 0x00, 0x70, 0x00], vec![0x52, 0x00, 0x80, 0x00], vec![0xD8, 0x01, 0x00, 0x00]]);
+}
+
+#[test]
+fn erase_range_over_whole_device_uses_chip_erase() {
+    let mut spi = MockSpi::new();
+    block_on(W25q128jv::new(&mut spi).erase_range(0, FLASH_SIZE)).unwrap();
+    assert!(spi.take_frames().contains(&vec![w(&[0xC7])]));
+}
+
+#[test]
+fn erase_range_checks_alignment_and_bounds() {
+    let mut spi = MockSpi::new();
+    let mut flash = W25q128jv::new(&mut spi);
+    assert_eq!(block_on(flash.erase_range(0x1000, 0x800)), Err(Error::NotAligned));
+    assert_eq!(block_on(flash.erase_range(0xFF_F000, 0x2000)), Err(Error::OutOfBounds));
+}
+
+/// A W25Q256 after `init`, with SR3 reporting `sr3` / `init` 之后的 W25Q256，SR3 报告 `sr3`
+fn w25q256(spi: &mut MockSpi, sr3: u8) -> W25q128jv<&mut MockSpi> {
+    spi.respond(&[0xEF, 0x40, 0x19]).respond(&[sr3]);
+    let mut flash = W25q128jv::new(spi);
+    block_on(flash.init()).unwrap();
+    flash
+}
+
+#[test]
+fn large_parts_use_4_byte_opcodes() {
+    let mut spi = MockSpi::new();
+    let mut flash = w25q256(&mut spi, 0x00);
+    assert_eq!(flash.address_mode(), AddressMode::ThreeByte);
+    let mut buf = [0u8; 2];
+    block_on(flash.read_data(0x0123_4567, &mut buf)).unwrap();
+    block_on(flash.fast_read(0x0123_4567, &mut buf)).unwrap();
+    block_on(flash.write_data(0x01FF_FF00, &[0xAA])).unwrap();
+    block_on(flash.erase_sector_4k(0x0123_4000)).unwrap();
+    block_on(flash.erase_block_64k(0x0123_0000)).unwrap();
+    let frames = spi.take_frames();
+    assert_eq!(frames[..3], [vec![Op::Delay(10_000)], vec![w(&[0x9F]), r(3)], vec![w(&[0x15]), r(1)]]);
+    assert_eq!(
+        frames[3..],
+        [
+            vec![status(), vec![w(&[0x13, 0x01, 0x23, 0x45, 0x67]), r(2)]],
+            vec![status(), vec![w(&[0x0C, 0x01, 0x23, 0x45, 0x67, 0x00]), r(2)]],
+            protection_reads(),
+            vec![status(), write_enable(), vec![w(&[0x12, 0x01, 0xFF, 0xFF, 0x00]), w(&[0xAA])], status()],
+            vec![status(), write_enable(), vec![w(&[0x21, 0x01, 0x23, 0x40, 0x00])], status()],
+            vec![status(), write_enable(), vec![w(&[0xDC, 0x01, 0x23, 0x00, 0x00])], status()],
+        ]
+        .concat()
+    );
+}
+
+#[test]
+fn extended_address_register_supplies_the_top_byte() {
+    let mut spi = MockSpi::new();
+    let mut flash = w25q256(&mut spi, 0x00);
+    block_on(flash.erase_block_32k(0x0123_8000)).unwrap();
+    block_on(flash.lock_block(0x0100_0000)).unwrap();
+    block_on(flash.unlock_block(0x0000_0000)).unwrap();
+    assert_eq!(
+        spi.take_frames()[3..],
+        [
+            protection_reads(),
+            // Written once, then cached / 只写入一次，之后使用缓存
+            vec![status(), status(), write_enable(), vec![w(&[0xC5, 0x01])]],
+            vec![write_enable(), vec![w(&[0x52, 0x23, 0x80, 0x00])], status()],
+            vec![status(), write_enable(), vec![w(&[0x36, 0x00, 0x00, 0x00])]],
+            vec![status(), status(), write_enable(), vec![w(&[0xC5, 0x00])]],
+            vec![write_enable(), vec![w(&[0x39, 0x00, 0x00, 0x00])]],
+        ]
+        .concat()
+    );
+}
+
+#[test]
+fn four_byte_address_mode_extends_every_address() {
+    let mut spi = MockSpi::new();
+    // ADS = 1: already in 4-byte mode / ADS = 1：已处于 4 字节模式
+    let mut flash = w25q256(&mut spi, 0x01);
+    assert_eq!(flash.address_mode(), AddressMode::FourByte);
+    block_on(flash.lock_block(0x0123_0000)).unwrap();
+    block_on(flash.exit_4byte_address_mode()).unwrap();
+    block_on(flash.enter_4byte_address_mode()).unwrap();
+    let mut buf = [0u8; 1];
+    assert_eq!(block_on(flash.read_data(0x0200_0000, &mut buf)), Err(Error::OutOfBounds));
+    assert_eq!(
+        spi.take_frames()[3..],
+        [
+            status(),
+            write_enable(),
+            vec![w(&[0x36, 0x01, 0x23, 0x00, 0x00])],
+            status(),
+            vec![w(&[0xE9])],
+            status(),
+            vec![w(&[0xB7])],
+        ]
+    );
+}
+
+#[test]
+fn protect_writes_bp_tb_sec_and_cmp() {
+    let mut spi = MockSpi::new();
+    spi.respond(&[0x00]).respond(&[0x80]).respond(&[0x02]);
+    // Lower 15.75MB is the complement of the top 256KB / 低 15.75MB 是顶部 256KB 的补集
+    block_on(W25q128jv::new(&mut spi).protect(0..0xFC_0000)).unwrap();
+    assert_eq!(
+        spi.take_frames(),
+        [
+            vec![vec![w(&[0x15]), r(1)], status(), vec![w(&[0x35]), r(1)]],
+            // CMP first: the chip briefly protects everything, not just the top 256KB
+            // 先写 CMP：芯片短暂保护全部，而不是只保护顶部 256KB
+            vec![status(), write_enable(), vec![w(&[0x31, 0x42])], status()],
+            vec![status(), write_enable(), vec![w(&[0x01, 0x84])], status()],
+        ]
+        .concat()
+    );
+}
+
+#[test]
+fn protect_orders_status_writes_to_widen_protection() {
+    // From everything but the top 4KB (SEC = 1, BP = 001, CMP = 1) to the top 256KB
+    // 从除顶部 4KB 以外的全部（SEC = 1，BP = 001，CMP = 1）改为顶部 256KB
+    let mut spi = MockSpi::new();
+    spi.respond(&[0x00]).respond(&[0x44]).respond(&[0x40]);
+    block_on(W25q128jv::new(&mut spi).protect(0xFC_0000..0x100_0000)).unwrap();
+    // SR1 first keeps the lower 15.75MB protected instead of just the top 4KB
+    // 先写 SR1，低 15.75MB 保持受保护，而不是只保护顶部 4KB
+    assert_eq!(
+        spi.take_frames()[3..],
+        [
+            vec![status(), write_enable(), vec![w(&[0x01, 0x04])], status()],
+            vec![status(), write_enable(), vec![w(&[0x31, 0x00])], status()],
+        ]
+        .concat()
+    );
+
+    // From the same region to everything: BP = 111 with the old CMP would protect nothing
+    // 从同一区域改为全部：BP = 111 配合旧的 CMP 将不保护任何区域
+    let mut spi = MockSpi::new();
+    spi.respond(&[0x00]).respond(&[0x44]).respond(&[0x40]);
+    block_on(W25q128jv::new(&mut spi).protect(0..0x100_0000)).unwrap();
+    assert_eq!(
+        spi.take_frames()[3..],
+        [
+            vec![status(), write_enable(), vec![w(&[0x31, 0x00])], status()],
+            vec![status(), write_enable(), vec![w(&[0x01, 0x1C])], status()],
+        ]
+        .concat()
+    );
+}
+
+#[test]
+fn protection_table_matches_datasheet() {
+    let geometry = FlashGeometry::W25Q128;
+    let region = |bp, tb, sec, cmp| ProtectionRegion { bp, tb, sec, cmp }.range(&geometry);
+    assert_eq!(ProtectionRegion::NONE.range(&geometry), 0..0);
+    assert_eq!(ProtectionRegion::ALL.range(&geometry), 0..0x100_0000);
+    assert_eq!(region(0b001, false, false, false), 0xFC_0000..0x100_0000);
+    assert_eq!(region(0b110, false, false, false), 0x80_0000..0x100_0000);
+    assert_eq!(region(0b011, true, false, false), 0..0x10_0000);
+    assert_eq!(region(0b001, false, true, false), 0xFF_F000..0x100_0000);
+    assert_eq!(region(0b101, true, true, false), 0..0x8000);
+    assert_eq!(region(0b010, true, true, true), 0x2000..0x100_0000);
+    assert_eq!(region(0b000, false, false, true), 0..0x100_0000);
+    assert_eq!(region(0b111, false, false, true), 0..0);
+    for bits in 0..64u8 {
+        let original = ProtectionRegion { bp: bits & 0x07, tb: bits & 0x08 != 0, sec: bits & 0x10 != 0, cmp: bits & 0x20 != 0 };
+        let found = ProtectionRegion::from_range(original.range(&geometry), &geometry).unwrap();
+        assert_eq!(found.range(&geometry), original.range(&geometry));
+    }
+}
+
+#[test]
+fn protection_table_scales_with_geometry() {
+    // W25Q64: 1/64 is 128KB / W25Q64：1/64 为 128KB
+    let w25q64 = FlashGeometry::from_jedec_id(0xEF, 0x40, 0x17).unwrap();
+    assert_eq!(ProtectionRegion { bp: 0b001, tb: false, sec: false, cmp: false }.range(&w25q64), 0x7E_0000..0x80_0000);
+    assert_eq!(ProtectionRegion { bp: 0b110, tb: true, sec: false, cmp: false }.range(&w25q64), 0..0x40_0000);
+    // W25Q256: BP3-BP0 in 64KB steps, TB in bit 6 / W25Q256：BP3-BP0 以 64KB 为步进，TB 位于第 6 位
+    let w25q256 = FlashGeometry::from_jedec_id(0xEF, 0x40, 0x19).unwrap();
+    let region = |bp, tb| ProtectionRegion { bp, tb, sec: false, cmp: false }.range(&w25q256);
+    assert_eq!(region(0b0001, false), 0x1FF_0000..0x200_0000);
+    assert_eq!(region(0b1001, true), 0..0x100_0000);
+    assert_eq!(region(0b1010, false), 0..0x200_0000);
+    let (mut sr1, mut sr2) = (StatusRegister1::default(), StatusRegister2::default());
+    ProtectionRegion { bp: 0b1001, tb: true, sec: false, cmp: false }.apply(&mut sr1, &mut sr2, &w25q256);
+    assert_eq!(u8::from(sr1), 0x64);
+    assert_eq!(ProtectionRegion::from_status(sr1, sr2, &w25q256).range(&w25q256), 0..0x100_0000);
+}
+
+#[test]
+fn protect_rejects_ranges_missing_from_the_table() {
+    let mut spi = MockSpi::new();
+    let result = block_on(W25q128jv::new(&mut spi).protect(0x1000..0x3000));
+    assert_eq!(result, Err(Error::InvalidProtectionRange));
+    assert!(spi.take_frames().is_empty());
+}
+
+#[test]
+fn protect_is_unsupported_with_individual_block_locks() {
+    let mut spi = MockSpi::new();
+    // SR3 WPS = 1 / SR3 WPS = 1
+    spi.respond(&[0x04]).respond(&[0x04]);
+    let mut flash = W25q128jv::new(&mut spi);
+    assert_eq!(block_on(flash.protect(0xFC_0000..0x100_0000)), Err(Error::Unsupported));
+    assert_eq!(block_on(flash.unprotect_all()), Err(Error::Unsupported));
+    // Nothing but the WPS reads went out / 只发出了 WPS 读取
+    assert_eq!(spi.take_frames(), vec![vec![w(&[0x15]), r(1)]; 2]);
+}
+
+#[test]
+fn protected_addresses_are_rejected_up_front() {
+    let mut spi = MockSpi::new();
+    // BP = 001, TB = 1: lower 256KB / 低 256KB
+    spi.respond(&[0x24]).respond(&[0x00]);
+    let mut flash = W25q128jv::new(&mut spi);
+    assert_eq!(block_on(flash.program(0x3_FFFF, &[0])), Err(Error::Protected));
+    assert_eq!(block_on(flash.erase_sector_4k(0x1000)), Err(Error::Protected));
+    assert_eq!(block_on(flash.erase_range(0x3_0000, 0x2_0000)), Err(Error::Protected));
+    assert_eq!(block_on(flash.erase_chip()), Err(Error::Protected));
+    // Only the protection itself was read / 只读取了保护设置本身
+    assert_eq!(spi.take_frames(), protection_reads());
+}
+
+#[test]
+fn block_lock_commands_use_their_opcodes() {
+    let mut spi = MockSpi::new();
+    // Three idle status reads, then the lock bit / 三次空闲状态读取，然后是锁定位
+    spi.respond(&[0x00]).respond(&[0x00]).respond(&[0x00]).respond(&[0x01]);
+    let mut flash = W25q128jv::new(&mut spi);
+    block_on(flash.lock_block(0x12_0000)).unwrap();
+    block_on(flash.unlock_block(0x00_3000)).unwrap();
+    assert!(block_on(flash.read_block_lock(0xFF_F000)).unwrap());
+    block_on(flash.global_lock()).unwrap();
+    block_on(flash.global_unlock()).unwrap();
+    assert_eq!(
+        spi.take_frames(),
+        vec![
+            status(),
+            write_enable(),
+            vec![w(&[0x36, 0x12, 0x00, 0x00])],
+            status(),
+            write_enable(),
+            vec![w(&[0x39, 0x00, 0x30, 0x00])],
+            status(),
+            vec![w(&[0x3D, 0xFF, 0xF0, 0x00]), r(1)],
+            status(),
+            write_enable(),
+            vec![w(&[0x7E])],
+            status(),
+            write_enable(),
+            vec![w(&[0x98])],
+        ]
+    );
+}
+
+#[test]
+fn block_lock_units_cover_the_array() {
+    let geometry = FlashGeometry::W25Q128;
+    assert_eq!(geometry.block_lock_units(), 16 + 254 + 16);
+    assert_eq!(geometry.block_lock_unit(0), 0..0x1000);
+    assert_eq!(geometry.block_lock_unit(15), 0xF000..0x1_0000);
+    assert_eq!(geometry.block_lock_unit(16), 0x1_0000..0x2_0000);
+    assert_eq!(geometry.block_lock_unit(269), 0xFE_0000..0xFF_0000);
+    assert_eq!(geometry.block_lock_unit(270), 0xFF_0000..0xFF_1000);
+    assert_eq!(geometry.block_lock_unit(285), 0xFF_F000..0x100_0000);
+    for id in 0x15..=0x20 {
+        let Some(geometry) = FlashGeometry::from_jedec_id(0xEF, 0x40, id) else { continue };
+        let mut end = 0;
+        for index in 0..geometry.block_lock_units() {
+            let unit = geometry.block_lock_unit(index);
+            assert_eq!(unit.start, end);
+            end = unit.end;
+        }
+        assert_eq!(end as usize, geometry.capacity);
+    }
+}
+
+#[test]
+fn security_register_commands_use_their_opcodes() {
+    let mut spi = MockSpi::new();
+    let mut flash = W25q128jv::new(&mut spi);
+    let mut buf = [0u8; 4];
+    block_on(flash.read_security_register(2, 0x10, &mut buf)).unwrap();
+    block_on(flash.program_security_register(1, 0x05, &[0x12, 0x34])).unwrap();
+    block_on(flash.erase_security_register(3)).unwrap();
+    assert_eq!(
+        spi.take_frames(),
+        vec![
+            status(),
+            vec![w(&[0x48, 0x00, 0x20, 0x10, 0x00]), r(4)],
+            vec![w(&[0x35]), r(1)],
+            status(),
+            write_enable(),
+            vec![w(&[0x42, 0x00, 0x10, 0x05]), w(&[0x12, 0x34])],
+            status(),
+            vec![w(&[0x35]), r(1)],
+            status(),
+            write_enable(),
+            vec![w(&[0x44, 0x00, 0x30, 0x00])],
+            status(),
+        ]
+    );
+}
+
+#[test]
+fn security_registers_check_index_offset_and_lock() {
+    let mut spi = MockSpi::new();
+    // LB2 set / LB2 已置位
+    spi.respond(&[0x10]).respond(&[0x10]);
+    let mut flash = W25q128jv::new(&mut spi);
+    let mut buf = [0u8; 2];
+    assert_eq!(block_on(flash.read_security_register(0, 0, &mut buf)), Err(Error::OutOfBounds));
+    assert_eq!(block_on(flash.read_security_register(4, 0, &mut buf)), Err(Error::OutOfBounds));
+    assert_eq!(block_on(flash.read_security_register(1, 255, &mut buf)), Err(Error::OutOfBounds));
+    assert_eq!(block_on(flash.program_security_register(3, 200, &[0; 57])), Err(Error::OutOfBounds));
+    assert_eq!(block_on(flash.program_security_register(2, 0, &[0])), Err(Error::Protected));
+    assert_eq!(block_on(flash.erase_security_register(2)), Err(Error::Protected));
+    assert_eq!(spi.take_frames(), vec![vec![w(&[0x35]), r(1)], vec![w(&[0x35]), r(1)]]);
+}
+
+#[test]
+fn lock_security_register_sets_its_lb_bit() {
+    let mut spi = MockSpi::new();
+    spi.respond(&[0x02]);
+    let confirmation = OtpLockConfirmation::i_understand_this_is_permanent();
+    block_on(W25q128jv::new(&mut spi).lock_security_register(3, confirmation)).unwrap();
+    assert_eq!(
+        spi.take_frames(),
+        vec![vec![w(&[0x35]), r(1)], status(), write_enable(), vec![w(&[0x31, 0x22])], status()]
+    );
+}
+
+#[test]
+fn lock_security_register_keeps_volatile_bits_out_of_the_otp_write() {
+    let mut spi = MockSpi::new();
+    // QE set with a volatile write, then read back as set / QE 通过易失性写入置位，之后读回为置位
+    spi.respond(&[0x00]).respond(&[0x00]).respond(&[0x00]).respond(&[0x02]);
+    let mut flash = W25q128jv::new(&mut spi);
+    block_on(flash.set_quad_enable(true, StatusWriteMode::Volatile)).unwrap();
+    let confirmation = OtpLockConfirmation::i_understand_this_is_permanent();
+    block_on(flash.lock_security_register(1, confirmation)).unwrap();
+    assert_eq!(
+        spi.take_frames()[5..],
+        [
+            vec![vec![w(&[0x35]), r(1)]],
+            // Non-volatile QE stays clear / 非易失 QE 保持清零
+            vec![status(), write_enable(), vec![w(&[0x31, 0x08])], status()],
+            // Volatile QE restored / 恢复易失 QE
+            vec![status(), vec![w(&[0x50])], vec![w(&[0x31, 0x0A])], status()],
+        ]
+        .concat()
+    );
+}
+
+#[test]
+fn write_status_register_2_keeps_the_lock_bits() {
+    let mut spi = MockSpi::new();
+    // LB1 already set / LB1 已置位
+    spi.respond(&[0x08]);
+    let sr2 = StatusRegister2 { qe: true, lb: [false, true, true], ..Default::default() };
+    block_on(W25q128jv::new(&mut spi).write_status_register_2(sr2, StatusWriteMode::NonVolatile)).unwrap();
+    assert_eq!(
+        spi.take_frames(),
+        vec![vec![w(&[0x35]), r(1)], status(), write_enable(), vec![w(&[0x31, 0x0A])], status()]
+    );
+}
+
+#[test]
+fn bus_errors_are_wrapped() {
+    let mut spi = MockSpi::new();
+    spi.fail_after(0);
+    let result = block_on(W25q128jv::new(&mut spi).read_status_register());
+    assert_eq!(result, Err(Error::Spi(ErrorKind::Other)));
+}
+
+#[test]
+fn blocking_device_sends_the_same_frames() {
+    let mut spi = MockSpi::new();
+    spi.respond(&[0xEF, 0x40, 0x18]);
+    let mut flash = W25q128jv::new_blocking(&mut spi);
+    block_on(flash.read_jedec_id()).unwrap();
+    let _spi = flash.release().ok().unwrap().release();
+    assert_eq!(spi.take_frames(), vec![vec![w(&[0x9F]), r(3)]]);
+}
+
+#[cfg(feature = "shared-bus")]
+#[test]
+fn shared_bus_device_frames_each_command_with_its_own_cs() {
+    let log = BusLog::default();
+    let mut bus = MockBus::new(log.clone());
+    bus.respond(&[0xEF, 0x40, 0x18]);
+    let bus = BusMutex::<BusRawMutex, _>::new(bus);
+    let mut flash = W25q128jv::new_shared(&bus, MockCs::new("flash", log.clone()));
+    block_on(flash.read_jedec_id()).unwrap();
+    assert_eq!(
+        *log.borrow(),
+        vec![BusEvent::Select("flash"), BusEvent::Write(vec![0x9F]), BusEvent::Read(3), BusEvent::Deselect("flash")]
+    );
+}
+
+#[cfg(feature = "shared-bus")]
+#[test]
+fn shared_bus_is_released_between_frames_and_reconfigured_per_device() {
+    let log = BusLog::default();
+    let mut bus = MockBus::new(log.clone());
+    bus.respond(&[0xEF, 0x40, 0x18]);
+    let bus = BusMutex::<BusRawMutex, _>::new(bus);
+    let mut flash = W25q128jv::new_shared_with_bus_config(&bus, MockCs::new("flash", log.clone()), 50_000_000);
+    let mut display = SpiDeviceWithConfig::new(&bus, MockCs::new("display", log.clone()), 8_000_000);
+    // The display takes the bus while `init` waits tSHSL after its first frame
+    // 显示屏在 `init` 第一帧之后等待 tSHSL 期间占用总线
+    let display = async {
+        while !log.borrow().contains(&BusEvent::Deselect("flash")) {
+            yield_now().await;
+        }
+        display.write(&[0x2C]).await
+    };
+    let (init, display) = block_on(join(flash.init(), display));
+    init.unwrap();
+    display.unwrap();
+
+    // Each frame is set to its device's config just before CS goes low, and no other device
+    // starts inside it / 每帧在 CS 拉低前设置为其设备的配置，且帧内不会有其他设备开始传输
+    let mut frames = Vec::new();
+    let (mut config, mut owner) = (None, None);
+    for event in log.borrow().iter() {
+        match *event {
+            BusEvent::Config(frequency) if owner.is_none() => config = Some(frequency),
+            BusEvent::Select(name) => {
+                assert_eq!(owner.replace(name), None);
+                frames.push((name, config.take()));
+            }
+            BusEvent::Deselect(name) => assert_eq!(owner.take(), Some(name)),
+            ref event => assert!(owner.is_some(), "{event:?} outside a CS frame"),
+        }
+    }
+    assert_eq!(
+        frames,
+        [("flash", Some(50_000_000)), ("display", Some(8_000_000)), ("flash", Some(50_000_000))]
+    );
+}
+
+#[test]
+fn shared_flash_keeps_other_tasks_out_of_a_program() {
+    let mut spi = MockSpi::new();
+    // Protection reads, idle, then busy once after Page Program, then the JEDEC ID
+    // 保护读取、空闲，Page Program 之后忙一次，然后是 JEDEC ID
+    for miso in [&[0x00][..], &[0x00], &[0x00], &[0x00], &[0x01], &[0x00], &[0xEF, 0x40, 0x18]] {
+        spi.respond(miso);
+    }
+    let shared = SharedFlash::<NoopRawMutex, _>::new(W25q128jv::new(&mut spi));
+    // The second task asks while the program sleeps on BUSY / 编程在 BUSY 上休眠时，第二个任务发出请求
+    let (program, id) = block_on(join(shared.program(0x00_0100, &[0xAA, 0xBB]), async {
+        shared.lock().await.read_jedec_id().await
+    }));
+    program.unwrap();
+    assert_eq!(id, Ok((0xEF, 0x40, 0x18)));
+    drop(shared);
+    assert_eq!(
+        spi.take_frames(),
+        [
+            protection_reads(),
+            vec![
+                status(),
+                write_enable(),
+                vec![w(&[0x02, 0x00, 0x01, 0x00]), w(&[0xAA, 0xBB])],
+                status(),
+                status(),
+                vec![w(&[0x9F]), r(3)],
+            ],
+        ]
+        .concat()
+    );
+}
@@ -0,0 +1,723 @@
+// tests/simulated.rs
+
+//! Host tests for `SimulatedW25q128` through the real `W25q128jv` driver.
+//! 通过真实的 `W25q128jv` 驱动对 `SimulatedW25q128` 进行主机测试。
+
+mod common;
+
+use embassy_futures::{block_on, join::join, select::{select, Either}};
+use embassy_sync::blocking_mutex::raw::NoopRawMutex;
+use embassy_time::{Duration, Timer};
+use embedded_hal_async::spi::{Operation, SpiDevice};
+use embedded_storage_async::nor_flash::{NorFlash, ReadNorFlash};
+use w25q128::{
+    AddressBytes, Config, DEVICE_ID, JEDEC_MAN_ID, DriveStrength, DummyClocks, Error, InterfaceMode, FlashGeometry, MemoryMappedSpiDevice, MultiIoRead, OtpLockConfirmation, PowerState, ReadMode, ReadParameters, RecoverySteps, SharedFlash, SimulatedBusError, SimulatedTiming, SimulatedW25q128, StatusRegister1,
+    StatusRegister3, StatusWriteMode, W25q128jv, FLASH_SIZE, PAGE_SIZE, SECTOR_SIZE,
+};
+
+fn flash() -> W25q128jv<SimulatedW25q128> {
+    W25q128jv::new(SimulatedW25q128::with_timing(SimulatedTiming::ZERO))
+}
+
+#[test]
+fn new_device_is_erased_and_identifies() {
+    block_on(async {
+        let mut flash = flash();
+        flash.verify_jedec_id().await.unwrap();
+        let mut buf = [0u8; 64];
+        flash.read_data(0, &mut buf).await.unwrap();
+        assert!(buf.iter().all(|&b| b == 0xFF));
+    });
+}
+
+#[test]
+fn identification_registers() {
+    block_on(async {
+        let mut sim = SimulatedW25q128::with_timing(SimulatedTiming::ZERO);
+        sim.set_unique_id(0x0011_2233_4455_6677);
+        let mut flash = W25q128jv::new(sim);
+        assert_eq!(flash.read_manufacturer_device_id().await.unwrap(), (JEDEC_MAN_ID, DEVICE_ID));
+        assert_eq!(flash.read_unique_id().await.unwrap(), 0x0011_2233_4455_6677);
+    });
+}
+
+#[test]
+fn sfdp_describes_w25q128jv() {
+    block_on(async {
+        let params = flash().read_sfdp_parameters().await.unwrap();
+        assert_eq!(params.revision, (1, 5));
+        assert_eq!(params.capacity, FLASH_SIZE as u64);
+        assert_eq!(params.address_bytes, AddressBytes::Three);
+        assert_eq!(params.sector_erase_opcode, Some(0x20));
+
+        let sizes: Vec<_> = params.erase_types.iter().flatten().map(|erase| (erase.size, erase.opcode)).collect();
+        assert_eq!(sizes, vec![(4096, 0x20), (32768, 0x52), (65536, 0xD8)]);
+        let sector = params.erase_type(SECTOR_SIZE as u32).unwrap();
+        assert_eq!(sector.typical_time, Some(Duration::from_millis(48)));
+        assert_eq!(sector.max_time, Some(Duration::from_millis(384)));
+
+        let quad_io = params.fast_read.read_1_4_4.unwrap();
+        assert_eq!((quad_io.opcode, quad_io.mode_clocks, quad_io.dummy_clocks), (0xEB, 2, 4));
+        assert_eq!(params.fast_read.read_1_1_2.unwrap().opcode, 0x3B);
+        assert_eq!(params.fast_read.read_4_4_4, None);
+
+        let program = params.program.unwrap();
+        assert_eq!(program.page_size, PAGE_SIZE);
+        assert_eq!(program.page_program_typical, Duration::from_micros(448));
+        assert_eq!(program.chip_erase_typical, Duration::from_secs(40));
+    });
+}
+
+#[test]
+fn init_detects_simulated_part() {
+    block_on(async {
+        let w25q64 = FlashGeometry::from_jedec_id(0xEF, 0x40, 0x17).unwrap();
+        let config = Config { verify_sfdp: true, ..Config::default() };
+        let mut flash = W25q128jv::new_with_config(SimulatedW25q128::with_geometry(w25q64, SimulatedTiming::ZERO), config);
+        flash.init().await.unwrap();
+        assert_eq!(*flash.geometry(), w25q64);
+        flash.verify_jedec_id().await.unwrap();
+        assert_eq!(ReadNorFlash::capacity(&flash), 8 * 1024 * 1024);
+        assert_eq!(flash.erase_range(0, 8 * 1024 * 1024).await, Ok(()));
+        assert_eq!(flash.program(0x80_0000, &[0]).await, Err(Error::OutOfBounds));
+    });
+}
+
+#[test]
+fn init_recovers_a_chip_left_busy_or_powered_down() {
+    block_on(async {
+        // MCU reset in the middle of an erase: the erase is left to finish / MCU 在擦除过程中复位：等待擦除完成
+        let timing = SimulatedTiming { sector_erase: Duration::from_millis(50), ..SimulatedTiming::ZERO };
+        let mut flash = W25q128jv::new(SimulatedW25q128::with_timing(timing));
+        flash.program(0x3000, b"x").await.unwrap();
+        flash.start_erase_sector_4k(0x3000).await.unwrap();
+        let mut flash = W25q128jv::new(flash.release().ok().unwrap());
+        let steps = flash.init().await.unwrap();
+        assert_eq!(steps, RecoverySteps { exit_qpi: true, exit_continuous_read: true, release_power_down: true, wait_busy: true, software_reset: false });
+        assert!(!flash.is_busy().await.unwrap());
+        let mut buf = [0u8; 1];
+        flash.read_data(0x3000, &mut buf).await.unwrap();
+        assert_eq!(buf, [0xFF]);
+
+        // MCU reset while the chip sleeps / 芯片睡眠时 MCU 复位
+        flash.deep_power_down().await.unwrap();
+        let mut flash = W25q128jv::new(flash.release().ok().unwrap());
+        let steps = flash.init().await.unwrap();
+        assert_eq!(steps, RecoverySteps { exit_qpi: true, exit_continuous_read: true, release_power_down: true, wait_busy: false, software_reset: false });
+
+        // Nothing to do for a chip in a known state / 处于已知状态的芯片无需任何步骤
+        assert!(!flash.init().await.unwrap().any());
+    });
+}
+
+fn qpi_flash() -> W25q128jv<SimulatedW25q128> {
+    let mut chip = SimulatedW25q128::with_timing(SimulatedTiming::ZERO);
+    chip.set_qpi_capable(true);
+    W25q128jv::new(chip)
+}
+
+#[test]
+fn qpi_mode_runs_the_whole_api() {
+    block_on(async {
+        let mut flash = qpi_flash();
+        flash.init().await.unwrap();
+        flash.enter_qpi().await.unwrap();
+        assert_eq!(flash.interface_mode(), InterfaceMode::Qpi);
+        flash.erase_sector_4k(0x2000).await.unwrap();
+        flash.program(0x2000, b"qpi").await.unwrap();
+        let mut buf = [0u8; 3];
+        flash.read_data(0x2000, &mut buf).await.unwrap();
+        assert_eq!(&buf, b"qpi");
+        flash.set_read_parameters(ReadParameters { dummy_clocks: DummyClocks::Eight, ..Default::default() }).await.unwrap();
+        flash.fast_read(0x2000, &mut buf).await.unwrap();
+        assert_eq!(&buf, b"qpi");
+        flash.read_multi_io(ReadMode::QuadIo, 0x2000, &mut buf).await.unwrap();
+        assert_eq!(&buf, b"qpi");
+        assert_eq!(flash.read_unique_id().await, Err(Error::Unsupported));
+
+        flash.exit_qpi().await.unwrap();
+        flash.read_unique_id().await.unwrap();
+        buf.fill(0);
+        flash.read_data(0x2000, &mut buf).await.unwrap();
+        assert_eq!(&buf, b"qpi");
+        assert!(!flash.release().ok().unwrap().is_qpi());
+    });
+}
+
+#[test]
+fn enter_qpi_is_rejected_by_the_jv() {
+    block_on(async {
+        let mut flash = flash();
+        flash.program(0x100, b"spi").await.unwrap();
+        assert_eq!(flash.enter_qpi().await, Err(Error::Unsupported));
+        assert_eq!(flash.interface_mode(), InterfaceMode::Spi);
+        let mut buf = [0u8; 3];
+        flash.read_data(0x100, &mut buf).await.unwrap();
+        assert_eq!(&buf, b"spi");
+    });
+}
+
+#[test]
+fn init_recovers_a_chip_left_in_qpi() {
+    block_on(async {
+        let mut flash = qpi_flash();
+        flash.enter_qpi().await.unwrap();
+        flash.program(0x40, b"boot").await.unwrap();
+        // A new firmware starts in SPI mode / 新固件以 SPI 模式启动
+        let mut chip = flash.release().ok().unwrap();
+        chip.host_reset();
+        let mut flash = W25q128jv::new(chip);
+        let steps = flash.init().await.unwrap();
+        assert_eq!(steps, RecoverySteps { exit_qpi: true, ..Default::default() });
+        assert_eq!(flash.interface_mode(), InterfaceMode::Spi);
+        let mut buf = [0u8; 4];
+        flash.read_data(0x40, &mut buf).await.unwrap();
+        assert_eq!(&buf, b"boot");
+    });
+}
+
+#[test]
+fn memory_mapped_window_shows_the_array() {
+    block_on(async {
+        let mut flash = flash();
+        flash.program(0x100, b"xip").await.unwrap();
+        let window = flash.enter_memory_mapped().await.unwrap();
+        assert_eq!(&window[0x100..0x103], b"xip");
+        assert_eq!(flash.program(0x200, b"no").await, Err(Error::Unsupported));
+        // The window is still served, so the chip is not handed back / 窗口仍在响应，因此不会交还芯片
+        let mut flash = flash.release().err().unwrap();
+        // The chip leaves continuous read mode, so commands work again / 芯片退出连续读取模式，命令恢复可用
+        unsafe { flash.exit_memory_mapped().await }.unwrap();
+        flash.program(0x200, b"ok").await.unwrap();
+        let mut buf = [0u8; 2];
+        flash.read_data(0x200, &mut buf).await.unwrap();
+        assert_eq!(&buf, b"ok");
+
+        // The window is served by the backend until unmapped / 在取消映射之前，窗口由后端响应
+        let mut sim = SimulatedW25q128::with_timing(SimulatedTiming::ZERO);
+        let read = MultiIoRead { opcode: 0xEB, address: 0, address_bytes: 3, address_lines: 4, mode_bits: Some(0x20), dummy_clocks: 4, data_lines: 4 };
+        sim.map(read, FLASH_SIZE).unwrap();
+        assert_eq!(sim.map(read, FLASH_SIZE), Err(SimulatedBusError::MemoryMapped));
+        assert_eq!(sim.transaction(&mut [Operation::Write(&[0x05])]).await, Err(SimulatedBusError::MemoryMapped));
+        unsafe { sim.unmap() }.unwrap();
+        assert_eq!(sim.map(read, FLASH_SIZE + 1), Err(SimulatedBusError::OutOfBounds));
+
+        // Re-entering reuses the same buffer, refreshed with the current array / 重新进入时复用同一缓冲区，并按当前阵列刷新
+        let again = flash.enter_memory_mapped().await.unwrap();
+        assert_eq!(again.as_ptr(), window.as_ptr());
+        assert_eq!(&again[0x200..0x202], b"ok");
+        unsafe { flash.exit_memory_mapped().await }.unwrap();
+    });
+}
+
+#[test]
+fn large_part_reaches_past_16mb() {
+    block_on(async {
+        let w25q512 = FlashGeometry::from_jedec_id(0xEF, 0x40, 0x20).unwrap();
+        let mut flash = W25q128jv::new(SimulatedW25q128::with_geometry(w25q512, SimulatedTiming::ZERO));
+        flash.init().await.unwrap();
+        let top = 0x03FF_8000;
+        flash.program(top, b"top").await.unwrap();
+        flash.program(0x0100_0000, b"mid").await.unwrap();
+        let mut buf = [0u8; 3];
+        flash.read_data(top, &mut buf).await.unwrap();
+        assert_eq!(&buf, b"top");
+        flash.fast_read(0x0100_0000, &mut buf).await.unwrap();
+        assert_eq!(&buf, b"mid");
+        // Nothing aliased into the low 16MB / 低 16MB 中没有别名写入
+        flash.read_data(top & 0xFF_FFFF, &mut buf).await.unwrap();
+        assert_eq!(buf, [0xFF; 3]);
+
+        // 32KB erase goes through the Extended Address Register / 32KB 擦除通过扩展地址寄存器寻址
+        flash.erase_block_32k(top).await.unwrap();
+        flash.read_data(top, &mut buf).await.unwrap();
+        assert_eq!(buf, [0xFF; 3]);
+        assert_eq!(flash.read_extended_address_register().await.unwrap(), 0x03);
+
+        // Same result in 4-byte address mode / 4 字节地址模式下结果相同
+        flash.enter_4byte_address_mode().await.unwrap();
+        assert!(flash.read_status_register_3().await.unwrap().ads);
+        flash.erase_block_32k(0x0100_0000).await.unwrap();
+        flash.read_data(0x0100_0000, &mut buf).await.unwrap();
+        assert_eq!(buf, [0xFF; 3]);
+        assert_eq!(flash.read_data(0x0400_0000, &mut buf).await, Err(Error::OutOfBounds));
+    });
+}
+
+#[test]
+fn reads_do_not_wait_for_a_background_erase() {
+    block_on(async {
+        let timing = SimulatedTiming { block_erase_64k: Duration::from_millis(300), ..SimulatedTiming::ZERO };
+        let mut flash = W25q128jv::new(SimulatedW25q128::with_timing(timing));
+        flash.program(0x1000, b"log").await.unwrap();
+        flash.program(0x2_0000, b"old").await.unwrap();
+
+        flash.start_erase_block_64k(0x2_0000).await.unwrap();
+        let mut buf = [0u8; 3];
+        flash.read_data(0x1000, &mut buf).await.unwrap();
+        assert_eq!(&buf, b"log");
+        flash.fast_read(0x1000, &mut buf).await.unwrap();
+        assert_eq!(&buf, b"log");
+        // Still erasing, and resumed after each read / 仍在擦除，且每次读取后都已恢复
+        assert_eq!(flash.erase_in_progress(), Some(0x2_0000..0x3_0000));
+        assert!(flash.is_busy().await.unwrap());
+        assert!(!flash.read_status_register_2().await.unwrap().sus);
+
+        // Reading the erased block waits for the erase / 读取正在擦除的块会等待擦除完成
+        flash.read_data(0x2_0000, &mut buf).await.unwrap();
+        assert_eq!(buf, [0xFF; 3]);
+        assert!(!flash.is_busy().await.unwrap());
+        assert_eq!(flash.erase_in_progress(), None);
+    });
+}
+
+#[test]
+fn suspended_erase_resumes_before_other_commands() {
+    block_on(async {
+        let timing = SimulatedTiming { sector_erase: Duration::from_millis(50), ..SimulatedTiming::ZERO };
+        let mut flash = W25q128jv::new(SimulatedW25q128::with_timing(timing));
+        flash.start_erase_sector_4k(0x5000).await.unwrap();
+        assert!(flash.suspend().await.unwrap());
+        assert!(flash.read_status_register_2().await.unwrap().sus);
+        assert!(!flash.is_busy().await.unwrap());
+        // Program waits for the resumed erase to finish / 编程会等待恢复后的擦除完成
+        flash.program(0x6000, &[0x42]).await.unwrap();
+        assert_eq!(flash.erase_in_progress(), None);
+        let mut buf = [0u8; 1];
+        flash.read_data(0x6000, &mut buf).await.unwrap();
+        assert_eq!(buf, [0x42]);
+    });
+}
+
+#[test]
+fn deep_power_down_ignores_commands_until_released() {
+    block_on(async {
+        let mut flash = flash();
+        flash.program(0x100, b"zz").await.unwrap();
+        flash.deep_power_down().await.unwrap();
+
+        // A new driver does not know the chip is asleep / 新的驱动不知道芯片处于睡眠状态
+        let chip = flash.release().ok().unwrap();
+        assert!(chip.is_powered_down());
+        let mut flash = W25q128jv::new(chip);
+        assert_eq!(flash.read_jedec_id().await.unwrap(), (0xFF, 0xFF, 0xFF));
+
+        flash.release_from_power_down().await.unwrap();
+        let mut buf = [0u8; 2];
+        flash.read_data(0x100, &mut buf).await.unwrap();
+        assert_eq!(&buf, b"zz");
+
+        // The tracked state wakes the chip automatically / 跟踪的状态会自动唤醒芯片
+        flash.deep_power_down().await.unwrap();
+        flash.program(0x200, b"ok").await.unwrap();
+        flash.read_data(0x200, &mut buf).await.unwrap();
+        assert_eq!(&buf, b"ok");
+        assert!(!flash.release().ok().unwrap().is_powered_down());
+    });
+}
+
+#[test]
+fn multi_io_reads_return_the_same_data() {
+    block_on(async {
+        let mut flash = flash();
+        flash.program(0x4_0FFE, b"dual/quad").await.unwrap();
+        for mode in [ReadMode::Single, ReadMode::DualOutput, ReadMode::QuadOutput, ReadMode::DualIo, ReadMode::QuadIo] {
+            let mut buf = [0u8; 9];
+            flash.read_multi_io(mode, 0x4_0FFE, &mut buf).await.unwrap();
+            assert_eq!(&buf, b"dual/quad");
+        }
+        assert!(flash.read_status_register_2().await.unwrap().qe);
+
+        // Clearing QE behind the driver's back breaks quad reads / 绕过驱动清除 QE 会使四线读取失效
+        flash.set_quad_enable(false, StatusWriteMode::Volatile).await.unwrap();
+        let mut buf = [0u8; 4];
+        flash.read_multi_io(ReadMode::QuadIo, 0x4_0FFE, &mut buf).await.unwrap();
+        assert_eq!(&buf, b"dual", "the driver sets QE again");
+        assert!(flash.read_status_register_2().await.unwrap().qe);
+
+        let mut flash = W25q128jv::new(SimulatedW25q128::with_geometry(FlashGeometry::from_jedec_id(0xEF, 0x40, 0x20).unwrap(), SimulatedTiming::ZERO));
+        flash.init().await.unwrap();
+        flash.program(0x300_0000, b"high").await.unwrap();
+        flash.read_multi_io(ReadMode::QuadIo, 0x300_0000, &mut buf).await.unwrap();
+        assert_eq!(&buf, b"high");
+    });
+}
+
+#[test]
+fn program_only_clears_bits() {
+    block_on(async {
+        let mut flash = flash();
+        flash.program(0x100, &[0xF0, 0x0F]).await.unwrap();
+        flash.program(0x100, &[0x3C, 0xFF]).await.unwrap();
+        let mut buf = [0u8; 2];
+        flash.fast_read(0x100, &mut buf).await.unwrap();
+        assert_eq!(buf, [0x30, 0x0F]);
+    });
+}
+
+#[test]
+fn erase_restores_ff() {
+    block_on(async {
+        let mut flash = flash();
+        flash.program(0x2000, &[0u8; 300]).await.unwrap();
+        flash.erase_sector_4k(0x2000).await.unwrap();
+        let sim = flash.release().ok().unwrap();
+        assert!(sim.memory()[0x2000..0x2000 + SECTOR_SIZE].iter().all(|&b| b == 0xFF));
+    });
+}
+
+#[test]
+fn program_spans_pages() {
+    block_on(async {
+        let mut flash = flash();
+        let data: Vec<u8> = (0..600).map(|i| i as u8).collect();
+        flash.program(PAGE_SIZE as u32 - 10, &data).await.unwrap();
+        let mut buf = vec![0u8; data.len()];
+        flash.read_data(PAGE_SIZE as u32 - 10, &mut buf).await.unwrap();
+        assert_eq!(buf, data);
+    });
+}
+
+#[test]
+fn raw_page_program_wraps_inside_page() {
+    block_on(async {
+        let mut sim = SimulatedW25q128::with_timing(SimulatedTiming::ZERO);
+        sim.write(&[0x06]).await.unwrap();
+        sim.transaction(&mut [Operation::Write(&[0x02, 0x00, 0x00, 0xFE]), Operation::Write(&[1, 2, 3, 4])])
+            .await
+            .unwrap();
+        assert_eq!(&sim.memory()[0xFE..0x100], &[1, 2]);
+        assert_eq!(&sim.memory()[0x00..0x02], &[3, 4]);
+        assert_eq!(sim.memory()[0x100], 0xFF);
+    });
+}
+
+#[test]
+fn program_without_write_enable_is_ignored() {
+    block_on(async {
+        let mut sim = SimulatedW25q128::with_timing(SimulatedTiming::ZERO);
+        sim.transaction(&mut [Operation::Write(&[0x02, 0x00, 0x00, 0x00]), Operation::Write(&[0x00])])
+            .await
+            .unwrap();
+        assert_eq!(sim.memory()[0], 0xFF);
+    });
+}
+
+#[test]
+fn busy_during_erase() {
+    block_on(async {
+        let mut sim = SimulatedW25q128::new();
+        sim.write(&[0x06]).await.unwrap();
+        sim.write(&[0x20, 0x00, 0x00, 0x00]).await.unwrap();
+        assert!(sim.is_busy());
+
+        let mut flash = W25q128jv::new(sim);
+        assert!(flash.is_busy().await.unwrap());
+        // The driver waits for BUSY to clear before reading / 驱动在读取前等待 BUSY 清除
+        let mut buf = [0u8; 4];
+        flash.read_data(0, &mut buf).await.unwrap();
+        assert!(!flash.is_busy().await.unwrap());
+    });
+}
+
+#[test]
+fn slow_erase_times_out() {
+    block_on(async {
+        let config = Config { sector_erase_timeout: Duration::from_millis(1), ..Config::default() };
+        let mut flash = W25q128jv::new_with_config(SimulatedW25q128::new(), config);
+        assert_eq!(flash.erase_sector_4k(0).await, Err(Error::Timeout));
+        // The erase still completes in the background / 擦除仍会在后台完成
+        flash.set_config(Config::default());
+        let mut buf = [0u8; 1];
+        flash.read_data(0, &mut buf).await.unwrap();
+    });
+}
+
+#[test]
+fn status_registers_are_writable() {
+    block_on(async {
+        let mut flash = flash();
+        assert_eq!(flash.read_status_register_3().await.unwrap().drv, DriveStrength::Percent25);
+
+        let sr1 = StatusRegister1 { bp: 0b011, tb: true, ..Default::default() };
+        flash.write_status_register_1(sr1, StatusWriteMode::NonVolatile).await.unwrap();
+        assert_eq!(flash.read_status_register_1().await.unwrap(), sr1);
+
+        let sr3 = StatusRegister3 { wps: true, drv: DriveStrength::Percent75, ..Default::default() };
+        flash.write_status_register_3(sr3, StatusWriteMode::Volatile).await.unwrap();
+        assert_eq!(flash.read_status_register_3().await.unwrap(), sr3);
+    });
+}
+
+#[test]
+fn security_lock_bits_cannot_be_cleared() {
+    block_on(async {
+        let mut flash = flash();
+        flash.lock_security_register(2, OtpLockConfirmation::i_understand_this_is_permanent()).await.unwrap();
+        // Plain status writes neither clear nor set lock bits / 普通状态写入既不清除也不置位锁定位
+        let mut sr2 = flash.read_status_register_2().await.unwrap();
+        sr2.lb = [true, false, false];
+        flash.write_status_register_2(sr2, StatusWriteMode::NonVolatile).await.unwrap();
+        assert_eq!(flash.read_status_register_2().await.unwrap().lb, [false, true, false]);
+    });
+}
+
+#[test]
+fn status_write_without_enable_is_ignored() {
+    block_on(async {
+        let mut sim = SimulatedW25q128::with_timing(SimulatedTiming::ZERO);
+        sim.write(&[0x01, 0x1C]).await.unwrap();
+        let mut flash = W25q128jv::new(sim);
+        assert_eq!(flash.read_status_register_1().await.unwrap().bp, 0);
+    });
+}
+
+#[test]
+fn protected_region_blocks_program_and_erase() {
+    block_on(async {
+        let mut flash = flash();
+        flash.program(0xFF_F000, &[0x00]).await.unwrap();
+        flash.protect(0xFF_0000..0x100_0000).await.unwrap_err(); // Not in the table / 不在保护表中
+        flash.protect(0xFC_0000..0x100_0000).await.unwrap();
+        assert_eq!(flash.protected_region().await.unwrap().range(flash.geometry()), 0xFC_0000..0x100_0000);
+        assert_eq!(flash.erase_sector_4k(0xFF_F000).await, Err(Error::Protected));
+        assert_eq!(flash.program(0xFB_FFFF, &[0, 0]).await, Err(Error::Protected));
+        flash.program(0xFB_FFFF, &[0]).await.unwrap();
+
+        // The chip itself ignores raw commands to the region too / 芯片本身也会忽略对该区域的原始命令
+        let mut sim = flash.release().ok().unwrap();
+        sim.write(&[0x06]).await.unwrap();
+        sim.write(&[0x20, 0xFF, 0xF0, 0x00]).await.unwrap();
+        assert_eq!(sim.memory()[0xFF_F000], 0x00);
+
+        let mut flash = W25q128jv::new(sim);
+        flash.unprotect_all().await.unwrap();
+        flash.erase_sector_4k(0xFF_F000).await.unwrap();
+        assert_eq!(flash.release().ok().unwrap().memory()[0xFF_F000], 0xFF);
+    });
+}
+
+#[test]
+fn individual_block_locks() {
+    block_on(async {
+        let mut flash = flash();
+        flash.set_individual_block_lock(true, StatusWriteMode::Volatile).await.unwrap();
+        // Everything is locked at power-up / 上电时全部锁定
+        assert_eq!(flash.read_block_locks().await.unwrap().locked_count(), flash.geometry().block_lock_units());
+        assert_eq!(flash.program(0x2_0000, &[0]).await, Err(Error::Protected));
+
+        flash.global_unlock().await.unwrap();
+        flash.lock_block(0x0000).await.unwrap(); // Bottom block locks per sector / 底部块按扇区锁定
+        flash.lock_block(0x5_1234).await.unwrap(); // Middle blocks lock per 64KB / 中间块按 64KB 锁定
+        let locks = flash.read_block_locks().await.unwrap();
+        assert_eq!(locks.locked_count(), 2);
+        assert!(locks.is_locked(0x0FFF) && !locks.is_locked(0x1000));
+        assert!(locks.is_locked(0x5_0000) && locks.is_locked(0x5_FFFF));
+        assert!(flash.read_block_lock(0x5_8000).await.unwrap());
+
+        assert_eq!(flash.erase_sector_4k(0x0000).await, Err(Error::Protected));
+        assert_eq!(flash.erase_range(0x4_0000, 0x2_0000).await, Err(Error::Protected));
+        flash.program(0x1000, &[0x00]).await.unwrap();
+        flash.unlock_block(0x5_0000).await.unwrap();
+        flash.erase_block_64k(0x5_0000).await.unwrap();
+
+        // BP bits are ignored while WPS = 1, so protect refuses to set them / WPS = 1 时 BP 位被忽略，因此 protect 拒绝设置
+        flash.unlock_block(0x0000).await.unwrap();
+        assert_eq!(flash.protect(0..0x100_0000).await, Err(Error::Unsupported));
+        flash.program(0x0000, &[0x00]).await.unwrap();
+    });
+}
+
+#[test]
+fn security_registers_program_erase_and_lock() {
+    block_on(async {
+        let mut flash = flash();
+        flash.program_security_register(1, 0x80, b"calibration").await.unwrap();
+        flash.program_security_register(2, 0, &[0x0F]).await.unwrap();
+        let mut buf = [0u8; 11];
+        flash.read_security_register(1, 0x80, &mut buf).await.unwrap();
+        assert_eq!(&buf, b"calibration");
+        // The main array is untouched / 主阵列不受影响
+        flash.read_data(0x1080, &mut buf).await.unwrap();
+        assert_eq!(buf, [0xFF; 11]);
+
+        flash.erase_security_register(1).await.unwrap();
+        flash.read_security_register(1, 0x80, &mut buf).await.unwrap();
+        assert_eq!(buf, [0xFF; 11]);
+
+        assert!(!flash.is_security_register_locked(2).await.unwrap());
+        flash.lock_security_register(2, OtpLockConfirmation::i_understand_this_is_permanent()).await.unwrap();
+        assert!(flash.is_security_register_locked(2).await.unwrap());
+        assert_eq!(flash.erase_security_register(2).await, Err(Error::Protected));
+        let mut byte = [0u8; 1];
+        flash.read_security_register(2, 0, &mut byte).await.unwrap();
+        assert_eq!(byte, [0x0F]);
+    });
+}
+
+#[test]
+fn out_of_bounds_and_misaligned_requests_fail() {
+    block_on(async {
+        let mut flash = flash();
+        let mut buf = [0u8; 2];
+        assert_eq!(flash.read_data(FLASH_SIZE as u32 - 1, &mut buf).await, Err(Error::OutOfBounds));
+        assert_eq!(flash.write_data(0xFF, &[0, 0]).await, Err(Error::NotAligned));
+        assert_eq!(flash.erase_sector_4k(0x800).await, Err(Error::NotAligned));
+    });
+}
+
+#[test]
+fn nor_flash_traits() {
+    block_on(async {
+        let mut flash = flash();
+        assert_eq!(ReadNorFlash::capacity(&flash), FLASH_SIZE);
+        NorFlash::write(&mut flash, 0x10_0000, b"nor flash").await.unwrap();
+        let mut buf = [0u8; 9];
+        ReadNorFlash::read(&mut flash, 0x10_0000, &mut buf).await.unwrap();
+        assert_eq!(&buf, b"nor flash");
+        NorFlash::erase(&mut flash, 0x10_0000, 0x10_0000 + SECTOR_SIZE as u32).await.unwrap();
+        ReadNorFlash::read(&mut flash, 0x10_0000, &mut buf).await.unwrap();
+        assert_eq!(buf, [0xFF; 9]);
+    });
+}
+
+#[test]
+fn blocking_nor_flash_traits() {
+    use embedded_storage::nor_flash::{NorFlash, ReadNorFlash};
+
+    let mut flash = flash();
+    NorFlash::write(&mut flash, 0x20, &[0xA5; 3]).unwrap();
+    let mut buf = [0u8; 3];
+    ReadNorFlash::read(&mut flash, 0x20, &mut buf).unwrap();
+    assert_eq!(buf, [0xA5; 3]);
+}
+
+#[test]
+fn shared_flash_serves_two_tasks() {
+    let timing = SimulatedTiming {
+        page_program: Duration::from_micros(300),
+        sector_erase: Duration::from_millis(2),
+        ..SimulatedTiming::ZERO
+    };
+    let shared = SharedFlash::<NoopRawMutex, _>::new(W25q128jv::new(SimulatedW25q128::with_timing(timing)));
+    assert_eq!(shared.capacity(), FLASH_SIZE);
+
+    // Two tasks (e.g. a logger and a config store) erase and fill their own sectors concurrently
+    // 两个任务（例如日志任务和配置存储）同时擦除并写入各自的扇区
+    let task = |sector: u32, fill: u8| {
+        let mut flash = &shared;
+        async move {
+            let start = sector * SECTOR_SIZE as u32;
+            flash.erase(start, start + SECTOR_SIZE as u32).await.unwrap();
+            for page in 0..4 {
+                NorFlash::write(&mut flash, start + page * PAGE_SIZE as u32, &[fill; PAGE_SIZE]).await.unwrap();
+            }
+            let mut buf = [0u8; 4 * PAGE_SIZE];
+            flash.read(start, &mut buf).await.unwrap();
+            assert!(buf.iter().all(|&b| b == fill));
+        }
+    };
+    block_on(join(task(0, 0x11), task(1, 0x22)));
+
+    let mut flash = shared.into_inner();
+    let mut buf = [0u8; 2];
+    block_on(flash.read_data(SECTOR_SIZE as u32 - 1, &mut buf)).unwrap();
+    assert_eq!(buf, [0xFF, 0x22]);
+}
+
+#[test]
+fn shared_flash_capacity_follows_init() {
+    block_on(async {
+        let w25q64 = FlashGeometry::from_jedec_id(0xEF, 0x40, 0x17).unwrap();
+        let chip = || W25q128jv::new(SimulatedW25q128::with_geometry(w25q64, SimulatedTiming::ZERO));
+
+        // Wrapped before `init`, e.g. in a `StaticCell` at startup / 在 `init` 之前包装，例如启动时放入 `StaticCell`
+        let shared = SharedFlash::<NoopRawMutex, _>::new(chip());
+        assert_eq!(shared.capacity(), FLASH_SIZE);
+        shared.init().await.unwrap();
+        assert_eq!(ReadNorFlash::capacity(&&shared), 8 * 1024 * 1024);
+
+        // `init` run through `lock` / 通过 `lock` 运行 `init`
+        let shared = SharedFlash::<NoopRawMutex, _>::new(chip());
+        let mut flash = shared.lock().await;
+        flash.init().await.unwrap();
+        // Still the old value while the lock is held / 持有锁期间仍为旧值
+        assert_eq!(shared.capacity(), FLASH_SIZE);
+        drop(flash);
+        assert_eq!(shared.capacity(), 8 * 1024 * 1024);
+    });
+}
+
+#[test]
+fn shared_flash_runs_auto_power_down_from_config() {
+    let config = Config { auto_power_down: Some(Duration::from_millis(20)), ..Config::default() };
+    let shared = SharedFlash::<NoopRawMutex, _>::new(W25q128jv::new_with_config(SimulatedW25q128::with_timing(SimulatedTiming::ZERO), config));
+    let task = async {
+        shared.program(0x100, b"awake").await.unwrap();
+        assert_eq!(shared.lock().await.power_state(), PowerState::Active);
+        Timer::after_millis(60).await;
+        assert_eq!(shared.lock().await.power_state(), PowerState::PowerDown);
+        // The next command wakes it up again / 下一条命令再次唤醒芯片
+        let mut buf = [0u8; 5];
+        shared.fast_read(0x100, &mut buf).await.unwrap();
+        assert_eq!(&buf, b"awake");
+        assert_eq!(shared.lock().await.power_state(), PowerState::Active);
+    };
+    match block_on(select(shared.run_auto_power_down(), task)) {
+        Either::First(result) => panic!("runner stopped: {result:?}"),
+        Either::Second(()) => {}
+    }
+
+    // Switched off, the runner returns instead of polling / 关闭后运行器直接返回，而不是轮询
+    block_on(async {
+        let mut flash = shared.lock().await;
+        let config = Config { auto_power_down: None, ..*flash.config() };
+        flash.set_config(config);
+    });
+    assert_eq!(block_on(shared.run_auto_power_down()), Ok(()));
+}
+
+#[test]
+fn shared_flash_rewrites_one_sector_from_two_tasks() {
+    let timing = SimulatedTiming {
+        page_program: Duration::from_micros(300),
+        sector_erase: Duration::from_millis(2),
+        ..SimulatedTiming::ZERO
+    };
+    let mut sim = SimulatedW25q128::with_timing(timing);
+    sim.memory_mut()[..SECTOR_SIZE].fill(0x00);
+    let shared = SharedFlash::<NoopRawMutex, _>::new(W25q128jv::new(sim));
+
+    // Both tasks set bits in sector 0, so each write erases it; the lock keeps either task from
+    // erasing between the other's read and reprogram
+    // 两个任务都在扇区 0 中置位，因此每次写入都会擦除它；锁保证一个任务不会在另一个任务的读取和重新编程之间擦除
+    let mut buffer = [0u8; SECTOR_SIZE];
+    let first = async { shared.write(0x10, b"logger").await.unwrap() };
+    let second = async { shared.write_with_buffer(0x800, b"config", &mut buffer).await.unwrap() };
+    block_on(join(first, second));
+
+    let sim = shared.into_inner().release().ok().unwrap();
+    assert_eq!(&sim.memory()[0x10..0x16], b"logger");
+    assert_eq!(&sim.memory()[0x800..0x806], b"config");
+    assert!(sim.memory()[..0x10].iter().all(|&b| b == 0x00));
+}
+
+#[test]
+fn write_rewrites_across_sectors_and_keeps_the_rest() {
+    block_on(async {
+        let mut sim = SimulatedW25q128::with_timing(SimulatedTiming::ZERO);
+        sim.memory_mut()[..2 * SECTOR_SIZE].iter_mut().enumerate().for_each(|(i, b)| *b = i as u8);
+        let mut flash = W25q128jv::new(sim);
+
+        // Sets bits in the first sector, only clears them in the second / 在第一个扇区置位，在第二个扇区只清零
+        let start = SECTOR_SIZE as u32 - 2;
+        flash.write(start, &[0xFF, 0xFF, 0x00, 0x00]).await.unwrap();
+        let mut buffer = [0u8; SECTOR_SIZE];
+        flash.write_with_buffer(0x10, b"config", &mut buffer).await.unwrap();
+
+        let mut expected: Vec<u8> = (0..2 * SECTOR_SIZE).map(|i| i as u8).collect();
+        expected[SECTOR_SIZE - 2..SECTOR_SIZE + 2].copy_from_slice(&[0xFF, 0xFF, 0x00, 0x00]);
+        expected[0x10..0x16].copy_from_slice(b"config");
+        assert_eq!(&flash.release().ok().unwrap().memory()[..2 * SECTOR_SIZE], &expected[..]);
+    });
+}